@@ -1,14 +1,23 @@
-use std::any::Any;
+use std::{any::Any, path::PathBuf};
 
 use egui::{
-    emath::{self, Numeric}, epaint::{self}, scroll_area::ScrollSource, text::LayoutJob, vec2, Align2, Color32, CornerRadius, CursorIcon, DragValue, FontId, Frame, Id, InnerResponse, Label, LayerId, Layout, Order, Rect, RichText, ScrollArea, Sense, Shape, Stroke, TextFormat, TextStyle, Ui, UiBuilder
+    emath::{self, Numeric}, epaint::{self}, scroll_area::ScrollSource, text::LayoutJob, vec2, Align2, Color32, CornerRadius, CursorIcon, DragValue, FontId, Frame, Id, InnerResponse, Key, KeyboardShortcut, Label, LayerId, Layout, Modifiers, Order, Rect, RichText, ScrollArea, Sense, Shape, Stroke, TextFormat, TextStyle, Ui, UiBuilder
 };
 use itertools::Itertools;
 
-use crate::{card_system::{Ability, BaseCard, Cooldown, CooldownModifier, DirectionCard, DragableCard, Effect, Keybind, MultiCastModifier, PassiveCard, ProjectileModifier, SignedSimpleCooldownModifier, SimpleCooldownModifier, SimpleProjectileModifierType, SimpleStatusEffectType, StatusEffect, UnsignedSimpleStatusEffectType, VoxelMaterial}, gui::{darken, GuiState, PADDING}, settings_manager::Control, utils::{translate_egui_key_code, translate_egui_pointer_button}};
+use crate::{card_system::{Ability, BaseCard, CardManager, ChargeModifierType, Cooldown, CooldownModifier, Deck, DirectionCard, DragableCard, Effect, Keybind, MultiCastModifier, PassiveCard, ProjectileModifier, SignedSimpleCooldownModifier, SimpleCooldownModifier, SimpleProjectileModifierType, SimpleStatusEffectType, StatusEffect, UnsignedSimpleStatusEffectType, VoxelMaterial}, keybind_editor::draw_keybind_tree, deck_edit::{is_valid_drag, DeckEdit, DeckPath, DeckSlot, DropableType, EditableCard, ModificationType}, deck_picker::{get_deck_name, list_deck_files}, deck_storage::{autosave, load_deck, save_deck_and_dock}, edit_history::EditHistory, gui::{darken, GuiElement, GuiState, PADDING}, settings_manager::{Control, Settings}, trajectory_preview::draw_trajectory_preview, utils::{fuzzy_match_score, translate_egui_key_code, translate_egui_pointer_button}};
 
 const ID_SOURCE: &str = "card_editor";
 
+const SLOT_FILTERS: [DropableType; 6] = [
+    DropableType::BaseProjectile,
+    DropableType::MultiCastBaseCard,
+    DropableType::BaseStatusEffects,
+    DropableType::BaseNone,
+    DropableType::Cooldown,
+    DropableType::Direction,
+];
+
 pub enum EditMode {
     FullEditing,
     Readonly,
@@ -49,6 +58,221 @@ pub enum PaletteState {
     Dock,
 }
 
+fn get_slot_filter_name(slot_filter: &Option<DropableType>) -> &'static str {
+    match slot_filter {
+        None => "Any slot",
+        Some(DropableType::BaseProjectile) => "Valid for projectiles",
+        Some(DropableType::MultiCastBaseCard) => "Valid for multicasts",
+        Some(DropableType::BaseStatusEffects) => "Valid for status effects",
+        Some(DropableType::BaseNone) => "Valid for empty card slots",
+        Some(DropableType::Cooldown) => "Valid for cooldowns",
+        Some(DropableType::Direction) => "Valid for directions",
+        Some(DropableType::Palette) => "Valid for the palette",
+    }
+}
+
+/// The cards of the current palette tab, or the best matches from every palette while searching
+fn get_palette_cards(gui_state: &GuiState) -> Vec<DragableCard> {
+    let is_valid_for_slot = |card: &DragableCard| {
+        gui_state
+            .palette_slot_filter
+            .is_none_or(|slot| is_valid_drag(&card.get_type(), &slot))
+    };
+    let query = gui_state.palette_search.trim();
+    if query.is_empty() {
+        return gui_state
+            .palette_state
+            .get_cards(&gui_state.dock_cards)
+            .into_iter()
+            .filter(is_valid_for_slot)
+            .collect();
+    }
+    PaletteState::ALL
+        .iter()
+        .flat_map(|palette| palette.get_cards(&gui_state.dock_cards))
+        .filter(is_valid_for_slot)
+        .filter_map(|card| {
+            // Name matches always rank above matches that are only in the description
+            let score = fuzzy_match_score(query, &card.get_name())
+                .map(|score| score + 1000)
+                .or_else(|| fuzzy_match_score(query, &card.get_hover_text()))?;
+            Some((score, card))
+        })
+        .sorted_by_key(|(score, _)| std::cmp::Reverse(*score))
+        .map(|(_, card)| card)
+        .collect()
+}
+
+impl PaletteState {
+    pub const ALL: [PaletteState; 9] = [
+        PaletteState::BaseCards,
+        PaletteState::Materials,
+        PaletteState::ProjectileModifiers,
+        PaletteState::AdvancedProjectileModifiers,
+        PaletteState::MultiCastModifiers,
+        PaletteState::StatusEffects,
+        PaletteState::CooldownModifiers,
+        PaletteState::Directions,
+        PaletteState::Dock,
+    ];
+
+    pub fn get_cards(&self, dock_cards: &[DragableCard]) -> Vec<DragableCard> {
+        match self {
+            PaletteState::ProjectileModifiers => vec![
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Gravity,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Health,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Length,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Width,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Height,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Size,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Speed,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
+                    SimpleProjectileModifierType::Lifetime,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::NoEnemyFire),
+                DragableCard::ProjectileModifier(ProjectileModifier::FriendlyFire),
+                DragableCard::ProjectileModifier(ProjectileModifier::LockToOwner(
+                    DirectionCard::None,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::PiercePlayers),
+                DragableCard::ProjectileModifier(ProjectileModifier::Charge(
+                    ChargeModifierType::Speed,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::Charge(
+                    ChargeModifierType::Size,
+                    1,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::Charge(
+                    ChargeModifierType::Damage,
+                    1,
+                )),
+            ],
+            PaletteState::BaseCards => vec![
+                DragableCard::BaseCard(BaseCard::Projectile(vec![])),
+                DragableCard::BaseCard(BaseCard::MultiCast(vec![], vec![])),
+                DragableCard::BaseCard(BaseCard::Trigger(0)),
+                DragableCard::BaseCard(BaseCard::Effect(Effect::Damage(1))),
+                DragableCard::BaseCard(BaseCard::Effect(Effect::Knockback(
+                    1,
+                    DirectionCard::None,
+                ))),
+                DragableCard::BaseCard(BaseCard::Effect(Effect::Cleanse)),
+                DragableCard::BaseCard(BaseCard::Effect(Effect::Teleport)),
+                DragableCard::BaseCard(BaseCard::StatusEffects(1, vec![])),
+            ],
+            PaletteState::AdvancedProjectileModifiers => vec![
+                DragableCard::ProjectileModifier(ProjectileModifier::OnHit(BaseCard::None)),
+                DragableCard::ProjectileModifier(ProjectileModifier::OnHeadshot(
+                    BaseCard::None,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::OnExpiry(
+                    BaseCard::None,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::OnTrigger(
+                    0,
+                    BaseCard::None,
+                )),
+                DragableCard::ProjectileModifier(ProjectileModifier::Trail(
+                    1,
+                    BaseCard::None,
+                )),
+            ],
+            PaletteState::MultiCastModifiers => vec![
+                DragableCard::MultiCastModifier(MultiCastModifier::Spread(1)),
+                DragableCard::MultiCastModifier(MultiCastModifier::Duplication(1)),
+            ],
+            PaletteState::CooldownModifiers => vec![
+                DragableCard::CooldownModifier(CooldownModifier::SimpleCooldownModifier(
+                    SimpleCooldownModifier::AddCharge,
+                    1,
+                )),
+                DragableCard::CooldownModifier(CooldownModifier::SimpleCooldownModifier(
+                    SimpleCooldownModifier::AddCooldown,
+                    1,
+                )),
+                DragableCard::CooldownModifier(
+                    CooldownModifier::SignedSimpleCooldownModifier(
+                        SignedSimpleCooldownModifier::DecreaseCooldown,
+                        1,
+                    ),
+                ),
+                DragableCard::CooldownModifier(CooldownModifier::Reloading),
+            ],
+            PaletteState::StatusEffects => vec![
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::DamageOverTime,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::IncreaseDamageTaken,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::IncreaseGravity(DirectionCard::None),
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::Speed,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::UnsignedSimpleStatusEffect(
+                    UnsignedSimpleStatusEffectType::Overheal,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::Grow,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
+                    SimpleStatusEffectType::IncreaseMaxHealth,
+                    1,
+                )),
+                DragableCard::StatusEffect(StatusEffect::Invincibility),
+                DragableCard::StatusEffect(StatusEffect::Trapped),
+                DragableCard::StatusEffect(StatusEffect::Lockout),
+                DragableCard::StatusEffect(StatusEffect::Stun),
+                DragableCard::StatusEffect(StatusEffect::OnHit(Box::new(BaseCard::None))),
+            ],
+            PaletteState::Materials => vec![
+                DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Grass)),
+                DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Dirt)),
+                DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Stone)),
+                DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Ice)),
+                DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Water)),
+            ],
+            PaletteState::Directions => vec![
+                DragableCard::Direction(DirectionCard::Up),
+                DragableCard::Direction(DirectionCard::Forward),
+                DragableCard::Direction(DirectionCard::Movement),
+            ],
+            PaletteState::Dock => dock_cards.to_vec(),
+        }
+    }
+}
+
 pub fn dnd_drag_source<Payload, R>(
     ui: &mut Ui,
     id: Id,
@@ -102,53 +326,49 @@ where
     }
 }
 
+/// A draggable card that can also be right clicked to open the clipboard menu
+fn card_drag_source<R>(
+    ui: &mut Ui,
+    id: Id,
+    path: Vec<usize>,
+    add_contents: impl FnOnce(&mut Ui) -> R,
+) -> InnerResponse<R> {
+    let response = dnd_drag_source(ui, id, Location { path: path.clone() }, add_contents);
+    record_context_menu_target(ui, &response.response, &path);
+    response
+}
+
 /// What is being dragged.
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct Location {
     path: Vec<usize>,
 }
 
-#[derive(Debug)]
-pub enum DragableType {
-    ProjectileModifier,
-    MultiCastModifier,
-    CooldownModifier,
-    StatusEffect,
-    BaseCard,
-    Direction,
-}
-
-#[derive(Debug)]
-pub enum DropableType {
-    MultiCastBaseCard,
-    BaseNone,
-    BaseProjectile,
-    BaseStatusEffects,
-    Cooldown,
-    Direction,
-    Palette,
-}
-
-#[derive(Debug)]
-pub enum ModificationType {
-    Add,
-    Remove,
-    Other,
+/// The card or slot that was last right clicked
+#[derive(Clone, Debug)]
+struct ContextMenuTarget {
+    pass: u64,
+    path: Vec<usize>,
 }
 
-pub fn is_valid_drag(from: &DragableType, to: &DropableType) -> bool {
-    match (from, to) {
-        (DragableType::ProjectileModifier, DropableType::BaseProjectile) => true,
-        (DragableType::StatusEffect, DropableType::BaseStatusEffects) => true,
-        (DragableType::MultiCastModifier, DropableType::MultiCastBaseCard) => true,
-        (DragableType::BaseCard, DropableType::MultiCastBaseCard) => true,
-        (DragableType::BaseCard, DropableType::BaseNone) => true,
-        (DragableType::CooldownModifier, DropableType::Cooldown) => true,
-        (DragableType::BaseCard, DropableType::Cooldown) => true,
-        (DragableType::Direction, DropableType::Direction) => true,
-        (_, DropableType::Palette) => true,
-        _ => false,
+fn record_context_menu_target(ui: &Ui, response: &egui::Response, path: &[usize]) {
+    if !response.contains_pointer() || !ui.input(|i| i.pointer.secondary_clicked()) {
+        return;
     }
+    let pass = ui.ctx().cumulative_pass_nr();
+    let target_id = Id::new(ID_SOURCE).with("context_menu_target");
+    ui.data_mut(|d| {
+        // Nested cards finish drawing before their parents, so the first target this frame is the innermost one
+        if d.get_temp::<ContextMenuTarget>(target_id)
+            .is_some_and(|target| target.pass == pass)
+        {
+            return;
+        }
+        d.insert_temp(target_id, ContextMenuTarget {
+            pass,
+            path: path.to_vec(),
+        });
+    });
 }
 
 impl DrawableCard for Cooldown {
@@ -206,11 +426,22 @@ impl DrawableCard for Cooldown {
                 for (ability_idx, mut ability) in abilities.iter_mut().enumerate() {
                     path.push(ability_idx);
                     ui.horizontal(|ui| {
-                        draw_keybind(ui, &mut ability);
+                        draw_keybind(
+                            ui,
+                            &mut ability,
+                            edit_mode,
+                            Id::new(ID_SOURCE).with("keybind").with(path.clone()),
+                        );
                         ability
                             .card
                             .draw(ui, path, dnd_path, modify_path, edit_mode);
                     });
+                    let preview_id = Id::new(ID_SOURCE).with("trajectory").with(path.clone());
+                    egui::CollapsingHeader::new("Trajectory")
+                        .id_salt(preview_id)
+                        .show(ui, |ui| {
+                            draw_trajectory_preview(ui, &ability.card, preview_id);
+                        });
                     path.pop();
                 }
                 path.pop();
@@ -239,155 +470,80 @@ impl DrawableCard for Cooldown {
                 path: path.clone(),
             }));
         }
+        record_context_menu_target(ui, &response.response, path);
     }
+}
 
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        self.cooldown_value = None;
-        let type_idx = path.pop().unwrap() as usize;
-        if type_idx == 0 {
-            let idx = path.pop().unwrap() as usize;
-            self.modifiers[idx].modify_from_path(path, modification_type);
-        } else if type_idx == 1 {
-            let idx = path.pop().unwrap() as usize;
-            self.abilities[idx]
-                .card
-                .modify_from_path(path, modification_type);
-            self.abilities[idx].invalidate_cooldown_cache();
-        } else {
-            panic!("Invalid state");
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        self.cooldown_value = None;
-        let type_idx = path.pop().unwrap() as usize;
-        if type_idx == 0 {
-            let idx = path.pop().unwrap() as usize;
-            self.modifiers[idx].take_from_path(path)
-        } else if type_idx == 1 {
-            let idx = path.pop().unwrap() as usize;
-            if path.is_empty() {
-                let ability_card = self.abilities[idx].card.clone();
-                self.abilities[idx].card = BaseCard::None;
-                self.abilities[idx].invalidate_cooldown_cache();
-                DragableCard::BaseCard(ability_card)
-            } else {
-                let result = self.abilities[idx].card.take_from_path(path);
-                self.abilities[idx].invalidate_cooldown_cache();
-                result
+fn draw_keybind(ui: &mut Ui, ability: &mut Ability, edit_mode: &EditMode, id: Id) {
+    ui.vertical(|ui| {
+        ui.add_enabled_ui(edit_mode.can_edit_modifiers(), |ui| {
+            draw_keybind_control(ui, ability);
+            draw_hold_time(ui, ability);
+            draw_keybind_text(ui, ability);
+            if draw_keybind_tree(ui, &mut ability.keybind, id) {
+                ability.keybind_text = None;
             }
-        } else {
-            panic!("Invalid state");
-        }
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        self.cooldown_value = None;
-        if path.is_empty() {
-            if let DragableCard::BaseCard(item) = item {
-                self.abilities.push(Ability {
-                    card: item,
-                    ..Default::default()
-                });
-            } else if let DragableCard::CooldownModifier(modifier_item) = item {
-                let mut combined = false;
-                match modifier_item.clone() {
-                    CooldownModifier::SimpleCooldownModifier(last_type, last_s) => {
-                        for modifier in self.modifiers.iter_mut() {
-                            match modifier {
-                                CooldownModifier::SimpleCooldownModifier(current_type, s)
-                                    if *current_type == last_type =>
-                                {
-                                    *s += last_s;
-                                    combined = true;
-                                    break;
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    CooldownModifier::SignedSimpleCooldownModifier(last_type, last_s) => {
-                        for modifier in self.modifiers.iter_mut() {
-                            match modifier {
-                                CooldownModifier::SignedSimpleCooldownModifier(current_type, s)
-                                    if *current_type == last_type =>
-                                {
-                                    *s += last_s;
-                                    combined = true;
-                                    break;
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+        });
+    });
+}
 
-                if !combined {
-                    self.modifiers.push(modifier_item.clone());
-                }
-            } else {
-                panic!("Invalid state")
-            }
-        } else {
-            assert!(path.pop().unwrap() == 1);
-            let idx = path.pop().unwrap() as usize;
-            self.abilities[idx].card.insert_to_path(path, item);
-            self.abilities[idx].invalidate_cooldown_cache();
+fn draw_keybind_text(ui: &mut Ui, ability: &mut Ability) {
+    let mut text = ability
+        .keybind_text
+        .clone()
+        .unwrap_or_else(|| ability.keybind.to_expression_string());
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut text)
+            .desired_width(120.0)
+            .font(TextStyle::Monospace),
+    );
+    if response.changed() {
+        if let Ok(keybind) = Keybind::parse(&text) {
+            ability.keybind = keybind;
         }
+        ability.keybind_text = Some(text);
     }
-
-    fn cleanup(&mut self, path: &mut Vec<usize>) {
-        if path.is_empty() {
-            return;
-        }
-        let idx_type = path.pop().unwrap();
-        if idx_type == 0 {
-            let idx = path.pop().unwrap() as usize;
-            assert!(path.is_empty());
-            match &mut self.modifiers[idx] {
-                CooldownModifier::None => {
-                    self.modifiers.remove(idx);
-                }
-                CooldownModifier::SimpleCooldownModifier(_, s) => {
-                    if *s == 0 {
-                        self.modifiers.remove(idx);
-                    }
-                }
-                CooldownModifier::SignedSimpleCooldownModifier(_, s) => {
-                    if *s == 0 {
-                        self.modifiers.remove(idx);
-                    }
+    if let Some(text) = &ability.keybind_text {
+        match Keybind::parse(text) {
+            Ok(_) => {
+                if !response.has_focus() {
+                    ability.keybind_text = None;
                 }
-                _ => {}
             }
-        } else if idx_type == 1 {
-            let idx = path.pop().unwrap() as usize;
-            if path.is_empty() {
-                if matches!(self.abilities[idx].card, BaseCard::None) && self.abilities.len() > 1 {
-                    self.abilities.remove(idx);
-                }
-            } else {
-                self.abilities[idx].card.cleanup(path);
-                self.abilities[idx].invalidate_cooldown_cache();
+            Err(err) => {
+                ui.label(RichText::new(err.to_string()).color(Color32::RED));
             }
-        } else {
-            panic!("Invalid state");
         }
     }
 }
 
-fn draw_keybind(ui: &mut Ui, ability: &mut Ability) {
+fn draw_keybind_control(ui: &mut Ui, ability: &mut Ability) {
     let desired_size = ui.spacing().interact_size.y * egui::vec2(3.0, 3.0);
     let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
 
     if ability.is_keybind_selected {
+        let hold_time = match ability.keybind {
+            Keybind::HeldFor(_, seconds) => Some(seconds),
+            _ => None,
+        };
+        let set_control = |control: Control| match hold_time {
+            Some(seconds) => Keybind::HeldFor(control, seconds),
+            None => Keybind::Pressed(control),
+        };
         ui.ctx().input(|input| {
             for key in input.events.iter() {
-                if let egui::Event::Key { key, pressed, .. } = key {
-                    if *pressed {
-                        ability.keybind =
-                            Keybind::Pressed(Control::Key(translate_egui_key_code(*key)));
+                if let egui::Event::Key {
+                    key,
+                    physical_key,
+                    pressed,
+                    ..
+                } = key
+                {
+                    // Keys without a physical key of their own keep waiting for another press
+                    if let Some(key_code) = translate_egui_key_code(physical_key.unwrap_or(*key))
+                        && *pressed
+                    {
+                        ability.keybind = set_control(Control::Key(key_code));
                         ability.is_keybind_selected = false;
                     }
                 }
@@ -396,9 +552,8 @@ fn draw_keybind(ui: &mut Ui, ability: &mut Ability) {
                 } = key
                 {
                     if *pressed {
-                        ability.keybind = Keybind::Pressed(Control::Mouse(
-                            translate_egui_pointer_button(*button),
-                        ));
+                        ability.keybind =
+                            set_control(Control::Mouse(translate_egui_pointer_button(*button)));
                         ability.is_keybind_selected = false;
                     }
                 }
@@ -431,6 +586,34 @@ fn draw_keybind(ui: &mut Ui, ability: &mut Ability) {
     }
 }
 
+fn draw_hold_time(ui: &mut Ui, ability: &mut Ability) {
+    let (control, hold_time) = match &ability.keybind {
+        Keybind::Pressed(control) => (control.clone(), None),
+        Keybind::HeldFor(control, seconds) => (control.clone(), Some(*seconds)),
+        _ => return,
+    };
+    let mut is_held = hold_time.is_some();
+    if ui
+        .checkbox(&mut is_held, "Hold")
+        .on_hover_text("Activate on release after holding for a minimum time")
+        .changed()
+    {
+        ability.keybind = if is_held {
+            Keybind::HeldFor(control, 0.5)
+        } else {
+            Keybind::Pressed(control)
+        };
+    }
+    if let Keybind::HeldFor(_, seconds) = &mut ability.keybind {
+        ui.add(
+            DragValue::new(seconds)
+                .range(0.0..=ProjectileModifier::FULL_CHARGE_TIME)
+                .speed(0.05)
+                .suffix("s"),
+        );
+    }
+}
+
 const CARD_UI_SPACING: f32 = 3.0;
 const CARD_UI_ROUNDING: f32 = 3.0;
 trait DrawableCard {
@@ -442,11 +625,6 @@ trait DrawableCard {
         modify_path: &mut Option<(Vec<usize>, ModificationType)>,
         edit_mode: &EditMode,
     );
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType);
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard;
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard);
-    fn cleanup(&mut self, path: &mut Vec<usize>);
 }
 
 impl DrawableCard for CooldownModifier {
@@ -497,38 +675,6 @@ impl DrawableCard for CooldownModifier {
             ),
         }
     }
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        assert!(path.is_empty());
-        match self {
-            CooldownModifier::SimpleCooldownModifier(_, v) => match modification_type {
-                ModificationType::Add => *v += 1,
-                ModificationType::Remove => {
-                    if *v > 1 {
-                        *v -= 1
-                    }
-                }
-                ModificationType::Other => {}
-            },
-            CooldownModifier::SignedSimpleCooldownModifier(_, v) => match modification_type {
-                ModificationType::Add => *v += 1,
-                ModificationType::Remove => *v -= 1,
-                ModificationType::Other => {}
-            },
-            _ => {}
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        assert!(path.is_empty());
-        let modifier = self.clone();
-        *self = CooldownModifier::None;
-        DragableCard::CooldownModifier(modifier)
-    }
-
-    fn insert_to_path(&mut self, _path: &mut Vec<usize>, _item: DragableCard) {}
-
-    fn cleanup(&mut self, _path: &mut Vec<usize>) {}
 }
 
 impl DrawableCard for PassiveCard {
@@ -546,7 +692,7 @@ impl DrawableCard for PassiveCard {
                 darken(ui.visuals_mut().widgets.inactive.bg_stroke.color, 0.25);
 
             let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-            let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+            let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                 let mut advanced_effects = vec![];
                 ui.horizontal(|ui| {
                     ui.add_space(CARD_UI_SPACING);
@@ -594,91 +740,9 @@ impl DrawableCard for PassiveCard {
                     path: path.clone(),
                 }));
             }
+            record_context_menu_target(ui, &response.response, path);
         });
     }
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        if path.is_empty() {
-        } else {
-            let effect_idx = path.pop().unwrap() as usize;
-            self.passive_effects[effect_idx].modify_from_path(path, modification_type);
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        let Some(effect_idx) = path.pop() else {
-            panic!("Invalid state: path is empty");
-        };
-        if path.is_empty() {
-            let effect = self.passive_effects[effect_idx as usize].clone();
-            self.passive_effects[effect_idx as usize] = StatusEffect::None;
-            return DragableCard::StatusEffect(effect);
-        }
-        self.passive_effects
-            .get_mut(effect_idx as usize)
-            .unwrap()
-            .take_from_path(path)
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        if path.is_empty() {
-            let DragableCard::StatusEffect(item) = item else {
-                panic!("Invalid state")
-            };
-            if let StatusEffect::SimpleStatusEffect(last_ty, last_s) = item.clone() {
-                let mut combined = false;
-                for effect in self.passive_effects.iter_mut() {
-                    match effect {
-                        StatusEffect::SimpleStatusEffect(ty, s) => {
-                            if last_ty == *ty {
-                                *s += last_s;
-                                combined = true;
-                                break;
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                if !combined {
-                    self.passive_effects.push(item.clone());
-                }
-            } else {
-                self.passive_effects.push(item.clone());
-            }
-
-            self.passive_effects.retain(|effect| match effect {
-                StatusEffect::SimpleStatusEffect(_, s) => *s != 0,
-                _ => true,
-            });
-        } else {
-            let idx = path.pop().unwrap() as usize;
-            assert!(path.pop().unwrap() == 0);
-            self.passive_effects[idx].insert_to_path(path, item);
-        }
-    }
-
-    fn cleanup(&mut self, path: &mut Vec<usize>) {
-        if path.len() <= 1 {
-            self.passive_effects.retain(|effect| match effect {
-                StatusEffect::None => false,
-                _ => true,
-            });
-        } else {
-            let idx = path.pop().unwrap() as usize;
-            assert!(path.pop().unwrap() == 0);
-            match &mut self.passive_effects[idx] {
-                StatusEffect::OnHit(card_box) => {
-                    card_box.cleanup(path)
-                }
-                StatusEffect::SimpleStatusEffect(SimpleStatusEffectType::IncreaseGravity(_), _) => {
-                }
-                ref invalid => panic!(
-                    "Invalid state: cannot follow path {} into {:?}",
-                    idx, invalid
-                ),
-            }
-        }
-    }
 }
 
 impl DrawableCard for MultiCastModifier {
@@ -710,42 +774,6 @@ impl DrawableCard for MultiCastModifier {
             }
         }
     }
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        assert!(path.is_empty());
-        match self {
-            MultiCastModifier::None => {}
-            MultiCastModifier::Spread(value) => match modification_type {
-                ModificationType::Add => *value += 1,
-                ModificationType::Remove => {
-                    if *value > 1 {
-                        *value -= 1
-                    }
-                }
-                ModificationType::Other => {}
-            },
-            MultiCastModifier::Duplication(value) => match modification_type {
-                ModificationType::Add => *value += 1,
-                ModificationType::Remove => {
-                    if *value > 1 {
-                        *value -= 1
-                    }
-                }
-                ModificationType::Other => {}
-            },
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        assert!(path.is_empty());
-        let multicast_modifier = self.clone();
-        *self = MultiCastModifier::None;
-        DragableCard::MultiCastModifier(multicast_modifier)
-    }
-
-    fn insert_to_path(&mut self, _path: &mut Vec<usize>, _item: DragableCard) {}
-
-    fn cleanup(&mut self, _path: &mut Vec<usize>) {}
 }
 
 impl DrawableCard for ProjectileModifier {
@@ -773,6 +801,17 @@ impl DrawableCard for ProjectileModifier {
                 path,
                 edit_mode,
             ),
+            ProjectileModifier::Charge(_, v) => draw_modifier(
+                ui,
+                item_id,
+                name,
+                Some(v),
+                hover_text,
+                true,
+                modify_path,
+                path,
+                edit_mode,
+            ),
 
             ProjectileModifier::LockToOwner(direction) => {
                 draw_modifier(
@@ -819,7 +858,7 @@ impl DrawableCard for ProjectileModifier {
                     ProjectileModifier::OnHit(base_card)
                     | ProjectileModifier::OnHeadshot(base_card)
                     | ProjectileModifier::OnExpiry(base_card) => {
-                        dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| {
+                        card_drag_source(ui, item_id, path.clone(), |ui| {
                             draw_modifier(
                                 ui,
                                 item_id,
@@ -844,7 +883,7 @@ impl DrawableCard for ProjectileModifier {
                     }
                     ProjectileModifier::OnTrigger(frequency, base_card)
                     | ProjectileModifier::Trail(frequency, base_card) => {
-                        dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| {
+                        card_drag_source(ui, item_id, path.clone(), |ui| {
                             draw_modifier(
                                 ui,
                                 item_id,
@@ -872,132 +911,6 @@ impl DrawableCard for ProjectileModifier {
             });
         }
     }
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        if path.is_empty() {
-            match self {
-                ProjectileModifier::SimpleModify(_type, value) => match modification_type {
-                    ModificationType::Add => *value += 1,
-                    ModificationType::Remove => *value -= 1,
-                    ModificationType::Other => {}
-                },
-                ProjectileModifier::Trail(frequency, _card) => match modification_type {
-                    ModificationType::Add => *frequency += 1,
-                    ModificationType::Remove => {
-                        if *frequency > 1 {
-                            *frequency -= 1
-                        }
-                    }
-                    ModificationType::Other => {}
-                },
-                ProjectileModifier::OnTrigger(id, _card) => match modification_type {
-                    ModificationType::Add => *id += 1,
-                    ModificationType::Remove => {
-                        if *id > 0 {
-                            *id -= 1
-                        }
-                    }
-                    ModificationType::Other => {}
-                },
-                ProjectileModifier::FriendlyFire
-                | ProjectileModifier::LockToOwner(_)
-                | ProjectileModifier::NoEnemyFire
-                | ProjectileModifier::PiercePlayers
-                | ProjectileModifier::OnHeadshot(_)
-                | ProjectileModifier::OnHit(_)
-                | ProjectileModifier::OnExpiry(_)
-                | ProjectileModifier::WallBounce
-                | ProjectileModifier::None => {}
-            }
-        } else {
-            assert!(path.pop().unwrap() == 0);
-            match self {
-                ProjectileModifier::OnHit(card) => {
-                    card.modify_from_path(path, modification_type)
-                }
-                ProjectileModifier::OnHeadshot(card) => {
-                    card.modify_from_path(path, modification_type)
-                }
-                ProjectileModifier::OnExpiry(card) => {
-                    card.modify_from_path(path, modification_type)
-                }
-                ProjectileModifier::OnTrigger(_, card) => {
-                    card.modify_from_path(path, modification_type)
-                }
-                ProjectileModifier::Trail(_freqency, card) => {
-                    card.modify_from_path(path, modification_type)
-                }
-                _ => panic!("Invalid state"),
-            }
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        if path.is_empty() {
-            let value = self.clone();
-            *self = ProjectileModifier::None;
-            DragableCard::ProjectileModifier(value)
-        } else {
-            assert!(path.pop().unwrap() == 0);
-            if path.is_empty() {
-                if let ProjectileModifier::LockToOwner(direction) = self {
-                    return direction.take_from_path(path);
-                }
-                let card_ref = match self {
-                    ProjectileModifier::OnHit(card)
-                    | ProjectileModifier::OnHeadshot(card)
-                    | ProjectileModifier::OnExpiry(card)
-                    | ProjectileModifier::OnTrigger(_, card)
-                    | ProjectileModifier::Trail(_, card) => card,
-                    invalid_take_modifier => panic!(
-                        "Invalid state: cannot take from {:?}",
-                        invalid_take_modifier
-                    ),
-                };
-                let result = DragableCard::BaseCard(card_ref.clone());
-                *card_ref = BaseCard::None;
-                result
-            } else {
-                match self {
-                    ProjectileModifier::OnHit(card)
-                    | ProjectileModifier::OnHeadshot(card)
-                    | ProjectileModifier::OnExpiry(card)
-                    | ProjectileModifier::OnTrigger(_, card)
-                    | ProjectileModifier::Trail(_, card) => card.take_from_path(path),
-                    _ => panic!("Invalid state"),
-                }
-            }
-        }
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        assert!(path.pop().unwrap() == 0);
-        match self {
-            ProjectileModifier::OnHit(card)
-            | ProjectileModifier::OnHeadshot(card)
-            | ProjectileModifier::OnExpiry(card)
-            | ProjectileModifier::OnTrigger(_, card)
-            | ProjectileModifier::Trail(_, card) => card.insert_to_path(path, item),
-            ProjectileModifier::LockToOwner(direction) => direction.insert_to_path(path, item),
-            _ => panic!("Invalid state"),
-        }
-    }
-
-    fn cleanup(&mut self, path: &mut Vec<usize>) {
-        assert!(path.pop().unwrap() == 0);
-        match self {
-            ProjectileModifier::OnHit(card)
-            | ProjectileModifier::OnHeadshot(card)
-            | ProjectileModifier::OnExpiry(card)
-            | ProjectileModifier::OnTrigger(_, card)
-            | ProjectileModifier::Trail(_, card) => card.cleanup(path),
-            ProjectileModifier::LockToOwner(direction) => direction.cleanup(path),
-            ref invalid => panic!(
-                "Invalid state: cannot follow path {:?} into {:?}",
-                path, invalid
-            ),
-        }
-    }
 }
 
 impl DrawableCard for StatusEffect {
@@ -1018,7 +931,7 @@ impl DrawableCard for StatusEffect {
                 SimpleStatusEffectType::IncreaseGravity(direction),
                 v,
             ) => {
-                dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| {
+                card_drag_source(ui, item_id, path.clone(), |ui| {
                     draw_modifier(
                         ui,
                         item_id,
@@ -1083,7 +996,7 @@ impl DrawableCard for StatusEffect {
                 let hover_text = self.get_hover_text();
                 match self {
                     StatusEffect::OnHit(base_card) => {
-                        dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| {
+                        card_drag_source(ui, item_id, path.clone(), |ui| {
                             draw_label(ui, "On Hit", hover_text.clone(), modify_path, path);
                             path.push(0);
                             base_card.draw(
@@ -1101,80 +1014,6 @@ impl DrawableCard for StatusEffect {
             });
         }
     }
-
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
-        match self {
-            StatusEffect::SimpleStatusEffect(_, stacks) => match modification_type {
-                ModificationType::Add => *stacks += 1,
-                ModificationType::Remove => *stacks -= 1,
-                ModificationType::Other => {}
-            },
-            StatusEffect::UnsignedSimpleStatusEffect(_, stacks) => {
-                match modification_type {
-                    ModificationType::Add => *stacks += 1,
-                    ModificationType::Remove => {
-                        if *stacks > 0 {
-                            *stacks -= 1
-                        }
-                    }
-                    ModificationType::Other => {}
-                }
-            }
-            StatusEffect::None
-            | StatusEffect::Invincibility
-            | StatusEffect::Trapped
-            | StatusEffect::Lockout
-            | StatusEffect::Stun => {}
-            StatusEffect::OnHit(card) => {
-                assert!(path.pop().unwrap() == 0);
-                card.modify_from_path(path, modification_type)
-            }
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        match self {
-            StatusEffect::OnHit(card) => {
-                assert!(path.pop().unwrap() == 0);
-                card.take_from_path(path)
-            }
-            StatusEffect::SimpleStatusEffect(
-                SimpleStatusEffectType::IncreaseGravity(direction),
-                _,
-            ) => {
-                assert!(path.pop().unwrap() == 0);
-                direction.take_from_path(path)
-            }
-            _ => panic!("Invalid state"),
-        }
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        match self {
-            StatusEffect::OnHit(card) => card.insert_to_path(path, item),
-            StatusEffect::SimpleStatusEffect(
-                SimpleStatusEffectType::IncreaseGravity(direction),
-                _,
-            ) => {
-                direction.insert_to_path(path, item);
-            }
-            _ => panic!("Invalid state"),
-        }
-    }
-
-    fn cleanup(&mut self, path: &mut Vec<usize>) {
-        assert!(path.pop().unwrap() == 0);
-        match self {
-            StatusEffect::OnHit(card) => card.cleanup(path),
-            StatusEffect::SimpleStatusEffect(
-                SimpleStatusEffectType::IncreaseGravity(direction),
-                _,
-            ) => {
-                direction.cleanup(path);
-            }
-            _ => {}
-        }
-    }
 }
 
 impl DrawableCard for DirectionCard {
@@ -1188,11 +1027,11 @@ impl DrawableCard for DirectionCard {
     ) {
         let item_id = egui::Id::new(ID_SOURCE).with(path.clone());
         let is_draggable = !matches!(self, DirectionCard::None) && edit_mode.can_drag_modifiers();
-        dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| {
+        card_drag_source(ui, item_id, path.clone(), |ui| {
             ui.visuals_mut().widgets.inactive.bg_stroke = Stroke::new(0.5, Color32::TRANSPARENT);
             ui.visuals_mut().widgets.inactive.bg_fill = Color32::TRANSPARENT;
             let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-            let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+            let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                 let where_to_put_background = ui.painter().add(Shape::Noop);
                 ui.vertical(|ui| {
                     ui.horizontal(|ui| {
@@ -1230,33 +1069,9 @@ impl DrawableCard for DirectionCard {
                     path: path.clone(),
                 }));
             }
+            record_context_menu_target(ui, &response.response, path);
         });
     }
-
-    fn modify_from_path(
-        &mut self,
-        _path: &mut Vec<usize>,
-        _modification_type: ModificationType,
-    ) {
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        assert!(path.is_empty());
-        let taken_direction = self.clone();
-        *self = DirectionCard::None;
-        DragableCard::Direction(taken_direction)
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        assert!(path.is_empty());
-        if let DragableCard::Direction(new_direction) = item {
-            *self = new_direction;
-        } else {
-            panic!("Invalid state");
-        }
-    }
-
-    fn cleanup(&mut self, _path: &mut Vec<usize>) {}
 }
 
 impl DrawableCard for BaseCard {
@@ -1271,7 +1086,7 @@ impl DrawableCard for BaseCard {
         let item_id = egui::Id::new(ID_SOURCE).with(path.clone());
         let is_draggable = !matches!(self, BaseCard::None | BaseCard::Palette(_))
             && edit_mode.can_drag_base_cards();
-        dnd_drag_source::<Location, _>(ui,item_id, Location { path: path.clone() }, |ui| match self {
+        card_drag_source(ui, item_id, path.clone(), |ui| match self {
             BaseCard::Projectile(modifiers) => {
                 ui.vertical(|ui| {
                     ui.visuals_mut().widgets.inactive.bg_stroke = Stroke::new(0.5, Color32::WHITE);
@@ -1279,14 +1094,18 @@ impl DrawableCard for BaseCard {
                         darken(ui.visuals_mut().widgets.inactive.bg_stroke.color, 0.25);
 
                     let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-                    let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+                    let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                         let mut advanced_modifiers = vec![];
                         ui.horizontal(|ui| {
                             ui.add_space(CARD_UI_SPACING);
                             ui.vertical(|ui| {
                                 ui.add_space(CARD_UI_SPACING);
                                 ui.horizontal_wrapped(|ui| {
-                                    ui.add(Label::new("Create Projectile").selectable(false));
+                                    ui.add(Label::new("Create Projectile").selectable(false))
+                                        .on_hover_ui(|ui| {
+                                            // Only built while hovered to avoid cloning the modifiers every frame
+                                            projectile_stats_ui(ui, &BaseCard::Projectile(modifiers.clone()))
+                                        });
                                     for (modifier_idx, modifier) in modifiers.iter_mut().enumerate()
                                     {
                                         if modifier.is_advanced() {
@@ -1327,6 +1146,7 @@ impl DrawableCard for BaseCard {
                             path: path.clone(),
                         }));
                     }
+                    record_context_menu_target(ui, &response.response, path);
                 });
             }
             BaseCard::MultiCast(cards, modifiers) => {
@@ -1335,7 +1155,7 @@ impl DrawableCard for BaseCard {
                     ui.visuals_mut().widgets.inactive.bg_fill =
                         darken(ui.visuals_mut().widgets.inactive.bg_stroke.color, 0.25);
                     let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-                    let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+                    let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                         ui.horizontal(|ui| {
                             ui.add_space(CARD_UI_SPACING);
                             ui.vertical(|ui| {
@@ -1380,6 +1200,7 @@ impl DrawableCard for BaseCard {
                             path: path.clone(),
                         }));
                     }
+                    record_context_menu_target(ui, &response.response, path);
                 });
             }
             BaseCard::CreateMaterial(mat) => {
@@ -1500,7 +1321,7 @@ impl DrawableCard for BaseCard {
                         darken(ui.visuals_mut().widgets.inactive.bg_stroke.color, 0.25);
 
                     let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-                    let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+                    let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                         let mut advanced_effects = vec![];
                         ui.horizontal(|ui| {
                             ui.add_space(CARD_UI_SPACING);
@@ -1557,6 +1378,7 @@ impl DrawableCard for BaseCard {
                             path: path.clone(),
                         }));
                     }
+                    record_context_menu_target(ui, &response.response, path);
                 });
             }
             BaseCard::Trigger(id) => {
@@ -1602,7 +1424,7 @@ impl DrawableCard for BaseCard {
                 ui.visuals_mut().widgets.inactive.bg_fill =
                     darken(ui.visuals_mut().widgets.inactive.bg_stroke.color, 0.25);
                 let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-                let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+                let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                     ui.horizontal(|ui| {
                         ui.add_space(CARD_UI_SPACING);
                         ui.add(Label::new("None").selectable(false));
@@ -1616,12 +1438,13 @@ impl DrawableCard for BaseCard {
                         path: path.clone(),
                     }));
                 }
+                record_context_menu_target(ui, &response.response, path);
             }
             BaseCard::Palette(palette_cards) => {
                 ui.visuals_mut().widgets.inactive.bg_stroke = Stroke::new(1.0, Color32::GRAY);
                 ui.visuals_mut().widgets.inactive.bg_fill = Color32::BLACK;
                 let frame = Frame::default().inner_margin(CARD_UI_SPACING);
-                let (_, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
+                let (response, payload) = ui.dnd_drop_zone::<Location, _>(frame, |ui| {
                     ui.set_min_size(vec2(200.0, 40.0));
                     ui.add_space(CARD_UI_SPACING);
                     ui.horizontal_wrapped(|ui| {
@@ -1646,385 +1469,27 @@ impl DrawableCard for BaseCard {
                         path: path.clone(),
                     }));
                 }
+                record_context_menu_target(ui, &response.response, path);
             }
         });
     }
+}
 
-    fn modify_from_path(&mut self, path: &mut Vec<usize>, modification_type: ModificationType) {
+impl DragableCard {
+    fn draw_draggable(
+        &mut self,
+        ui: &mut Ui,
+        path: &mut Vec<usize>,
+        dnd_path: &mut Option<(Location, Location)>,
+        modify_path: &mut Option<(Vec<usize>, ModificationType)>,
+        edit_mode: &EditMode,
+    ) {
         match self {
-            BaseCard::Projectile(modifiers) => {
-                let idx = path.pop().unwrap() as usize;
-                modifiers[idx].modify_from_path(path, modification_type);
+            DragableCard::BaseCard(card) => {
+                card.draw(ui, path, dnd_path, modify_path, &edit_mode)
             }
-            BaseCard::MultiCast(cards, modifiers) => {
-                let type_idx = path.pop().unwrap() as usize;
-                if type_idx == 0 {
-                    let idx = path.pop().unwrap() as usize;
-                    modifiers[idx].modify_from_path(path, modification_type);
-                } else if type_idx == 1 {
-                    let idx = path.pop().unwrap() as usize;
-                    cards[idx].modify_from_path(path, modification_type)
-                } else {
-                    panic!("Invalid state");
-                }
-            }
-            BaseCard::CreateMaterial(_) => panic!("Invalid state"),
-            BaseCard::StatusEffects(duration, effects) => {
-                if path.is_empty() {
-                    match modification_type {
-                        ModificationType::Add => *duration += 1,
-                        ModificationType::Remove => {
-                            if *duration > 1 {
-                                *duration -= 1
-                            }
-                        }
-                        ModificationType::Other => {}
-                    }
-                } else {
-                    let effect_idx = path.pop().unwrap() as usize;
-                    effects[effect_idx].modify_from_path(path, modification_type);
-                }
-            }
-            BaseCard::Effect(effect) => {
-                assert!(path.is_empty());
-                match effect {
-                    Effect::Damage(damage) => match modification_type {
-                        ModificationType::Add => *damage += 1,
-                        ModificationType::Remove => *damage -= 1,
-                        ModificationType::Other => {}
-                    },
-                    Effect::Knockback(knockback, _) => match modification_type {
-                        ModificationType::Add => *knockback += 1,
-                        ModificationType::Remove => *knockback -= 1,
-                        ModificationType::Other => {}
-                    },
-                    Effect::Cleanse => {}
-                    Effect::Teleport => {}
-                }
-            }
-            BaseCard::Trigger(id) => match modification_type {
-                ModificationType::Add => *id += 1,
-                ModificationType::Remove => {
-                    if *id > 0 {
-                        *id -= 1
-                    }
-                }
-                ModificationType::Other => {}
-            },
-            BaseCard::None => panic!("Invalid state"),
-            BaseCard::Palette(..) => {}
-        }
-    }
-
-    fn take_from_path(&mut self, path: &mut Vec<usize>) -> DragableCard {
-        if path.is_empty() {
-            let result = DragableCard::BaseCard(self.clone());
-            *self = BaseCard::None;
-            return result;
-        }
-        match self {
-            BaseCard::Projectile(modifiers) => {
-                let idx = path.pop().unwrap() as usize;
-                modifiers[idx].take_from_path(path)
-            }
-            BaseCard::MultiCast(cards, modifiers) => {
-                let type_idx = path.pop().unwrap() as usize;
-                if type_idx == 0 {
-                    let idx = path.pop().unwrap() as usize;
-                    modifiers[idx].take_from_path(path)
-                } else if type_idx == 1 {
-                    let idx = path.pop().unwrap() as usize;
-                    if path.is_empty() {
-                        let value = cards[idx].clone();
-                        cards[idx] = BaseCard::None;
-                        DragableCard::BaseCard(value)
-                    } else {
-                        cards[idx].take_from_path(path)
-                    }
-                } else {
-                    panic!("Invalid state");
-                }
-            }
-            BaseCard::StatusEffects(_, effects) => {
-                let Some(effect_idx) = path.pop() else {
-                    panic!("Invalid state: path is empty");
-                };
-                if path.is_empty() {
-                    let effect = effects[effect_idx as usize].clone();
-                    effects[effect_idx as usize] = StatusEffect::None;
-                    return DragableCard::StatusEffect(effect);
-                }
-                effects
-                    .get_mut(effect_idx as usize)
-                    .unwrap()
-                    .take_from_path(path)
-            }
-            BaseCard::Palette(cards) => {
-                let card_idx = path.pop().unwrap() as usize;
-                cards[card_idx].clone()
-            }
-            BaseCard::Effect(Effect::Knockback(_, direction)) => {
-                assert!(path.pop().unwrap() == 0);
-                let value = DragableCard::Direction(direction.clone());
-                *direction = DirectionCard::None;
-                value
-            }
-            invalid_take @ (BaseCard::CreateMaterial(_)
-            | BaseCard::None
-            | BaseCard::Trigger(_)
-            | BaseCard::Effect(_)) => panic!("Invalid state: cannot take from {:?}", invalid_take),
-        }
-    }
-
-    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) {
-        match self {
-            BaseCard::Projectile(modifiers) => {
-                if path.is_empty() {
-                    let DragableCard::ProjectileModifier(item) = item else {
-                        panic!("Invalid state")
-                    };
-                    if let ProjectileModifier::SimpleModify(last_ty, last_s) = item.clone() {
-                        let mut combined = false;
-                        for modifier in modifiers.iter_mut() {
-                            match modifier {
-                                ProjectileModifier::SimpleModify(ty, s) => {
-                                    if last_ty == *ty {
-                                        *s += last_s;
-                                        combined = true;
-                                        break;
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        if !combined {
-                            modifiers.push(item.clone());
-                        }
-                    } else {
-                        modifiers.push(item.clone());
-                    }
-
-                    modifiers.retain(|modifier| match modifier {
-                        ProjectileModifier::SimpleModify(_, s) => *s != 0,
-                        _ => true,
-                    });
-                } else {
-                    let idx = path.pop().unwrap() as usize;
-                    modifiers[idx].insert_to_path(path, item);
-                }
-            }
-            BaseCard::MultiCast(cards, modifiers) => {
-                if path.is_empty() {
-                    if let DragableCard::BaseCard(item) = item {
-                        cards.push(item);
-                    } else if let DragableCard::MultiCastModifier(modifier_item) = item {
-                        let mut combined = false;
-                        match modifier_item.clone() {
-                            MultiCastModifier::None => {}
-                            MultiCastModifier::Duplication(last_s) => {
-                                for modifier in modifiers.iter_mut() {
-                                    match modifier {
-                                        MultiCastModifier::Duplication(s) => {
-                                            *s += last_s;
-                                            combined = true;
-                                            break;
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            MultiCastModifier::Spread(last_s) => {
-                                for modifier in modifiers.iter_mut() {
-                                    match modifier {
-                                        MultiCastModifier::Spread(s) => {
-                                            *s += last_s;
-                                            combined = true;
-                                            break;
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-
-                        if !combined {
-                            modifiers.push(modifier_item.clone());
-                        }
-                    } else {
-                        panic!("Invalid state")
-                    }
-                } else {
-                    assert!(path.pop().unwrap() == 1);
-                    let idx = path.pop().unwrap() as usize;
-                    cards[idx].insert_to_path(path, item)
-                }
-            }
-            BaseCard::StatusEffects(_, effects) => {
-                if path.is_empty() {
-                    let DragableCard::StatusEffect(item) = item else {
-                        panic!("Invalid state")
-                    };
-                    if let StatusEffect::SimpleStatusEffect(last_ty, last_s) = item.clone() {
-                        let mut combined = false;
-                        for effect in effects.iter_mut() {
-                            match effect {
-                                StatusEffect::SimpleStatusEffect(ty, s) => {
-                                    if last_ty == *ty {
-                                        *s += last_s;
-                                        combined = true;
-                                        break;
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        if !combined {
-                            effects.push(item.clone());
-                        }
-                    } else {
-                        effects.push(item.clone());
-                    }
-
-                    effects.retain(|effect| match effect {
-                        StatusEffect::SimpleStatusEffect(_, s) => *s != 0,
-                        _ => true,
-                    });
-                } else {
-                    let idx = path.pop().unwrap() as usize;
-                    assert!(path.pop().unwrap() == 0);
-                    effects[idx].insert_to_path(path, item);
-                }
-            }
-            BaseCard::Effect(Effect::Knockback(_, direction)) => {
-                assert!(path.pop().unwrap() == 0);
-                if let DragableCard::Direction(new_direction) = item {
-                    *direction = new_direction;
-                } else {
-                    panic!("Invalid state")
-                }
-            }
-            BaseCard::None => {
-                assert!(
-                    path.is_empty(),
-                    "Invalid state: should not have nonempty path {:?} when inserting into None",
-                    path
-                );
-                let DragableCard::BaseCard(item) = item else {
-                    panic!("Invalid state")
-                };
-                *self = item;
-            }
-            c => panic!("Invalid state: Could not insert into {:?}", c),
-        }
-    }
-
-    fn cleanup(&mut self, path: &mut Vec<usize>) {
-        match self {
-            BaseCard::Projectile(modifiers) => {
-                if path.len() <= 1 {
-                    modifiers.retain(|modifier| match modifier {
-                        ProjectileModifier::None => false,
-                        _ => true,
-                    });
-                } else {
-                    let idx = path.pop().unwrap() as usize;
-                    modifiers[idx].cleanup(path);
-                }
-            }
-            BaseCard::MultiCast(cards, modifiers) => {
-                if path.is_empty() {
-                    cards.retain(|card| !matches!(card, BaseCard::None));
-                } else {
-                    let idx_type = path.pop().unwrap();
-                    if idx_type == 0 {
-                        let idx = path.pop().unwrap() as usize;
-                        assert!(path.is_empty());
-                        match modifiers[idx] {
-                            MultiCastModifier::None => {
-                                modifiers.remove(idx);
-                            }
-                            MultiCastModifier::Duplication(s) => {
-                                if s == 0 {
-                                    modifiers.remove(idx);
-                                }
-                            }
-                            MultiCastModifier::Spread(s) => {
-                                if s == 0 {
-                                    modifiers.remove(idx);
-                                }
-                            }
-                        }
-                    } else if idx_type == 1 {
-                        let idx = path.pop().unwrap() as usize;
-                        if path.is_empty() {
-                            if matches!(cards[idx], BaseCard::None) {
-                                cards.remove(idx);
-                            }
-                        } else {
-                            cards[idx].cleanup(path);
-                        }
-                    } else {
-                        panic!("Invalid state");
-                    }
-                }
-            }
-            BaseCard::StatusEffects(_, effects) => {
-                if path.len() <= 1 {
-                    effects.retain(|effect| match effect {
-                        StatusEffect::None => false,
-                        _ => true,
-                    });
-                } else {
-                    let idx = path.pop().unwrap() as usize;
-                    assert!(path.pop().unwrap() == 0);
-                    match &mut effects[idx] {
-                        StatusEffect::OnHit(card) => card.cleanup(path),
-                        StatusEffect::SimpleStatusEffect(
-                            SimpleStatusEffectType::IncreaseGravity(_),
-                            _,
-                        ) => {}
-                        ref invalid => panic!(
-                            "Invalid state: cannot follow path {} into {:?}",
-                            idx, invalid
-                        ),
-                    }
-                }
-            }
-            BaseCard::None => {
-                assert!(path.is_empty(), "Invalid state");
-            }
-            BaseCard::Effect(Effect::Knockback(_, _)) => {}
-            c => panic!("Invalid state: Could cleanup {:?}", c),
-        }
-    }
-}
-
-impl DragableCard {
-    pub fn get_type(&self) -> DragableType {
-        match self {
-            DragableCard::BaseCard(_) => DragableType::BaseCard,
-            DragableCard::CooldownModifier(_) => DragableType::CooldownModifier,
-            DragableCard::MultiCastModifier(_) => DragableType::MultiCastModifier,
-            DragableCard::ProjectileModifier(_) => DragableType::ProjectileModifier,
-            DragableCard::StatusEffect(_) => DragableType::StatusEffect,
-            DragableCard::Direction(_) => DragableType::Direction,
-        }
-    }
-
-    fn draw_draggable(
-        &mut self,
-        ui: &mut Ui,
-        path: &mut Vec<usize>,
-        dnd_path: &mut Option<(Location, Location)>,
-        modify_path: &mut Option<(Vec<usize>, ModificationType)>,
-        edit_mode: &EditMode,
-    ) {
-        match self {
-            DragableCard::BaseCard(card) => {
-                card.draw(ui, path, dnd_path, modify_path, &edit_mode)
-            }
-            DragableCard::CooldownModifier(modifier) => {
-                modifier.draw(ui, path, dnd_path, modify_path, edit_mode)
+            DragableCard::CooldownModifier(modifier) => {
+                modifier.draw(ui, path, dnd_path, modify_path, edit_mode)
             }
             DragableCard::MultiCastModifier(modifier) => {
                 modifier.draw(ui, path, dnd_path, modify_path, edit_mode)
@@ -2042,6 +1507,51 @@ impl DragableCard {
     }
 }
 
+/// Tooltip with the compiled stats of a projectile card, registered on a scratch card manager
+fn projectile_stats_ui(ui: &mut Ui, projectile: &BaseCard) {
+    if projectile.has_placeholder() {
+        ui.label("Finish the card to see its stats");
+        return;
+    }
+    let mut card_manager = CardManager::default();
+    let card = card_manager.register_base_card(projectile.clone());
+    let stats = card_manager.get_referenced_proj(card.card_idx);
+    let yes_no = |value: bool| if value { "Yes" } else { "No" }.to_string();
+    egui::Grid::new("projectile_stats")
+        .num_columns(2)
+        .show(ui, |ui| {
+            let mut row = |name: &str, value: String| {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("Damage", stats.damage.to_string());
+            row("Speed", format!("{:.1}", stats.speed));
+            row(
+                "Size",
+                format!("{:.2} x {:.2} x {:.2}", stats.length, stats.width, stats.height),
+            );
+            row("Lifetime", format!("{:.2}s", stats.lifetime));
+            row("Range", format!("{:.1}", stats.get_range()));
+            row("Gravity", format!("{:.1}", stats.gravity));
+            row("Health", format!("{:.1}", stats.health));
+            if !stats.charge.is_empty() {
+                let charged = stats.get_charged(ProjectileModifier::FULL_CHARGE_TIME);
+                row(
+                    "Fully charged",
+                    format!("{} damage, {:.1} speed", charged.damage, charged.speed),
+                );
+            }
+            row("Pierces players", yes_no(stats.pierce_players));
+            row("Bounces off walls", yes_no(stats.wall_bounce));
+            row("On hit", stats.on_hit.len().to_string());
+            row("On headshot", stats.on_headshot.len().to_string());
+            row("Trail", stats.trail.len().to_string());
+            row("On expiry", stats.on_expiry.len().to_string());
+            row("On trigger", stats.on_trigger.len().to_string());
+        });
+}
+
 pub fn draw_label(
     ui: &mut Ui,
     name: &str,
@@ -2148,9 +1658,12 @@ fn draw_modifier<T: std::fmt::Display + Numeric + Copy>(
         if let Some(count) = count {
             ui.vertical(|ui| {
                 if edit_mode.can_edit_modifiers() {
-                    if ui.add(DragValue::new(count).speed(0.1)).changed() {
+                    // The new value goes through a deck edit so it can be undone
+                    let mut value = *count;
+                    if ui.add(DragValue::new(&mut value).speed(0.1)).changed() {
                         if modify_path.is_none() {
-                            *modify_path = Some((path.clone(), ModificationType::Other));
+                            *modify_path =
+                                Some((path.clone(), ModificationType::Set(value.to_f64() as i64)));
                         }
                     }
                 } else {
@@ -2174,7 +1687,7 @@ fn draw_modifier<T: std::fmt::Display + Numeric + Copy>(
     let can_be_dragged = edit_mode.can_drag_modifiers() && handle_drag;
 
     if can_be_dragged {
-        dnd_drag_source::<Location, _>(ui, id, Location { path: path.clone() }, add_contents);
+        card_drag_source(ui, id, path.clone(), add_contents);
     } else {
         ui.scope(add_contents);
     }
@@ -2234,6 +1747,7 @@ fn draw_modifier<T: std::fmt::Display + Numeric + Copy>(
 pub fn card_editor(
     ctx: &egui::Context,
     gui_state: &mut GuiState,
+    settings: &Settings,
     // game: &mut Option<Game>
 ) {
     egui::CentralPanel::default()
@@ -2261,6 +1775,12 @@ pub fn card_editor(
                 ui.add_space(PADDING);
                 ui.horizontal_wrapped(|ui| {
                     ui.label(RichText::new("Card Editor").color(Color32::WHITE));
+                    if gui_state.deck_autosave.is_deck_dirty() {
+                        ui.colored_label(Color32::YELLOW, "Unsaved changes")
+                            .on_hover_text(format!("Changes are saved to {} automatically", settings.card_file));
+                    } else {
+                        ui.colored_label(Color32::GRAY, "Saved");
+                    }
                     // if let Some(game) = game {
                     //     egui::ComboBox::from_label("Decks")
                     //         .selected_text(format!("Player {}", gui_state.render_deck_idx))
@@ -2300,6 +1820,8 @@ pub fn card_editor(
                     //             }
                     //         });
                     // }
+                    ui.toggle_value(&mut gui_state.deck_text_editor.open, "RON View")
+                        .on_hover_text("Edit the deck as text next to the visual editor");
                     if ui.button("Export to Clipboard").clicked() {
                         let export = ron::to_string(&gui_state.render_deck).unwrap();
                         ctx.copy_text(export);
@@ -2329,12 +1851,74 @@ pub fn card_editor(
                         //     }
                         // }
 
+                        if ui.button("Save").on_hover_text("Ctrl+S").clicked() {
+                            save_deck_and_dock(gui_state, settings);
+                        }
+
+                        if ui
+                            .button("Compare")
+                            .on_hover_text("Compare this deck with another one side by side")
+                            .clicked()
+                        {
+                            let other = gui_state
+                                .deck_comparison
+                                .take()
+                                .map(|comparison| {
+                                    let [_, other] = comparison.decks;
+                                    other
+                                })
+                                .unwrap_or_else(|| ComparedDeck::current(&gui_state.render_deck));
+                            gui_state.deck_comparison = Some(DeckComparison {
+                                decks: [ComparedDeck::current(&gui_state.render_deck), other],
+                            });
+                            gui_state.menu_stack.push(GuiElement::DeckCompare);
+                        }
+
                         if ui.button("Clear Dock").clicked() {
                             gui_state.dock_cards = vec![];
+                            gui_state.deck_autosave.mark_dock_changed();
+                        }
+
+                        if ui
+                            .add_enabled(gui_state.deck_history.can_undo(), egui::Button::new("Undo"))
+                            .on_hover_text("Ctrl+Z")
+                            .clicked()
+                        {
+                            undo_deck_edit(gui_state);
+                        }
+                        if ui
+                            .add_enabled(gui_state.deck_history.can_redo(), egui::Button::new("Redo"))
+                            .on_hover_text("Ctrl+Shift+Z")
+                            .clicked()
+                        {
+                            redo_deck_edit(gui_state);
                         }
                     }
                 });
 
+                if matches!(edit_mode, EditMode::FullEditing) && !ctx.wants_keyboard_input() {
+                    // Check redo first since the undo shortcut also matches when shift is held
+                    if ctx.input_mut(|i| {
+                        i.consume_shortcut(&KeyboardShortcut::new(
+                            Modifiers::COMMAND | Modifiers::SHIFT,
+                            Key::Z,
+                        ))
+                    }) {
+                        redo_deck_edit(gui_state);
+                    } else if ctx.input_mut(|i| {
+                        i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z))
+                    }) {
+                        undo_deck_edit(gui_state);
+                    }
+                }
+                if matches!(edit_mode, EditMode::FullEditing)
+                    && ctx.input_mut(|i| {
+                        i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::S))
+                    })
+                {
+                    save_deck_and_dock(gui_state, settings);
+                }
+
                 if matches!(edit_mode, EditMode::FullEditing) {
                     ui.horizontal_wrapped(|ui| {
                         ui.selectable_value(
@@ -2383,152 +1967,35 @@ pub fn card_editor(
                             "Dock",
                         );
                     });
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut gui_state.palette_search)
+                                .hint_text("Search all palettes")
+                                .desired_width(200.0),
+                        );
+                        egui::ComboBox::from_id_salt("palette_slot_filter")
+                            .selected_text(get_slot_filter_name(&gui_state.palette_slot_filter))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut gui_state.palette_slot_filter,
+                                    None,
+                                    get_slot_filter_name(&None),
+                                );
+                                for slot in SLOT_FILTERS {
+                                    ui.selectable_value(
+                                        &mut gui_state.palette_slot_filter,
+                                        Some(slot),
+                                        get_slot_filter_name(&Some(slot)),
+                                    );
+                                }
+                            });
+                    });
                 }
             });
 
             let mut dnd_path = None;
             let mut modify_path = None;
-            let mut palette_card = BaseCard::Palette(match gui_state.palette_state {
-                PaletteState::ProjectileModifiers => vec![
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Gravity,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Health,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Length,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Width,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Height,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Size,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Speed,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::SimpleModify(
-                        SimpleProjectileModifierType::Lifetime,
-                        1,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::NoEnemyFire),
-                    DragableCard::ProjectileModifier(ProjectileModifier::FriendlyFire),
-                    DragableCard::ProjectileModifier(ProjectileModifier::LockToOwner(
-                        DirectionCard::None,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::PiercePlayers),
-                ],
-                PaletteState::BaseCards => vec![
-                    DragableCard::BaseCard(BaseCard::Projectile(vec![])),
-                    DragableCard::BaseCard(BaseCard::MultiCast(vec![], vec![])),
-                    DragableCard::BaseCard(BaseCard::Trigger(0)),
-                    DragableCard::BaseCard(BaseCard::Effect(Effect::Damage(1))),
-                    DragableCard::BaseCard(BaseCard::Effect(Effect::Knockback(
-                        1,
-                        DirectionCard::None,
-                    ))),
-                    DragableCard::BaseCard(BaseCard::Effect(Effect::Cleanse)),
-                    DragableCard::BaseCard(BaseCard::Effect(Effect::Teleport)),
-                    DragableCard::BaseCard(BaseCard::StatusEffects(1, vec![])),
-                ],
-                PaletteState::AdvancedProjectileModifiers => vec![
-                    DragableCard::ProjectileModifier(ProjectileModifier::OnHit(BaseCard::None)),
-                    DragableCard::ProjectileModifier(ProjectileModifier::OnHeadshot(
-                        BaseCard::None,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::OnExpiry(
-                        BaseCard::None,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::OnTrigger(
-                        0,
-                        BaseCard::None,
-                    )),
-                    DragableCard::ProjectileModifier(ProjectileModifier::Trail(
-                        1,
-                        BaseCard::None,
-                    )),
-                ],
-                PaletteState::MultiCastModifiers => vec![
-                    DragableCard::MultiCastModifier(MultiCastModifier::Spread(1)),
-                    DragableCard::MultiCastModifier(MultiCastModifier::Duplication(1)),
-                ],
-                PaletteState::CooldownModifiers => vec![
-                    DragableCard::CooldownModifier(CooldownModifier::SimpleCooldownModifier(
-                        SimpleCooldownModifier::AddCharge,
-                        1,
-                    )),
-                    DragableCard::CooldownModifier(CooldownModifier::SimpleCooldownModifier(
-                        SimpleCooldownModifier::AddCooldown,
-                        1,
-                    )),
-                    DragableCard::CooldownModifier(
-                        CooldownModifier::SignedSimpleCooldownModifier(
-                            SignedSimpleCooldownModifier::DecreaseCooldown,
-                            1,
-                        ),
-                    ),
-                    DragableCard::CooldownModifier(CooldownModifier::Reloading),
-                ],
-                PaletteState::StatusEffects => vec![
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::DamageOverTime,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::IncreaseDamageTaken,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::IncreaseGravity(DirectionCard::None),
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::Speed,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::UnsignedSimpleStatusEffect(
-                        UnsignedSimpleStatusEffectType::Overheal,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::Grow,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::SimpleStatusEffect(
-                        SimpleStatusEffectType::IncreaseMaxHealth,
-                        1,
-                    )),
-                    DragableCard::StatusEffect(StatusEffect::Invincibility),
-                    DragableCard::StatusEffect(StatusEffect::Trapped),
-                    DragableCard::StatusEffect(StatusEffect::Lockout),
-                    DragableCard::StatusEffect(StatusEffect::Stun),
-                    DragableCard::StatusEffect(StatusEffect::OnHit(Box::new(BaseCard::None))),
-                ],
-                PaletteState::Materials => vec![
-                    DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Grass)),
-                    DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Dirt)),
-                    DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Stone)),
-                    DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Ice)),
-                    DragableCard::BaseCard(BaseCard::CreateMaterial(VoxelMaterial::Water)),
-                ],
-                PaletteState::Directions => vec![
-                    DragableCard::Direction(DirectionCard::Up),
-                    DragableCard::Direction(DirectionCard::Forward),
-                    DragableCard::Direction(DirectionCard::Movement),
-                ],
-                PaletteState::Dock => gui_state.dock_cards.clone(),
-            });
+            let mut palette_card = BaseCard::Palette(get_palette_cards(gui_state));
 
             if matches!(edit_mode, EditMode::FullEditing) {
                 ui.scope(|ui| {
@@ -2543,6 +2010,35 @@ pub fn card_editor(
                 });
             }
             ui.separator();
+            if gui_state.deck_text_editor.open {
+                egui::SidePanel::right("deck_text_editor")
+                    .resizable(true)
+                    .default_width(ui.available_width() / 2.0)
+                    .frame(Frame::new().inner_margin(egui::Margin {
+                        left: PADDING as i8,
+                        ..Default::default()
+                    }))
+                    .show_inside(ui, |ui| {
+                        if let Some(deck) = gui_state.deck_text_editor.show(
+                            ui,
+                            &gui_state.render_deck,
+                            gui_state.deck_autosave.get_deck_generation(),
+                            matches!(edit_mode, EditMode::FullEditing),
+                        ) {
+                            // Typing is merged into one undo step until something else is edited
+                            let edit = DeckEdit::SetDeck {
+                                deck: Box::new(deck),
+                            };
+                            match gui_state.apply_deck_edit(edit, Some(vec![])) {
+                                Ok(_) => gui_state
+                                    .deck_text_editor
+                                    .mark_synced(gui_state.deck_autosave.get_deck_generation()),
+                                Err(e) => gui_state.errors.push(e),
+                            }
+                            invalidate_deck_caches(&mut gui_state.render_deck);
+                        }
+                    });
+            }
             ScrollArea::vertical()
                 .auto_shrink([false, false])
                 .scroll_bar_visibility(
@@ -2551,6 +2047,7 @@ pub fn card_editor(
                 .scroll_source(ScrollSource::ALL)
                 .show(ui, |ui| {
                     ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+                        let mut keybind_edits = vec![];
                         let total_impact = gui_state.render_deck.get_total_impact();
 
                         ui.horizontal_top(|ui| {
@@ -2570,6 +2067,11 @@ pub fn card_editor(
                                 cooldown.cooldown_value =
                                     Some(cooldown.get_cooldown_recovery(total_impact));
                             }
+                            let keybinds_before = cooldown
+                                .abilities
+                                .iter()
+                                .map(|ability| ability.keybind.clone())
+                                .collect_vec();
                             ui.horizontal_top(|ui| {
                                 cooldown.draw(
                                     ui,
@@ -2579,6 +2081,29 @@ pub fn card_editor(
                                     &edit_mode,
                                 );
                             });
+                            // Keybind widgets edit in place, the change is put back so it can be applied as an undoable edit
+                            for ((idx, ability), before) in
+                                cooldown.abilities.iter_mut().enumerate().zip(keybinds_before)
+                            {
+                                if ability.keybind != before {
+                                    keybind_edits.push(DeckEdit::SetKeybind {
+                                        cooldown: ability_idx,
+                                        ability: idx,
+                                        keybind: std::mem::replace(&mut ability.keybind, before),
+                                    });
+                                }
+                            }
+                        }
+
+                        for edit in keybind_edits {
+                            let DeckEdit::SetKeybind { cooldown, ability, .. } = edit else {
+                                unreachable!()
+                            };
+                            // Typing into the same keybind is merged into one undo step
+                            let coalesce_key = vec![cooldown + 2, usize::MAX, ability];
+                            if let Err(e) = gui_state.apply_deck_edit(edit, Some(coalesce_key)) {
+                                gui_state.errors.push(e);
+                            }
                         }
 
                         if matches!(edit_mode, EditMode::FullEditing) {
@@ -2587,91 +2112,541 @@ pub fn card_editor(
                                 .on_hover_text("Add a new cooldown")
                                 .clicked()
                             {
-                                gui_state.render_deck.cooldowns.push(Cooldown::empty());
+                                let edit = DeckEdit::InsertCooldown {
+                                    idx: gui_state.render_deck.cooldowns.len(),
+                                    cooldown: Cooldown::empty(),
+                                };
+                                if let Err(e) = gui_state.apply_deck_edit(edit, None) {
+                                    gui_state.errors.push(e);
+                                }
                             }
                         }
 
-                        if let Some((mut modify_path, modification_type)) = modify_path {
-                            for cooldown in gui_state.render_deck.cooldowns.iter_mut() {
-                                cooldown.cooldown_value = None;
-                            }
-                            let modify_action_idx = modify_path.pop().unwrap() as usize;
-                            if modify_action_idx == 1 {
-                                gui_state.render_deck.passive.modify_from_path(
-                                    &mut modify_path.clone(),
-                                    modification_type,
-                                );
-                            } else if modify_path.is_empty() {
-                                if matches!(modification_type, ModificationType::Remove) {
-                                    gui_state
-                                        .render_deck
-                                        .cooldowns
-                                        .remove(modify_action_idx - 2);
+                        if let Some((modify_path, modification_type)) = modify_path {
+                            if let Some(at) = DeckPath::from_editor_path(&modify_path) {
+                                // Repeated changes to the same modifier are merged, removing a whole cooldown is not
+                                let coalesce_key = (modify_path.len() > 1).then(|| modify_path.clone());
+                                let edit = match modification_type {
+                                    ModificationType::Remove
+                                        if at.path.is_empty()
+                                            && matches!(at.slot, DeckSlot::Cooldown(_)) =>
+                                    {
+                                        Some(DeckEdit::Remove { from: at })
+                                    }
+                                    _ if at.path.is_empty() => None,
+                                    ModificationType::Add => Some(DeckEdit::Increment { at }),
+                                    ModificationType::Remove => Some(DeckEdit::Decrement { at }),
+                                    ModificationType::Set(value) => Some(DeckEdit::SetValue { at, value }),
+                                };
+                                if let Some(edit) = edit {
+                                    if let Err(e) = gui_state.apply_deck_edit(edit, coalesce_key) {
+                                        gui_state.errors.push(e);
+                                    }
+                                    invalidate_deck_caches(&mut gui_state.render_deck);
                                 }
-                            } else if modify_action_idx > 1 {
-                                gui_state.render_deck.cooldowns[modify_action_idx - 2]
-                                    .modify_from_path(
-                                        &mut modify_path.clone(),
-                                        modification_type,
-                                    );
                             }
                         }
-                        if let Some((Location { path: source_path }, Location { path: drop_path })) = dnd_path.as_mut() {
-                            source_path.reverse();
-                            drop_path.reverse();
-
-                            let source_type = &DragableType::ProjectileModifier;
-                            let drop_type = &DropableType::BaseProjectile;
-
-
-                            for cooldown in gui_state.render_deck.cooldowns.iter_mut() {
-                                cooldown.cooldown_value = None;
-                            }
-                            let source_action_idx =
-                                source_path.pop().unwrap() as usize;
-                            let drop_action_idx = drop_path.pop().unwrap() as usize;
-                            if ui.input(|i| i.pointer.any_released())
-                                && (is_valid_drag(source_type, drop_type)
-                                    || drop_action_idx == 0)
-                            {
-                                // do the drop:
-                                let item = if source_action_idx == 0 {
-                                    palette_card.take_from_path(source_path)
-                                } else if source_action_idx == 1 {
-                                    gui_state
-                                        .render_deck
-                                        .passive
-                                        .take_from_path(&mut source_path.clone())
-                                } else {
-                                    gui_state.render_deck.cooldowns[source_action_idx - 2]
-                                        .take_from_path(&mut source_path.clone())
-                                };
-                                if drop_action_idx == 1 {
-                                    gui_state
-                                        .render_deck
-                                        .passive
-                                        .insert_to_path(drop_path, item);
-                                } else if drop_action_idx > 1 {
-                                    gui_state.render_deck.cooldowns[drop_action_idx - 2]
-                                        .insert_to_path(drop_path, item);
-                                } else if matches!(
-                                    gui_state.palette_state,
-                                    PaletteState::Dock
+                        if let Some((Location { path: source_path }, Location { path: drop_path })) = dnd_path {
+                            if ui.input(|i| i.pointer.any_released()) {
+                                let result = match (
+                                    DeckPath::from_editor_path(&source_path),
+                                    DeckPath::from_editor_path(&drop_path),
                                 ) {
-                                    gui_state.dock_cards.push(item);
-                                }
-                                if source_action_idx == 1 {
-                                    gui_state.render_deck.passive.cleanup(source_path);
-                                } else if source_action_idx > 1 {
-                                    gui_state.render_deck.cooldowns[source_action_idx - 2]
-                                        .cleanup(source_path);
+                                    (Some(from), Some(to)) => {
+                                        gui_state.apply_deck_edit(DeckEdit::Move { from, to }, None)
+                                    }
+                                    (None, Some(to)) => {
+                                        let mut palette_path =
+                                            source_path[1..].iter().rev().copied().collect_vec();
+                                        palette_card.take_from_path(&mut palette_path).and_then(|card| {
+                                            gui_state.apply_deck_edit(DeckEdit::Insert { to, card }, None)
+                                        })
+                                    }
+                                    (Some(from), None) => {
+                                        gui_state.apply_deck_edit(DeckEdit::Remove { from }, None)
+                                    }
+                                    (None, None) => {
+                                        let mut palette_path =
+                                            source_path[1..].iter().rev().copied().collect_vec();
+                                        palette_card.take_from_path(&mut palette_path).map(Some)
+                                    }
+                                };
+                                match result {
+                                    Ok(removed) => {
+                                        invalidate_deck_caches(&mut gui_state.render_deck);
+                                        // Cards dropped on the palette are kept when it is showing the dock
+                                        if let Some(card) = removed {
+                                            if matches!(gui_state.palette_state, PaletteState::Dock) {
+                                                gui_state.dock_cards.push(card);
+                                                gui_state.deck_autosave.mark_dock_changed();
+                                            }
+                                        }
+                                    }
+                                    Err(e) => gui_state.errors.push(e),
                                 }
                             }
                         }
+
+                        if matches!(edit_mode, EditMode::FullEditing) {
+                            card_context_menu(ui, gui_state, &palette_card);
+                        }
                     });
                 });
             if !matches!(edit_mode, EditMode::Readonly) {
                 gui_state.gui_deck = gui_state.render_deck.clone();
             }
+            if matches!(edit_mode, EditMode::FullEditing) {
+                autosave(gui_state, settings, ctx.input(|i| i.time));
+            }
+        });
+}
+
+fn invalidate_deck_caches(deck: &mut Deck) {
+    for cooldown in deck.cooldowns.iter_mut() {
+        cooldown.cooldown_value = None;
+        for ability in cooldown.abilities.iter_mut() {
+            ability.invalidate_cooldown_cache();
+        }
+    }
+}
+
+fn undo_deck_edit(gui_state: &mut GuiState) {
+    if let Some(edits) = gui_state.deck_history.undo() {
+        apply_history_edits(gui_state, edits);
+    }
+}
+
+fn redo_deck_edit(gui_state: &mut GuiState) {
+    if let Some(edits) = gui_state.deck_history.redo() {
+        apply_history_edits(gui_state, edits);
+    }
+}
+
+fn apply_history_edits(gui_state: &mut GuiState, edits: Vec<DeckEdit>) {
+    gui_state.deck_autosave.mark_deck_changed();
+    for edit in edits {
+        if let Err(e) = gui_state.render_deck.apply_edit(edit) {
+            // The rest of the history no longer matches the deck
+            gui_state
+                .errors
+                .push(format!("Failed to apply edit history: {}", e));
+            gui_state.deck_history = EditHistory::default();
+            break;
+        }
+    }
+    invalidate_deck_caches(&mut gui_state.render_deck);
+}
+
+fn get_editor_card(
+    gui_state: &GuiState,
+    palette_card: &BaseCard,
+    editor_path: &[usize],
+) -> Option<DragableCard> {
+    let card = match DeckPath::from_editor_path(editor_path) {
+        Some(path) => gui_state.render_deck.get_card(&path),
+        None => match (palette_card, editor_path) {
+            (BaseCard::Palette(cards), [0, card_idx]) => cards.get(*card_idx).cloned(),
+            _ => None,
+        },
+    };
+    card.filter(|card| !card.has_placeholder())
+}
+
+fn card_to_ron(card: &DragableCard) -> Result<String, String> {
+    match card {
+        DragableCard::BaseCard(card) => ron::to_string(card),
+        card => ron::to_string(card),
+    }
+    .map_err(|e| format!("Failed to copy card: {}", e))
+}
+
+/// Accepts both a plain base card as produced by `BaseCard::to_string` and any other card wrapped in its `DragableCard` variant
+fn card_from_ron(text: &str) -> Result<DragableCard, String> {
+    let text = text.trim();
+    ron::from_str::<DragableCard>(text).or_else(|dragable_error| {
+        ron::from_str::<BaseCard>(text)
+            .map(DragableCard::BaseCard)
+            .map_err(|base_error| {
+                format!(
+                    "Failed to parse clipboard as a card: {} (as a base card: {})",
+                    dragable_error, base_error
+                )
+            })
+    })
+}
+
+/// Right click menu for copying cards as RON, pasting them into a slot and filtering the palette by slot
+fn card_context_menu(ui: &mut Ui, gui_state: &mut GuiState, palette_card: &BaseCard) {
+    let target_id = Id::new(ID_SOURCE).with("context_menu_target");
+    let paste_target_id = Id::new(ID_SOURCE).with("paste_target");
+
+    if let Some(target) = ui.data(|d| d.get_temp::<ContextMenuTarget>(target_id)) {
+        let just_opened = target.pass == ui.ctx().cumulative_pass_nr();
+        let card = get_editor_card(gui_state, palette_card, &target.path);
+        let can_paste = DeckPath::from_editor_path(&target.path).is_some()
+            || matches!(gui_state.palette_state, PaletteState::Dock);
+        let drop_type = DeckPath::from_editor_path(&target.path)
+            .and_then(|path| gui_state.render_deck.get_drop_type(&path));
+        egui::Popup::new(
+            Id::new(ID_SOURCE).with("context_menu").with(&target.path),
+            ui.ctx().clone(),
+            egui::PopupAnchor::PointerFixed,
+            ui.layer_id(),
+        )
+        .kind(egui::PopupKind::Menu)
+        .open_memory(just_opened.then_some(egui::SetOpenCommand::Bool(true)))
+        .show(|ui| {
+            if ui
+                .add_enabled(card.is_some(), egui::Button::new("Copy as RON"))
+                .clicked()
+            {
+                if let Some(card) = &card {
+                    match card_to_ron(card) {
+                        Ok(text) => ui.ctx().copy_text(text),
+                        Err(e) => gui_state.errors.push(e),
+                    }
+                }
+            }
+            if ui
+                .add_enabled(can_paste, egui::Button::new("Paste"))
+                .clicked()
+            {
+                // The clipboard can only be read by the integration, the text arrives as a paste event
+                let paste_target = ContextMenuTarget {
+                    pass: ui.ctx().cumulative_pass_nr(),
+                    path: target.path.clone(),
+                };
+                ui.data_mut(|d| d.insert_temp(paste_target_id, paste_target));
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+            }
+            if let Some(drop_type) = drop_type {
+                if ui.button("Find cards for this slot").clicked() {
+                    gui_state.palette_slot_filter = Some(drop_type);
+                }
+            }
+        });
+    }
+
+    let Some(paste_target) = ui.data(|d| d.get_temp::<ContextMenuTarget>(paste_target_id)) else {
+        return;
+    };
+    let Some(text) = ui.input(|i| {
+        i.events.iter().find_map(|event| match event {
+            egui::Event::Paste(text) => Some(text.clone()),
+            _ => None,
+        })
+    }) else {
+        // The paste event comes the frame after the request, no event by then means the clipboard had no text
+        if ui.ctx().cumulative_pass_nr() > paste_target.pass + 1 {
+            ui.data_mut(|d| d.remove::<ContextMenuTarget>(paste_target_id));
+        }
+        return;
+    };
+    ui.data_mut(|d| d.remove::<ContextMenuTarget>(paste_target_id));
+    let paste_path = paste_target.path;
+
+    let result = card_from_ron(&text).and_then(|card| match DeckPath::from_editor_path(&paste_path) {
+        Some(to) => gui_state
+            .apply_deck_edit(DeckEdit::Insert { to, card }, None)
+            .map(|_| invalidate_deck_caches(&mut gui_state.render_deck)),
+        None if card.has_placeholder() => Err(format!("Cannot paste incomplete card {:?}", card)),
+        None => {
+            gui_state.dock_cards.push(card);
+            gui_state.deck_autosave.mark_dock_changed();
+            Ok(())
+        }
+    });
+    if let Err(e) = result {
+        gui_state.errors.push(e);
+    }
+}
+
+/// One side of a deck comparison, `source` is `None` for the deck being edited.
+/// Decks can come from the editor or the deck folder, matches do not keep the decks of other players to compare against.
+pub struct ComparedDeck {
+    pub name: String,
+    pub source: Option<PathBuf>,
+    pub deck: Deck,
+    /// Compared decks are read only, so the stats are only computed once
+    stats: DeckStats,
+}
+
+impl ComparedDeck {
+    pub fn new(name: String, source: Option<PathBuf>, deck: Deck) -> Self {
+        ComparedDeck {
+            name,
+            source,
+            stats: get_deck_stats(&deck),
+            deck,
+        }
+    }
+
+    pub fn current(deck: &Deck) -> Self {
+        ComparedDeck::new("Current deck".to_string(), None, deck.clone())
+    }
+}
+
+pub struct DeckComparison {
+    pub decks: [ComparedDeck; 2],
+}
+
+struct CooldownStats {
+    cooldown: f32,
+    recoveries: Vec<f32>,
+    impact_multiplier: f32,
+}
+
+struct DeckStats {
+    total_impact: f32,
+    cooldowns: Vec<CooldownStats>,
+}
+
+const COMPARE_TOLERANCE: f32 = 0.005;
+
+fn get_deck_stats(deck: &Deck) -> DeckStats {
+    let total_impact = deck.get_total_impact();
+    DeckStats {
+        total_impact,
+        cooldowns: deck
+            .cooldowns
+            .iter()
+            .map(|cooldown| {
+                let (cooldown_time, recoveries) = if cooldown.abilities.is_empty() {
+                    (0.0, vec![])
+                } else {
+                    cooldown.get_cooldown_recovery(total_impact)
+                };
+                CooldownStats {
+                    cooldown: cooldown_time,
+                    recoveries,
+                    impact_multiplier: cooldown.get_impact_multiplier(),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn is_different(value: f32, other: Option<f32>) -> bool {
+    other.is_none_or(|other| (value - other).abs() > COMPARE_TOLERANCE)
+}
+
+fn is_cooldown_different(stats: &CooldownStats, other: Option<&CooldownStats>) -> bool {
+    let Some(other) = other else {
+        return true;
+    };
+    is_different(stats.cooldown, Some(other.cooldown))
+        || is_different(stats.impact_multiplier, Some(other.impact_multiplier))
+        || stats.recoveries.len() != other.recoveries.len()
+        || stats
+            .recoveries
+            .iter()
+            .zip(other.recoveries.iter())
+            .any(|(recovery, other)| is_different(*recovery, Some(*other)))
+}
+
+/// Values that differ from the other deck are highlighted along with the difference,
+/// values the other deck has nothing to compare against are shown in blue
+fn draw_compared_value(ui: &mut Ui, label: &str, value: f32, other: Option<f32>, suffix: &str) {
+    let text = format!("{}: {:.2}{}", label, value, suffix);
+    let text = match other {
+        Some(other) if is_different(value, Some(other)) => {
+            RichText::new(format!("{} ({:+.2}{})", text, value - other, suffix))
+                .color(Color32::YELLOW)
+        }
+        Some(_) => RichText::new(text).color(Color32::WHITE),
+        None => RichText::new(text).color(Color32::LIGHT_BLUE),
+    };
+    ui.label(text);
+}
+
+fn draw_deck_source_picker(
+    ui: &mut Ui,
+    compared: &mut ComparedDeck,
+    side: usize,
+    current_deck: &Deck,
+    card_dir: &str,
+) -> Result<(), String> {
+    let mut selected = None;
+    egui::ComboBox::from_id_salt(("deck_compare_source", side))
+        .selected_text(&compared.name)
+        .show_ui(ui, |ui| {
+            if ui
+                .selectable_label(compared.source.is_none(), "Current deck")
+                .clicked()
+            {
+                selected = Some(None);
+            }
+            match list_deck_files(card_dir) {
+                Ok(files) => {
+                    for path in files {
+                        let is_selected = compared.source.as_ref() == Some(&path);
+                        if ui
+                            .selectable_label(is_selected, get_deck_name(card_dir, &path))
+                            .clicked()
+                        {
+                            selected = Some(Some(path));
+                        }
+                    }
+                }
+                Err(e) => {
+                    ui.colored_label(Color32::RED, e);
+                }
+            }
+        });
+    match selected {
+        Some(None) => *compared = ComparedDeck::current(current_deck),
+        Some(Some(path)) => {
+            let deck = load_deck(&path)?;
+            *compared = ComparedDeck::new(get_deck_name(card_dir, &path), Some(path), deck);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Draws a deck read only. The editor ids are built from card paths, so each side needs its own range of root indices.
+fn draw_compared_deck(
+    ui: &mut Ui,
+    deck: &mut Deck,
+    stats: &DeckStats,
+    other_stats: &DeckStats,
+    root_offset: usize,
+) {
+    let mut dnd_path = None;
+    let mut modify_path = None;
+    draw_compared_value(
+        ui,
+        "Total impact",
+        stats.total_impact,
+        Some(other_stats.total_impact),
+        "",
+    );
+    ui.horizontal_top(|ui| {
+        deck.passive.draw(
+            ui,
+            &mut vec![root_offset + 1],
+            &mut dnd_path,
+            &mut modify_path,
+            &EditMode::Readonly,
+        );
+    });
+    for (cooldown_idx, (cooldown, cooldown_stats)) in
+        deck.cooldowns.iter_mut().zip(stats.cooldowns.iter()).enumerate()
+    {
+        let other = other_stats.cooldowns.get(cooldown_idx);
+        cooldown.cooldown_value = Some((cooldown_stats.cooldown, cooldown_stats.recoveries.clone()));
+        let stroke = if is_cooldown_different(cooldown_stats, other) {
+            Stroke::new(1.0, Color32::YELLOW)
+        } else {
+            Stroke::NONE
+        };
+        Frame::new()
+            .stroke(stroke)
+            .inner_margin(CARD_UI_SPACING)
+            .corner_radius(CARD_UI_ROUNDING)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        RichText::new(format!("Cooldown {}", cooldown_idx + 1))
+                            .color(Color32::WHITE),
+                    );
+                    draw_compared_value(
+                        ui,
+                        "Time",
+                        cooldown_stats.cooldown,
+                        other.map(|other| other.cooldown),
+                        "s",
+                    );
+                    draw_compared_value(
+                        ui,
+                        "Impact multiplier",
+                        cooldown_stats.impact_multiplier,
+                        other.map(|other| other.impact_multiplier),
+                        "",
+                    );
+                });
+                ui.horizontal_wrapped(|ui| {
+                    for (ability_idx, recovery) in cooldown_stats.recoveries.iter().enumerate() {
+                        draw_compared_value(
+                            ui,
+                            &format!("Ability {} recovery", ability_idx + 1),
+                            *recovery,
+                            other.and_then(|other| other.recoveries.get(ability_idx).copied()),
+                            "s",
+                        );
+                    }
+                });
+                ui.horizontal_top(|ui| {
+                    cooldown.draw(
+                        ui,
+                        &mut vec![root_offset + cooldown_idx + 2],
+                        &mut dnd_path,
+                        &mut modify_path,
+                        &EditMode::Readonly,
+                    );
+                });
+            });
+    }
+}
+
+pub fn deck_compare(ctx: &egui::Context, gui_state: &mut GuiState, settings: &Settings) {
+    egui::CentralPanel::default()
+        .frame(Frame::new().inner_margin(PADDING))
+        .show(ctx, |ui| {
+            ui.painter()
+                .rect_filled(ui.clip_rect(), 0.0, Color32::BLACK);
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Deck Comparison").color(Color32::WHITE));
+                if ui.button("Back").clicked() {
+                    gui_state.menu_stack.pop();
+                }
+                if let Some(comparison) = &mut gui_state.deck_comparison
+                    && ui.button("Swap").clicked()
+                {
+                    comparison.decks.swap(0, 1);
+                }
+            });
+            let Some(comparison) = &mut gui_state.deck_comparison else {
+                ui.label("No decks to compare");
+                return;
+            };
+            for (side, compared) in comparison.decks.iter_mut().enumerate() {
+                if let Err(e) = draw_deck_source_picker(
+                    ui,
+                    compared,
+                    side,
+                    &gui_state.render_deck,
+                    &settings.card_dir,
+                ) {
+                    gui_state.errors.push(e);
+                }
+            }
+            ui.separator();
+
+            let root_offsets = [0, comparison.decks[0].deck.cooldowns.len() + 2];
+            ui.columns(2, |columns| {
+                for (side, column) in columns.iter_mut().enumerate() {
+                    let (first, second) = comparison.decks.split_at_mut(1);
+                    let (compared, other) = if side == 0 {
+                        (&mut first[0], &second[0])
+                    } else {
+                        (&mut second[0], &first[0])
+                    };
+                    column.label(RichText::new(&compared.name).color(Color32::WHITE));
+                    ScrollArea::vertical()
+                        .id_salt(("deck_compare", side))
+                        .auto_shrink([false, false])
+                        .show(column, |ui| {
+                            draw_compared_deck(
+                                ui,
+                                &mut compared.deck,
+                                &compared.stats,
+                                &other.stats,
+                                root_offsets[side],
+                            );
+                        });
+                }
+            });
         });
 }
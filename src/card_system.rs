@@ -1,5 +1,7 @@
-use crate::{settings_manager::Control, PLAYER_BASE_MAX_HEALTH};
-use cgmath::{Point3, Quaternion, Rad, Rotation3};
+use crate::{
+    settings_manager::Control, status_effects::StatusEffectContainer, PLAYER_BASE_MAX_HEALTH,
+};
+use cgmath::{Point3, Quaternion, Rad, Rotation3, Vector2};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -143,6 +145,8 @@ pub struct Ability {
     pub is_cache_valid: bool,
     #[serde(skip_serializing, default)]
     pub is_keybind_selected: bool,
+    #[serde(skip_serializing, default)]
+    pub keybind_text: Option<String>,
 }
 
 impl Default for Ability {
@@ -153,6 +157,7 @@ impl Default for Ability {
             cached_cooldown: None,
             is_cache_valid: false,
             is_keybind_selected: false,
+            keybind_text: None,
         }
     }
 }
@@ -170,12 +175,17 @@ impl Ability {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum Keybind {
     Pressed(Control),
     OnPressed(Control),
     OnReleased(Control),
+    /// Activates when the control is released after being held for at least the given number of seconds
+    HeldFor(Control, f32),
     IsOnGround,
+    HasCharges(u32),
+    HealthBelow(u32),
+    HasStatus(ReferencedStatusEffectType),
     And(Box<Keybind>, Box<Keybind>),
     Or(Box<Keybind>, Box<Keybind>),
     Not(Box<Keybind>),
@@ -188,13 +198,34 @@ impl Keybind {
             Keybind::Pressed(control) => Some(format!("{}", control)),
             Keybind::OnPressed(control) => Some(format!("{}", control)),
             Keybind::OnReleased(control) => Some(format!("{}", control)),
+            Keybind::HeldFor(control, _) => Some(format!("{}", control)),
             Keybind::IsOnGround => None,
+            Keybind::HasCharges(_) => None,
+            Keybind::HealthBelow(_) => None,
+            Keybind::HasStatus(_) => None,
             Keybind::And(_, _) => None,
             Keybind::Or(_, _) => None,
             Keybind::Not(_) => None,
             Keybind::True => Some("⨀".to_string()),
         }
     }
+
+    /// Every control this keybind reads, in the order they appear
+    pub fn get_controls(&self) -> Vec<Control> {
+        match self {
+            Keybind::Pressed(control)
+            | Keybind::OnPressed(control)
+            | Keybind::OnReleased(control)
+            | Keybind::HeldFor(control, _) => vec![control.clone()],
+            Keybind::And(a, b) | Keybind::Or(a, b) => {
+                let mut controls = a.get_controls();
+                controls.extend(b.get_controls());
+                controls
+            }
+            Keybind::Not(a) => a.get_controls(),
+            _ => vec![],
+        }
+    }
 }
 
 impl Cooldown {
@@ -336,6 +367,14 @@ pub enum ProjectileModifier {
     LockToOwner(DirectionCard),
     PiercePlayers,
     WallBounce,
+    Charge(ChargeModifierType, u32),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum ChargeModifierType {
+    Speed,
+    Size,
+    Damage,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -814,6 +853,39 @@ impl BaseCard {
         ron::to_string(self).unwrap()
     }
 
+    pub fn get_name(&self) -> String {
+        match self {
+            BaseCard::Projectile(_) => "Create Projectile".to_string(),
+            BaseCard::MultiCast(..) => "Multicast".to_string(),
+            BaseCard::CreateMaterial(material) => format!("Create Material {:?}", material),
+            BaseCard::Effect(effect) => effect.get_name(),
+            BaseCard::StatusEffects(..) => "Apply Status Effects".to_string(),
+            BaseCard::Trigger(_) => "Trigger".to_string(),
+            BaseCard::None => "None".to_string(),
+            BaseCard::Palette(_) => "Palette".to_string(),
+        }
+    }
+
+    pub fn get_hover_text(&self) -> String {
+        match self {
+            BaseCard::Projectile(_) => {
+                "Create a projectile that can be changed with projectile modifiers".to_string()
+            }
+            BaseCard::MultiCast(..) => "Activate every contained card at once".to_string(),
+            BaseCard::CreateMaterial(material) => {
+                format!("Place {:?} where this is activated", material)
+            }
+            BaseCard::Effect(effect) => effect.get_hover_text(),
+            BaseCard::StatusEffects(duration, _) => format!(
+                "Apply status effects for a duration of {}s",
+                *duration as f32 * BaseCard::EFFECT_LENGTH_SCALE
+            ),
+            BaseCard::Trigger(_) => "Cause on trigger events to activate".to_string(),
+            BaseCard::None => "An empty slot for a card".to_string(),
+            BaseCard::Palette(_) => "Cards to choose from".to_string(),
+        }
+    }
+
     pub fn get_cooldown(&self) -> f32 {
         puffin::profile_function!();
         let card_values = self.evaluate_value(true);
@@ -926,6 +998,7 @@ impl BaseCard {
                 let mut enemy_fire = true;
                 let mut pierce_players = false;
                 let mut wall_bounce = false;
+                let mut charged_damage = 1.0;
                 for modifier in modifiers {
                     match modifier {
                         ProjectileModifier::None => {}
@@ -986,6 +1059,18 @@ impl BaseCard {
                         }
                         ProjectileModifier::LockToOwner(_) => {}
                         ProjectileModifier::PiercePlayers => pierce_players = true,
+                        // Charged abilities are costed as if they were always fully charged
+                        ProjectileModifier::Charge(ChargeModifierType::Speed, s) => {
+                            speed += *s as i32
+                        }
+                        ProjectileModifier::Charge(ChargeModifierType::Size, s) => {
+                            length += *s as i32;
+                            width += *s as i32;
+                            height += *s as i32;
+                        }
+                        ProjectileModifier::Charge(ChargeModifierType::Damage, _) => {
+                            charged_damage *= modifier.get_effect_value()
+                        }
                     }
                 }
                 let speed =
@@ -1103,7 +1188,7 @@ impl BaseCard {
                     })
                 }));
                 value.extend(hit_value.into_iter().map(|hit_value| CardValue {
-                    damage: hit_value.damage,
+                    damage: charged_damage * hit_value.damage,
                     generic: hit_value.generic,
                     range_probabilities: convolve_range_probabilities(
                         range_probabilities,
@@ -1111,7 +1196,7 @@ impl BaseCard {
                     ),
                 }));
                 value.extend(headshot_value.into_iter().map(|headshot_value| CardValue {
-                    damage: headshot_value.damage,
+                    damage: charged_damage * headshot_value.damage,
                     generic: headshot_value.generic,
                     range_probabilities: convolve_range_probabilities(
                         headshot_range_probabilities,
@@ -1524,6 +1609,11 @@ impl BaseCard {
                                 return Some(format!("Projectile modifier too high ({} > 15)", s));
                             }
                         }
+                        ProjectileModifier::Charge(_, s) => {
+                            if *s > 15 {
+                                return Some(format!("Charge modifier too high ({} > 15)", s));
+                            }
+                        }
                         ProjectileModifier::OnHit(card) => {
                             if let Some(reason) = card.get_unreasonable_reason() {
                                 return Some(reason);
@@ -1647,6 +1737,30 @@ pub enum DragableCard {
     Direction(DirectionCard),
 }
 
+impl DragableCard {
+    pub fn get_name(&self) -> String {
+        match self {
+            DragableCard::ProjectileModifier(modifier) => modifier.get_name(),
+            DragableCard::MultiCastModifier(modifier) => modifier.get_name(),
+            DragableCard::CooldownModifier(modifier) => modifier.get_name(),
+            DragableCard::StatusEffect(effect) => effect.get_name(),
+            DragableCard::BaseCard(card) => card.get_name(),
+            DragableCard::Direction(direction) => direction.to_string(),
+        }
+    }
+
+    pub fn get_hover_text(&self) -> String {
+        match self {
+            DragableCard::ProjectileModifier(modifier) => modifier.get_hover_text(),
+            DragableCard::MultiCastModifier(modifier) => modifier.get_hover_text(),
+            DragableCard::CooldownModifier(modifier) => modifier.get_hover_text(),
+            DragableCard::StatusEffect(effect) => effect.get_hover_text(),
+            DragableCard::BaseCard(card) => card.get_hover_text(),
+            DragableCard::Direction(direction) => format!("Points {}", direction),
+        }
+    }
+}
+
 impl Default for BaseCard {
     fn default() -> Self {
         BaseCard::None
@@ -1654,6 +1768,9 @@ impl Default for BaseCard {
 }
 
 impl ProjectileModifier {
+    /// How long an ability has to be held for `Charge` modifiers to reach their full effect
+    pub const FULL_CHARGE_TIME: f32 = 2.0;
+
     pub fn is_advanced(&self) -> bool {
         match self {
             ProjectileModifier::None => false,
@@ -1668,6 +1785,7 @@ impl ProjectileModifier {
             ProjectileModifier::LockToOwner(_) => false,
             ProjectileModifier::PiercePlayers => false,
             ProjectileModifier::WallBounce => false,
+            ProjectileModifier::Charge(_, _) => false,
         }
     }
 
@@ -1720,6 +1838,21 @@ impl ProjectileModifier {
             ProjectileModifier::WallBounce => {
                 format!("Allows the projectile to bounce off walls")
             }
+            ProjectileModifier::Charge(ChargeModifierType::Speed, _) => format!(
+                "Charge speed (+8 per at full charge) +{}b/s after holding for {}s",
+                self.get_effect_value(),
+                Self::FULL_CHARGE_TIME
+            ),
+            ProjectileModifier::Charge(ChargeModifierType::Size, _) => format!(
+                "Charge size (+25% per at full charge) {} after holding for {}s",
+                self.get_effect_value(),
+                Self::FULL_CHARGE_TIME
+            ),
+            ProjectileModifier::Charge(ChargeModifierType::Damage, _) => format!(
+                "Charge damage (+25% per at full charge) {} after holding for {}s",
+                self.get_effect_value(),
+                Self::FULL_CHARGE_TIME
+            ),
         }
     }
 
@@ -1750,6 +1883,9 @@ impl ProjectileModifier {
             ProjectileModifier::SimpleModify(SimpleProjectileModifierType::Health, s) => {
                 1.5f32.powi(*s)
             }
+            ProjectileModifier::Charge(ChargeModifierType::Speed, s) => 8.0 * *s as f32,
+            ProjectileModifier::Charge(ChargeModifierType::Size, s) => 1.25f32.powi(*s as i32),
+            ProjectileModifier::Charge(ChargeModifierType::Damage, s) => 1.25f32.powi(*s as i32),
             ProjectileModifier::FriendlyFire => panic!(),
             ProjectileModifier::NoEnemyFire => panic!(),
             ProjectileModifier::OnHit(_) => panic!(),
@@ -1786,17 +1922,35 @@ impl ProjectileModifier {
             ProjectileModifier::LockToOwner(_) => "Lock To Owner",
             ProjectileModifier::PiercePlayers => "Pierce Players",
             ProjectileModifier::WallBounce => "Wall Bounce",
+            ProjectileModifier::Charge(ty, _) => match ty {
+                ChargeModifierType::Speed => "Charge Speed",
+                ChargeModifierType::Size => "Charge Size",
+                ChargeModifierType::Damage => "Charge Damage",
+            },
         }
         .to_string()
     }
 }
 
+/// The parts of a player's state that keybind conditions can depend on, gathered once per tick.
+/// Ground contact is only known to the GPU physics, so `IsOnGround` is not set from here.
+pub struct KeybindPlayerState<'a> {
+    pub charges: u32,
+    pub health_fraction: f32,
+    pub status_effects: &'a StatusEffectContainer,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum StateKeybind {
     Pressed(Control, bool),
     OnPressed(Control, bool),
     OnReleased(Control, bool),
+    /// Required hold time, time held so far while pressed and the hold time of the last qualifying release
+    HeldFor(Control, f32, Option<f32>, Option<f32>),
     IsOnGround(bool),
+    HasCharges(u32, bool),
+    HealthBelow(u32, bool),
+    HasStatus(ReferencedStatusEffectType, bool),
     And(Box<StateKeybind>, Box<StateKeybind>),
     Or(Box<StateKeybind>, Box<StateKeybind>),
     Not(Box<StateKeybind>),
@@ -1821,7 +1975,23 @@ impl StateKeybind {
                     *s = true;
                 }
             }
-            StateKeybind::IsOnGround(_) => {}
+            StateKeybind::HeldFor(c, required, held, released) => {
+                if *c == *control {
+                    if state {
+                        if held.is_none() {
+                            *held = Some(0.0);
+                        }
+                    } else if let Some(held_time) = held.take() {
+                        if held_time >= *required {
+                            *released = Some(held_time);
+                        }
+                    }
+                }
+            }
+            StateKeybind::IsOnGround(_)
+            | StateKeybind::HasCharges(_, _)
+            | StateKeybind::HealthBelow(_, _)
+            | StateKeybind::HasStatus(_, _) => {}
             StateKeybind::And(a, b) => {
                 a.as_mut().update(control, state);
                 b.as_mut().update(control, state);
@@ -1837,32 +2007,104 @@ impl StateKeybind {
         }
     }
 
-    pub fn update_on_ground(&mut self, state: bool) {
+    pub fn tick(&mut self, delta_time: f32) {
         match self {
-            StateKeybind::IsOnGround(s) => {
-                *s = state;
+            StateKeybind::HeldFor(_, _, Some(held), _) => {
+                *held += delta_time;
             }
             StateKeybind::And(a, b) => {
-                a.as_mut().update_on_ground(state);
-                b.as_mut().update_on_ground(state);
+                a.as_mut().tick(delta_time);
+                b.as_mut().tick(delta_time);
             }
             StateKeybind::Or(a, b) => {
-                a.as_mut().update_on_ground(state);
-                b.as_mut().update_on_ground(state);
+                a.as_mut().tick(delta_time);
+                b.as_mut().tick(delta_time);
             }
             StateKeybind::Not(a) => {
-                a.as_mut().update_on_ground(state);
+                a.as_mut().tick(delta_time);
             }
             _ => {}
         }
     }
 
+    /// How long the longest held `HeldFor` control has been held so far
+    pub fn get_held_time(&self) -> Option<f32> {
+        match self {
+            StateKeybind::HeldFor(_, _, held, _) => *held,
+            StateKeybind::And(a, b) | StateKeybind::Or(a, b) => {
+                match (a.get_held_time(), b.get_held_time()) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    (a, b) => a.or(b),
+                }
+            }
+            StateKeybind::Not(a) => a.get_held_time(),
+            _ => None,
+        }
+    }
+
+    /// The longest hold time that activated this keybind, used to scale `Charge` modifiers
+    pub fn get_charge_time(&self) -> f32 {
+        match self {
+            StateKeybind::HeldFor(_, _, _, released) => released.unwrap_or(0.0),
+            StateKeybind::And(a, b) => a.get_charge_time().max(b.get_charge_time()),
+            StateKeybind::Or(a, b) => a.get_charge_time().max(b.get_charge_time()),
+            StateKeybind::Not(a) => a.get_charge_time(),
+            _ => 0.0,
+        }
+    }
+
+    pub fn update_player_state(&mut self, player_state: &KeybindPlayerState) {
+        match self {
+            StateKeybind::HasCharges(charges, s) => {
+                *s = player_state.charges >= *charges;
+            }
+            StateKeybind::HealthBelow(percent, s) => {
+                *s = player_state.health_fraction * 100.0 < *percent as f32;
+            }
+            StateKeybind::HasStatus(effect_type, s) => {
+                *s = player_state.status_effects.has_status(*effect_type);
+            }
+            StateKeybind::And(a, b) => {
+                a.as_mut().update_player_state(player_state);
+                b.as_mut().update_player_state(player_state);
+            }
+            StateKeybind::Or(a, b) => {
+                a.as_mut().update_player_state(player_state);
+                b.as_mut().update_player_state(player_state);
+            }
+            StateKeybind::Not(a) => {
+                a.as_mut().update_player_state(player_state);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn get_controls(&self) -> Vec<Control> {
+        match self {
+            StateKeybind::Pressed(control, _)
+            | StateKeybind::OnPressed(control, _)
+            | StateKeybind::OnReleased(control, _)
+            | StateKeybind::HeldFor(control, _, _, _) => vec![control.clone()],
+            StateKeybind::And(a, b) | StateKeybind::Or(a, b) => {
+                let mut controls = a.get_controls();
+                controls.extend(b.get_controls());
+                controls
+            }
+            StateKeybind::Not(a) => a.get_controls(),
+            _ => vec![],
+        }
+    }
+
     pub fn get_simple_representation(&self) -> Option<String> {
         match self {
             StateKeybind::Pressed(control, _) => Some(format!("{}", control)),
             StateKeybind::OnPressed(control, _) => Some(format!("{}", control)),
             StateKeybind::OnReleased(control, _) => Some(format!("{}", control)),
+            StateKeybind::HeldFor(control, _, _, _) => Some(format!("{}", control)),
             StateKeybind::IsOnGround(_) => None,
+            StateKeybind::HasCharges(_, _) => None,
+            StateKeybind::HealthBelow(_, _) => None,
+            StateKeybind::HasStatus(_, _) => None,
             StateKeybind::And(_, _) => None,
             StateKeybind::Or(_, _) => None,
             StateKeybind::Not(_) => None,
@@ -1875,7 +2117,11 @@ impl StateKeybind {
             StateKeybind::Pressed(_, s) => *s,
             StateKeybind::OnPressed(_, s) => *s,
             StateKeybind::OnReleased(_, s) => *s,
+            StateKeybind::HeldFor(_, _, _, released) => released.is_some(),
             StateKeybind::IsOnGround(s) => *s,
+            StateKeybind::HasCharges(_, s) => *s,
+            StateKeybind::HealthBelow(_, s) => *s,
+            StateKeybind::HasStatus(_, s) => *s,
             StateKeybind::And(a, b) => a.get_state() && b.get_state(),
             StateKeybind::Or(a, b) => a.get_state() || b.get_state(),
             StateKeybind::Not(a) => !a.get_state(),
@@ -1888,7 +2134,11 @@ impl StateKeybind {
             StateKeybind::Pressed(_, _) => {}
             StateKeybind::OnPressed(_, s) => *s = false,
             StateKeybind::OnReleased(_, s) => *s = false,
+            StateKeybind::HeldFor(_, _, _, released) => *released = None,
             StateKeybind::IsOnGround(s) => *s = false,
+            StateKeybind::HasCharges(_, s) => *s = false,
+            StateKeybind::HealthBelow(_, s) => *s = false,
+            StateKeybind::HasStatus(_, s) => *s = false,
             StateKeybind::And(a, b) => {
                 a.as_mut().clear();
                 b.as_mut().clear();
@@ -1911,7 +2161,13 @@ impl From<Keybind> for StateKeybind {
             Keybind::Pressed(control) => StateKeybind::Pressed(control, false),
             Keybind::OnPressed(control) => StateKeybind::OnPressed(control, false),
             Keybind::OnReleased(control) => StateKeybind::OnReleased(control, false),
+            Keybind::HeldFor(control, seconds) => {
+                StateKeybind::HeldFor(control, seconds, None, None)
+            }
             Keybind::IsOnGround => StateKeybind::IsOnGround(false),
+            Keybind::HasCharges(charges) => StateKeybind::HasCharges(charges, false),
+            Keybind::HealthBelow(percent) => StateKeybind::HealthBelow(percent, false),
+            Keybind::HasStatus(effect_type) => StateKeybind::HasStatus(effect_type, false),
             Keybind::And(a, b) => StateKeybind::And(Box::new((*a).into()), Box::new((*b).into())),
             Keybind::Or(a, b) => StateKeybind::Or(Box::new((*a).into()), Box::new((*b).into())),
             Keybind::Not(a) => StateKeybind::Not(Box::new((*a).into())),
@@ -1974,6 +2230,38 @@ pub struct ReferencedProjectile {
     pub on_expiry: Vec<ReferencedBaseCard>,
     pub on_trigger: Vec<(u32, ReferencedBaseCard)>,
     pub trail: Vec<(f32, ReferencedBaseCard)>,
+    pub charge: Vec<(ChargeModifierType, u32)>,
+}
+
+impl ReferencedProjectile {
+    /// Returns the stats of this projectile after being charged for `charge_time` seconds.
+    /// A full charge gives the modifier's whole effect, shorter charges a fraction of its stacks.
+    pub fn get_charged(&self, charge_time: f32) -> ReferencedProjectile {
+        let charge = (charge_time / ProjectileModifier::FULL_CHARGE_TIME).clamp(0.0, 1.0);
+        let mut charged = self.clone();
+        for (ty, stacks) in self.charge.iter() {
+            let full_effect = ProjectileModifier::Charge(ty.clone(), *stacks).get_effect_value();
+            match ty {
+                ChargeModifierType::Speed => charged.speed += charge * full_effect,
+                ChargeModifierType::Size => {
+                    let scale = full_effect.powf(charge);
+                    charged.length *= scale;
+                    charged.width *= scale;
+                    charged.height *= scale;
+                }
+                ChargeModifierType::Damage => {
+                    charged.damage =
+                        (charged.damage as f32 * full_effect.powf(charge)).round() as i32
+                }
+            }
+        }
+        charged
+    }
+
+    /// Distance covered over the whole lifetime, ignoring gravity and collisions
+    pub fn get_range(&self) -> f32 {
+        self.speed * self.lifetime
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -1983,6 +2271,37 @@ pub struct ReferencedMulticast {
     pub duplication: u32,
 }
 
+impl ReferencedMulticast {
+    /// Spread needed to widen the cone of duplicates by one radian
+    pub const SPREAD_PER_RADIAN: f32 = 15.0;
+    /// Turn between consecutive duplicates on the spread spiral, close to the golden angle so they cover the cone evenly
+    pub const SPIRAL_STEP: f32 = 2.4;
+    /// Each duplication doubles the cast, so it is capped to keep the count in range
+    pub const MAX_DUPLICATION: u32 = 10;
+
+    /// Half angle of the cone the duplicates are spread over, in radians
+    pub fn get_spread_angle(&self) -> f32 {
+        self.spread as f32 / Self::SPREAD_PER_RADIAN
+    }
+
+    pub fn get_duplicate_count(&self) -> u32 {
+        2u32.pow(self.duplication.min(Self::MAX_DUPLICATION))
+    }
+
+    /// Yaw and pitch of each duplicate relative to the cast direction
+    pub fn get_duplicate_offsets(&self) -> Vec<Vector2<f32>> {
+        let spread = self.get_spread_angle();
+        let count = self.get_duplicate_count();
+        (0..count)
+            .map(|i| {
+                let radius = spread * (i as f32 / count as f32).sqrt();
+                let turn = Self::SPIRAL_STEP * i as f32;
+                Vector2::new(radius * turn.cos(), radius * turn.sin())
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum ReferencedEffect {
     Damage(i32),
@@ -1997,7 +2316,7 @@ pub struct ReferencedStatusEffects {
     pub effects: Vec<ReferencedStatusEffect>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum ReferencedStatusEffect {
     Speed(i32),
     DamageOverTime(i32),
@@ -2012,9 +2331,114 @@ pub enum ReferencedStatusEffect {
     OnHit(ReferencedBaseCard),
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferencedStatusEffectType {
+    Speed,
+    DamageOverTime,
+    IncreaseDamageTaken,
+    IncreaseGravity,
+    Overheal,
+    Grow,
+    IncreaseMaxHealth,
+    Invincibility,
+    Trapped,
+    Lockout,
+    OnHit,
+}
+
+impl ReferencedStatusEffectType {
+    pub const ALL: [ReferencedStatusEffectType; 11] = [
+        ReferencedStatusEffectType::Speed,
+        ReferencedStatusEffectType::DamageOverTime,
+        ReferencedStatusEffectType::IncreaseDamageTaken,
+        ReferencedStatusEffectType::IncreaseGravity,
+        ReferencedStatusEffectType::Overheal,
+        ReferencedStatusEffectType::Grow,
+        ReferencedStatusEffectType::IncreaseMaxHealth,
+        ReferencedStatusEffectType::Invincibility,
+        ReferencedStatusEffectType::Trapped,
+        ReferencedStatusEffectType::Lockout,
+        ReferencedStatusEffectType::OnHit,
+    ];
+}
+
+impl ReferencedStatusEffect {
+    pub fn get_type(&self) -> ReferencedStatusEffectType {
+        match self {
+            ReferencedStatusEffect::Speed(_) => ReferencedStatusEffectType::Speed,
+            ReferencedStatusEffect::DamageOverTime(_) => ReferencedStatusEffectType::DamageOverTime,
+            ReferencedStatusEffect::IncreaseDamageTaken(_) => {
+                ReferencedStatusEffectType::IncreaseDamageTaken
+            }
+            ReferencedStatusEffect::IncreaseGravity(_, _) => {
+                ReferencedStatusEffectType::IncreaseGravity
+            }
+            ReferencedStatusEffect::Overheal(_) => ReferencedStatusEffectType::Overheal,
+            ReferencedStatusEffect::Grow(_) => ReferencedStatusEffectType::Grow,
+            ReferencedStatusEffect::IncreaseMaxHealth(_) => {
+                ReferencedStatusEffectType::IncreaseMaxHealth
+            }
+            ReferencedStatusEffect::Invincibility => ReferencedStatusEffectType::Invincibility,
+            ReferencedStatusEffect::Trapped => ReferencedStatusEffectType::Trapped,
+            ReferencedStatusEffect::Lockout => ReferencedStatusEffectType::Lockout,
+            ReferencedStatusEffect::OnHit(_) => ReferencedStatusEffectType::OnHit,
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        match self {
+            ReferencedStatusEffect::DamageOverTime(stacks) => {
+                format!("Damage Over Time {}", stacks)
+            }
+            ReferencedStatusEffect::Speed(stacks) => format!("Speed {}", stacks),
+            ReferencedStatusEffect::IncreaseDamageTaken(stacks) => {
+                format!("Increase Damage Taken {}", stacks)
+            }
+            ReferencedStatusEffect::IncreaseGravity(direction, stacks) => {
+                format!("Increase Gravity {} {}", direction, stacks)
+            }
+            ReferencedStatusEffect::Overheal(stacks) => format!("Overheal {}", stacks),
+            ReferencedStatusEffect::Grow(stacks) => format!("Grow {}", stacks),
+            ReferencedStatusEffect::IncreaseMaxHealth(stacks) => {
+                format!("Increase Max Health {}", stacks)
+            }
+            ReferencedStatusEffect::Invincibility => "Invincibility".to_string(),
+            ReferencedStatusEffect::Trapped => "Trapped".to_string(),
+            ReferencedStatusEffect::Lockout => "Lockout".to_string(),
+            ReferencedStatusEffect::OnHit(_) => "On Player Hit".to_string(),
+        }
+    }
+
+    /// Whether a `Cleanse` effect should remove this status effect
+    pub fn is_harmful(&self) -> bool {
+        match self {
+            ReferencedStatusEffect::Speed(stacks) => *stacks < 0,
+            ReferencedStatusEffect::DamageOverTime(stacks) => *stacks > 0,
+            ReferencedStatusEffect::IncreaseDamageTaken(stacks) => *stacks > 0,
+            ReferencedStatusEffect::IncreaseGravity(_, _) => false,
+            ReferencedStatusEffect::Overheal(_) => false,
+            ReferencedStatusEffect::Grow(_) => false,
+            ReferencedStatusEffect::IncreaseMaxHealth(stacks) => *stacks < 0,
+            ReferencedStatusEffect::Invincibility => false,
+            ReferencedStatusEffect::Trapped => true,
+            ReferencedStatusEffect::Lockout => true,
+            ReferencedStatusEffect::OnHit(_) => false,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ReferencedTrigger(pub u32);
 
+/// Everything a single cast of a card does
+#[derive(Debug, Clone, Default)]
+pub struct CastEffects {
+    /// Each projectile with its yaw and pitch relative to the cast direction
+    pub projectiles: Vec<(ReferencedProjectile, Vector2<f32>)>,
+    pub effects: Vec<ReferencedEffect>,
+    pub status_effects: Vec<ReferencedStatusEffects>,
+}
+
 pub struct CardManager {
     pub referenced_multicasts: Vec<ReferencedMulticast>,
     pub referenced_projs: Vec<ReferencedProjectile>,
@@ -2095,6 +2519,7 @@ impl CardManager {
                 let mut lock_owner: Option<DirectionCard> = None;
                 let mut pierce_players = false;
                 let mut wall_bounce = false;
+                let mut charge = Vec::new();
                 for modifier in modifiers {
                     match modifier {
                         ProjectileModifier::None => {}
@@ -2154,6 +2579,7 @@ impl CardManager {
                         ProjectileModifier::LockToOwner(direction) => lock_owner = Some(direction),
                         ProjectileModifier::PiercePlayers => pierce_players = true,
                         ProjectileModifier::WallBounce => wall_bounce = true,
+                        ProjectileModifier::Charge(ty, stacks) => charge.push((ty, stacks)),
                     }
                 }
                 self.referenced_projs.push(ReferencedProjectile {
@@ -2203,6 +2629,7 @@ impl CardManager {
                     on_expiry,
                     on_trigger,
                     trail,
+                    charge,
                 });
 
                 ReferencedBaseCard {
@@ -2335,150 +2762,143 @@ impl CardManager {
         }
     }
 
-    // pub fn get_effects_from_base_card(
-    //     &self,
-    //     card: ReferencedBaseCard,
-    //     pos: &Point3<f32>,
-    //     rot: &Quaternion<f32>,
-    //     player_idx: u32,
-    //     is_from_head: bool,
-    // ) -> (
-    //     Vec<Projectile>,
-    //     Vec<(Point3<u32>, VoxelMaterial)>,
-    //     Vec<ReferencedEffect>,
-    //     Vec<ReferencedStatusEffects>,
-    //     Vec<(ReferencedTrigger, u32)>,
-    // ) {
-    //     let mut projectiles = vec![];
-    //     let mut new_voxels = vec![];
-    //     let mut effects = vec![];
-    //     let mut status_effects = vec![];
-    //     let mut triggers = vec![];
-    //     match card {
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::Projectile,
-    //             card_idx,
-    //         } => {
-    //             let proj_stats = self.get_referenced_proj(card_idx);
-    //             let proj_damage = proj_stats.damage as f32;
-    //             let should_collide_with_terrain = proj_stats.lock_owner.is_none()
-    //                 || !proj_stats.on_hit.is_empty()
-    //                 || !proj_stats.on_headshot.is_empty();
-    //             projectiles.push(Projectile {
-    //                 pos: [pos.x, pos.y, pos.z, 1.0],
-    //                 chunk_update_pos: [0, 0, 0, 0],
-    //                 dir: [rot.v[0], rot.v[1], rot.v[2], rot.s],
-    //                 size: [proj_stats.width, proj_stats.height, proj_stats.length, 1.0],
-    //                 vel: proj_stats.speed,
-    //                 health: proj_stats.health,
-    //                 lifetime: 0.0,
-    //                 owner: player_idx,
-    //                 damage: proj_damage,
-    //                 proj_card_idx: card_idx as u32,
-    //                 wall_bounce: if proj_stats.wall_bounce { 1 } else { 0 },
-    //                 is_from_head: if is_from_head { 1 } else { 0 },
-    //                 should_collide_with_terrain: if should_collide_with_terrain { 1 } else { 0 },
-    //                 _filler0: 0,
-    //                 _filler1: 0,
-    //                 _filler2: 0,
-    //             });
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::MultiCast,
-    //             card_idx,
-    //         } => {
-    //             let multicast = &self.referenced_multicasts[card_idx];
-    //             let mut individual_sub_projectiles = vec![];
-    //             let mut sub_projectiles = vec![];
-    //             let mut sub_voxels = vec![];
-    //             let mut sub_effects = vec![];
-    //             let mut sub_status_effects = vec![];
-    //             let mut sub_triggers = vec![];
-    //             for sub_card in multicast.sub_cards.iter() {
-    //                 let (
-    //                     sub_sub_projectiles,
-    //                     sub_sub_voxels,
-    //                     sub_sub_effects,
-    //                     sub_sub_status_effects,
-    //                     sub_sub_triggers,
-    //                 ) = self.get_effects_from_base_card(
-    //                     *sub_card,
-    //                     pos,
-    //                     rot,
-    //                     player_idx,
-    //                     is_from_head,
-    //                 );
-    //                 individual_sub_projectiles.extend(sub_sub_projectiles);
-    //                 sub_voxels.extend(sub_sub_voxels);
-    //                 sub_effects.extend(sub_sub_effects);
-    //                 sub_status_effects.extend(sub_sub_status_effects);
-    //                 sub_triggers.extend(sub_sub_triggers);
-    //             }
-    //             let spread: f32 = multicast.spread as f32 / 15.0;
-    //             let count = 2u32.pow(multicast.duplication);
-    //             let rotation_factor = 2.4;
-    //             for i in 0..count {
-    //                 for sub_projectile in individual_sub_projectiles.iter() {
-    //                     let sub_rot = Quaternion::from(sub_projectile.dir);
-    //                     let mut new_sub_projectile = sub_projectile.clone();
-    //                     let x_rot = spread
-    //                         * (i as f32 / count as f32).sqrt()
-    //                         * (rotation_factor * (i as f32)).cos();
-    //                     let y_rot = spread
-    //                         * (i as f32 / count as f32).sqrt()
-    //                         * (rotation_factor * (i as f32)).sin();
-    //                     let new_rot = sub_rot
-    //                         * Quaternion::from_axis_angle([0.0, 1.0, 0.0].into(), Rad(x_rot))
-    //                         * Quaternion::from_axis_angle([1.0, 0.0, 0.0].into(), Rad(y_rot));
-
-    //                     new_sub_projectile.dir =
-    //                         [new_rot.v[0], new_rot.v[1], new_rot.v[2], new_rot.s];
-    //                     sub_projectiles.push(new_sub_projectile);
-    //                 }
-    //             }
-    //             projectiles.extend(sub_projectiles);
-    //             new_voxels.extend(sub_voxels);
-    //             effects.extend(sub_effects);
-    //             status_effects.extend(sub_status_effects);
-    //             triggers.extend(sub_triggers);
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::CreateMaterial,
-    //             card_idx,
-    //         } => {
-    //             let material = &self.referenced_material_creators[card_idx];
-    //             new_voxels.push((pos.cast::<u32>().unwrap(), material.clone()));
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::Effect,
-    //             card_idx,
-    //         } => {
-    //             let effect = &self.referenced_effects[card_idx];
-    //             effects.push(effect.clone());
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::StatusEffects,
-    //             card_idx,
-    //         } => {
-    //             let effect = &self.referenced_status_effects[card_idx];
-    //             status_effects.push(effect.clone());
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::Trigger,
-    //             card_idx,
-    //         } => {
-    //             let trigger = self.referenced_triggers[card_idx].clone();
-    //             triggers.push((trigger, player_idx));
-    //         }
-    //         ReferencedBaseCard {
-    //             card_type: ReferencedBaseCardType::None,
-    //             ..
-    //         } => {}
-    //     }
-    //     (projectiles, new_voxels, effects, status_effects, triggers)
-    // }
+    /// What casting a card does, with `Charge` modifiers charged for `charge_time` seconds
+    pub fn get_effects_from_base_card(
+        &self,
+        card: ReferencedBaseCard,
+        charge_time: f32,
+    ) -> CastEffects {
+        let mut cast_effects = CastEffects::default();
+        match card.card_type {
+            ReferencedBaseCardType::Projectile => {
+                cast_effects.projectiles.push((
+                    self.get_referenced_proj(card.card_idx)
+                        .get_charged(charge_time),
+                    Vector2::new(0.0, 0.0),
+                ));
+            }
+            ReferencedBaseCardType::MultiCast => {
+                let multicast = &self.referenced_multicasts[card.card_idx];
+                let offsets = multicast.get_duplicate_offsets();
+                for sub_card in multicast.sub_cards.iter() {
+                    let sub_effects = self.get_effects_from_base_card(*sub_card, charge_time);
+                    // Only projectiles are duplicated, effects on the caster apply once
+                    for offset in offsets.iter() {
+                        cast_effects.projectiles.extend(
+                            sub_effects
+                                .projectiles
+                                .iter()
+                                .map(|(proj, direction)| (proj.clone(), direction + offset)),
+                        );
+                    }
+                    cast_effects.effects.extend(sub_effects.effects);
+                    cast_effects.status_effects.extend(sub_effects.status_effects);
+                }
+            }
+            ReferencedBaseCardType::Effect => {
+                cast_effects
+                    .effects
+                    .push(self.referenced_effects[card.card_idx].clone());
+            }
+            ReferencedBaseCardType::StatusEffects => {
+                cast_effects
+                    .status_effects
+                    .push(self.referenced_status_effects[card.card_idx].clone());
+            }
+            ReferencedBaseCardType::CreateMaterial
+            | ReferencedBaseCardType::Trigger
+            | ReferencedBaseCardType::None => {}
+        }
+        cast_effects
+    }
 
     pub fn get_referenced_proj(&self, idx: usize) -> &ReferencedProjectile {
         &self.referenced_projs[idx]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::KeyCode;
+
+    fn player_state(
+        charges: u32,
+        health_fraction: f32,
+        status_effects: &StatusEffectContainer,
+    ) -> KeybindPlayerState<'_> {
+        KeybindPlayerState {
+            charges,
+            health_fraction,
+            status_effects,
+        }
+    }
+
+    #[test]
+    fn player_state_conditions_follow_transitions() {
+        let mut keybind = StateKeybind::from(Keybind::And(
+            Box::new(Keybind::Pressed(Control::Key(KeyCode::Space))),
+            Box::new(Keybind::Or(
+                Box::new(Keybind::HasCharges(2)),
+                Box::new(Keybind::And(
+                    Box::new(Keybind::HealthBelow(50)),
+                    Box::new(Keybind::Not(Box::new(Keybind::HasStatus(
+                        ReferencedStatusEffectType::Lockout,
+                    )))),
+                )),
+            )),
+        ));
+        let mut status_effects = StatusEffectContainer::default();
+        keybind.update(&Control::Key(KeyCode::Space), true);
+
+        keybind.update_player_state(&player_state(1, 1.0, &status_effects));
+        assert!(!keybind.get_state());
+
+        keybind.update_player_state(&player_state(2, 1.0, &status_effects));
+        assert!(keybind.get_state());
+
+        keybind.update_player_state(&player_state(0, 0.4, &status_effects));
+        assert!(keybind.get_state());
+
+        status_effects.apply_effect(ReferencedStatusEffect::Lockout, 1.0);
+        keybind.update_player_state(&player_state(0, 0.4, &status_effects));
+        assert!(!keybind.get_state());
+
+        status_effects.tick(1.5);
+        keybind.update_player_state(&player_state(0, 0.4, &status_effects));
+        assert!(keybind.get_state());
+
+        keybind.update(&Control::Key(KeyCode::Space), false);
+        assert!(!keybind.get_state());
+    }
+
+    #[test]
+    fn multicast_duplicates_only_projectiles() {
+        let mut card_manager = CardManager::default();
+        let card = card_manager.register_base_card(BaseCard::MultiCast(
+            vec![
+                BaseCard::Projectile(vec![]),
+                BaseCard::Effect(Effect::Damage(-1)),
+                BaseCard::StatusEffects(
+                    1,
+                    vec![StatusEffect::SimpleStatusEffect(
+                        SimpleStatusEffectType::Speed,
+                        1,
+                    )],
+                ),
+            ],
+            vec![MultiCastModifier::Duplication(3)],
+        ));
+        let cast_effects = card_manager.get_effects_from_base_card(card, 0.0);
+        assert_eq!(cast_effects.projectiles.len(), 8);
+        assert_eq!(cast_effects.effects.len(), 1);
+        assert_eq!(cast_effects.status_effects.len(), 1);
+    }
+
+    #[test]
+    fn player_state_does_not_set_ground_contact() {
+        let mut keybind = StateKeybind::from(Keybind::Not(Box::new(Keybind::IsOnGround)));
+        keybind.update_player_state(&player_state(0, 1.0, &StatusEffectContainer::default()));
+        assert!(keybind.get_state());
+    }
+}
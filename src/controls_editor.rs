@@ -0,0 +1,380 @@
+use egui::{Button, Color32, Context, DragValue, Id, Rect, Response, Ui};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    card_system::Deck,
+    gamepad::{AxisDirection, GamepadEvent},
+    settings_manager::{Control, ControlSettings, GamepadSettings, Settings},
+};
+
+const CAPTURE_TARGET_ID: &str = "control_capture_target";
+const CAPTURED_CONTROL_ID: &str = "control_capture_result";
+/// Where the waiting control button is, mouse presses over it are bound and presses anywhere else cancel
+const CAPTURE_RECT_ID: &str = "control_capture_rect";
+/// Sticks have to be pushed this far to be bound, so resting drift doesn't get picked up
+const CAPTURE_AXIS_THRESHOLD: f32 = 0.5;
+
+/// Hands the next key or mouse button press to the control button waiting for one.
+/// This reads the window events directly so keys egui does not report, like Shift and Control, can be bound.
+/// Returns true if the event was used up by the capture.
+pub fn capture_window_event(ctx: &Context, event: &WindowEvent) -> bool {
+    let target_id = Id::new(CAPTURE_TARGET_ID);
+    let Some(target) = ctx.data(|data| data.get_temp::<Id>(target_id)) else {
+        return false;
+    };
+    let control = match event {
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    state,
+                    physical_key: PhysicalKey::Code(KeyCode::Escape),
+                    ..
+                },
+            ..
+        } => {
+            // Cancel on release so the release does not also close the menu
+            if *state == ElementState::Released {
+                ctx.data_mut(|data| data.remove::<Id>(target_id));
+            }
+            return true;
+        }
+        WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(key_code),
+                    repeat: false,
+                    ..
+                },
+            ..
+        } => Control::Key(*key_code),
+        WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button,
+            ..
+        } => {
+            let capture_rect = ctx.data(|data| data.get_temp::<Rect>(Id::new(CAPTURE_RECT_ID)));
+            let pointer = ctx.input(|i| i.pointer.latest_pos());
+            let is_over_button = capture_rect
+                .zip(pointer)
+                .is_some_and(|(rect, pointer)| rect.contains(pointer));
+            if !is_over_button {
+                // Let the click through so buttons like Back still work
+                ctx.data_mut(|data| data.remove::<Id>(target_id));
+                return false;
+            }
+            Control::Mouse(*button)
+        }
+        _ => return false,
+    };
+    finish_capture(ctx, target, control);
+    true
+}
+
+/// Hands the next gamepad button or push of a stick to the control button waiting for one
+pub fn capture_gamepad_event(ctx: &Context, event: &GamepadEvent) -> bool {
+    let Some(target) = ctx.data(|data| data.get_temp::<Id>(Id::new(CAPTURE_TARGET_ID))) else {
+        return false;
+    };
+    let control = match *event {
+        GamepadEvent::Button(button, true) => Control::GamepadButton(button),
+        GamepadEvent::Axis(axis, value) if value.abs() > CAPTURE_AXIS_THRESHOLD => {
+            let direction = if value > 0.0 {
+                AxisDirection::Positive
+            } else {
+                AxisDirection::Negative
+            };
+            Control::GamepadAxis(axis, direction)
+        }
+        _ => return false,
+    };
+    finish_capture(ctx, target, control);
+    true
+}
+
+fn finish_capture(ctx: &Context, target: Id, control: Control) {
+    ctx.data_mut(|data| {
+        data.remove::<Id>(Id::new(CAPTURE_TARGET_ID));
+        data.insert_temp(Id::new(CAPTURED_CONTROL_ID), (target, control));
+    });
+}
+
+/// Button showing a control, click it and then press any key or gamepad button, or click it again with any mouse button, to change it.
+/// Escape or clicking somewhere else cancels. The response is marked changed when the control changed.
+pub fn control_button(ui: &mut Ui, control: &mut Control, id: Id) -> Response {
+    let target_id = Id::new(CAPTURE_TARGET_ID);
+    let captured_id = Id::new(CAPTURED_CONTROL_ID);
+    let mut changed = false;
+    if let Some((target, captured)) = ui.data(|data| data.get_temp::<(Id, Control)>(captured_id))
+        && target == id
+    {
+        ui.data_mut(|data| data.remove::<(Id, Control)>(captured_id));
+        changed = *control != captured;
+        *control = captured;
+    }
+    let is_waiting = ui.data(|data| data.get_temp::<Id>(target_id)) == Some(id);
+    let text = if is_waiting {
+        "Press a key...".to_string()
+    } else {
+        control.to_string()
+    };
+    let mut response = ui
+        .add(Button::new(text).selected(is_waiting))
+        .on_hover_text(
+            "Click, then press any key or gamepad button, or click here with any mouse button. \
+            Escape or clicking elsewhere cancels.",
+        );
+    if response.clicked() && !is_waiting {
+        ui.data_mut(|data| data.insert_temp(target_id, id));
+    }
+    if is_waiting || response.clicked() {
+        ui.data_mut(|data| data.insert_temp(Id::new(CAPTURE_RECT_ID), response.rect));
+    }
+    if changed {
+        response.mark_changed();
+    }
+    response
+}
+
+/// Names of everything bound to a control, the settings controls and every ability keybind in the deck
+fn get_bindings(settings: &Settings, deck: &Deck) -> Vec<(String, Control)> {
+    let controls = &settings.movement_controls;
+    let mut bindings: Vec<(String, Control)> = [
+        ("Forward", &controls.forward),
+        ("Backward", &controls.backward),
+        ("Left", &controls.left),
+        ("Right", &controls.right),
+        ("Jump", &controls.jump),
+        ("Crouch", &controls.crouch),
+        ("Interact", &controls.interact),
+        ("Fullscreen", &settings.fullscreen_toggle),
+    ]
+    .into_iter()
+    .map(|(name, control)| (name.to_string(), control.clone()))
+    .collect();
+    let extra_bindings = controls
+        .extra_bindings
+        .iter()
+        .chain(settings.gamepad_settings.bindings.iter());
+    for binding in extra_bindings {
+        if let Some(control) = binding.input.get_control() {
+            bindings.push((format!("{:?}", binding.action), control));
+        }
+    }
+    for (cooldown_idx, cooldown) in deck.cooldowns.iter().enumerate() {
+        for (ability_idx, ability) in cooldown.abilities.iter().enumerate() {
+            for control in ability.keybind.get_controls() {
+                bindings.push((
+                    format!("Cooldown {} ability {}", cooldown_idx + 1, ability_idx + 1),
+                    control,
+                ));
+            }
+        }
+    }
+    bindings
+}
+
+/// Names of the other bindings that use `control`
+fn get_conflicts<'a>(
+    bindings: &'a [(String, Control)],
+    name: &str,
+    control: &Control,
+) -> Vec<&'a str> {
+    bindings
+        .iter()
+        .filter(|(other_name, other)| other_name != name && other == control)
+        .map(|(other_name, _)| other_name.as_str())
+        .collect()
+}
+
+/// Rebinds the movement and window controls, warning when a control is also used by something else
+pub fn controls_editor(ui: &mut Ui, settings: &mut Settings, deck: &Deck) {
+    let bindings = get_bindings(settings, deck);
+    let defaults = Settings::default();
+    let default_controls = &defaults.movement_controls;
+    let controls = &mut settings.movement_controls;
+    let rows = [
+        ("Forward", &mut controls.forward, &default_controls.forward),
+        (
+            "Backward",
+            &mut controls.backward,
+            &default_controls.backward,
+        ),
+        ("Left", &mut controls.left, &default_controls.left),
+        ("Right", &mut controls.right, &default_controls.right),
+        ("Jump", &mut controls.jump, &default_controls.jump),
+        ("Crouch", &mut controls.crouch, &default_controls.crouch),
+        (
+            "Interact",
+            &mut controls.interact,
+            &default_controls.interact,
+        ),
+        (
+            "Fullscreen",
+            &mut settings.fullscreen_toggle,
+            &defaults.fullscreen_toggle,
+        ),
+    ];
+    egui::Grid::new("controls_editor")
+        .num_columns(4)
+        .show(ui, |ui| {
+            for (name, control, default) in rows {
+                ui.label(name);
+                control_button(ui, control, Id::new("controls_editor").with(name));
+                let conflicts = get_conflicts(&bindings, name, control);
+                if conflicts.is_empty() {
+                    ui.label("");
+                } else {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        format!("Also used by {}", conflicts.join(", ")),
+                    );
+                }
+                if ui
+                    .add_enabled(*control != *default, Button::new("Reset"))
+                    .on_hover_text(format!("Reset to {}", default))
+                    .clicked()
+                {
+                    *control = default.clone();
+                }
+                ui.end_row();
+            }
+        });
+    ui.label("Mouse look");
+    let controls = &mut settings.movement_controls;
+    egui::Grid::new("mouse_look_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Yaw sensitivity");
+            ui.add(
+                DragValue::new(&mut controls.yaw_sensitivity)
+                    .speed(0.0005)
+                    .range(0.0001..=1.0),
+            );
+            ui.end_row();
+            ui.label("Pitch sensitivity");
+            ui.add(
+                DragValue::new(&mut controls.pitch_sensitivity)
+                    .speed(0.0005)
+                    .range(0.0001..=1.0),
+            );
+            ui.end_row();
+            ui.label("Invert Y");
+            ui.checkbox(&mut controls.invert_y, "");
+            ui.end_row();
+            ui.label("Acceleration");
+            ui.add(
+                DragValue::new(&mut controls.acceleration)
+                    .speed(0.01)
+                    .range(0.0..=10.0),
+            )
+            .on_hover_text("Extra sensitivity per 1000 pixels per second of mouse speed, 0 is off");
+            ui.end_row();
+            ui.label("Max acceleration");
+            ui.add_enabled(
+                controls.acceleration > 0.0,
+                DragValue::new(&mut controls.max_acceleration)
+                    .speed(0.05)
+                    .range(1.0..=20.0)
+                    .suffix("x"),
+            );
+            ui.end_row();
+        });
+    ui.label("Gamepad");
+    let gamepad = &mut settings.gamepad_settings;
+    egui::Grid::new("gamepad_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Deadzone");
+            ui.add(
+                DragValue::new(&mut gamepad.deadzone)
+                    .speed(0.005)
+                    .range(0.0..=0.95),
+            );
+            ui.end_row();
+            ui.label("Look speed");
+            ui.add(
+                DragValue::new(&mut gamepad.look_speed)
+                    .speed(5.0)
+                    .range(1.0..=5000.0),
+            )
+            .on_hover_text("Pixels of mouse movement per second with the stick pushed all the way");
+            ui.end_row();
+            ui.label("Look curve");
+            ui.add(
+                DragValue::new(&mut gamepad.look_curve)
+                    .speed(0.01)
+                    .range(0.2..=5.0),
+            )
+            .on_hover_text(
+                "1 turns in proportion to the stick, higher values give finer aim near the center",
+            );
+            ui.end_row();
+        });
+    if ui.button("Reset All Controls").clicked() {
+        settings.movement_controls = ControlSettings::default();
+        settings.gamepad_settings = GamepadSettings::default();
+        settings.fullscreen_toggle = defaults.fullscreen_toggle.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::{GamepadAxis, GamepadButton};
+
+    #[test]
+    fn conflicts_list_every_other_use_of_a_control() {
+        let mut settings = Settings::default();
+        settings.movement_controls.jump = Control::Key(KeyCode::KeyW);
+        let deck: Deck = ron::from_str(
+            "(
+                cooldowns: [(modifiers: [], abilities: [
+                    (card: None, keybind: And(Pressed(Key(KeyE)), Not(Pressed(Key(KeyW))))),
+                ])],
+                passive: (passive_effects: []),
+            )",
+        )
+        .unwrap();
+        let bindings = get_bindings(&settings, &deck);
+
+        assert_eq!(
+            get_conflicts(&bindings, "Jump", &Control::Key(KeyCode::KeyW)),
+            vec!["Forward", "Cooldown 1 ability 1"]
+        );
+        assert_eq!(
+            get_conflicts(&bindings, "Interact", &Control::Key(KeyCode::KeyE)),
+            vec!["Cooldown 1 ability 1"]
+        );
+        assert!(get_conflicts(&bindings, "Crouch", &settings.movement_controls.crouch).is_empty());
+    }
+
+    #[test]
+    fn gamepad_captures_go_to_the_waiting_button() {
+        let ctx = Context::default();
+        let target = Id::new("controls_editor").with("Jump");
+        let event = GamepadEvent::Button(GamepadButton::South, true);
+        assert!(!capture_gamepad_event(&ctx, &event));
+
+        ctx.data_mut(|data| data.insert_temp(Id::new(CAPTURE_TARGET_ID), target));
+        // Stick drift and releases don't count
+        assert!(!capture_gamepad_event(
+            &ctx,
+            &GamepadEvent::Axis(GamepadAxis::LeftStickX, 0.2)
+        ));
+        assert!(!capture_gamepad_event(
+            &ctx,
+            &GamepadEvent::Button(GamepadButton::East, false)
+        ));
+        assert!(capture_gamepad_event(&ctx, &event));
+        assert_eq!(
+            ctx.data(|data| data.get_temp::<(Id, Control)>(Id::new(CAPTURED_CONTROL_ID))),
+            Some((target, Control::GamepadButton(GamepadButton::South)))
+        );
+        // Only the first press is captured
+        assert!(!capture_gamepad_event(&ctx, &event));
+    }
+}
@@ -0,0 +1,2101 @@
+use crate::card_system::{
+    Ability, BaseCard, Cooldown, CooldownModifier, Deck, DirectionCard, DragableCard, Effect,
+    Keybind, MultiCastModifier, PassiveCard, ProjectileModifier, SimpleStatusEffectType,
+    StatusEffect,
+};
+
+#[derive(Debug)]
+pub enum DragableType {
+    ProjectileModifier,
+    MultiCastModifier,
+    CooldownModifier,
+    StatusEffect,
+    BaseCard,
+    Direction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropableType {
+    MultiCastBaseCard,
+    BaseNone,
+    BaseProjectile,
+    BaseStatusEffects,
+    Cooldown,
+    Direction,
+    Palette,
+}
+
+#[derive(Debug)]
+pub enum ModificationType {
+    Add,
+    Remove,
+    /// Sets the count to a value typed or dragged in the editor
+    Set(i64),
+}
+
+pub fn is_valid_drag(from: &DragableType, to: &DropableType) -> bool {
+    match (from, to) {
+        (DragableType::ProjectileModifier, DropableType::BaseProjectile) => true,
+        (DragableType::StatusEffect, DropableType::BaseStatusEffects) => true,
+        (DragableType::MultiCastModifier, DropableType::MultiCastBaseCard) => true,
+        (DragableType::BaseCard, DropableType::MultiCastBaseCard) => true,
+        (DragableType::BaseCard, DropableType::BaseNone) => true,
+        (DragableType::CooldownModifier, DropableType::Cooldown) => true,
+        (DragableType::BaseCard, DropableType::Cooldown) => true,
+        (DragableType::Direction, DropableType::Direction) => true,
+        (_, DropableType::Palette) => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckSlot {
+    Passive,
+    Cooldown(usize),
+}
+
+/// Location of a card within a deck. The path is root first, starting below the slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeckPath {
+    pub slot: DeckSlot,
+    pub path: Vec<usize>,
+}
+
+impl DeckPath {
+    pub fn new(slot: DeckSlot, path: Vec<usize>) -> Self {
+        DeckPath { slot, path }
+    }
+
+    /// Converts a path from the card editor where the first index is 0 for the palette, 1 for the passive
+    /// and the cooldown index offset by 2. Returns `None` for palette paths since those are not part of the deck.
+    pub fn from_editor_path(editor_path: &[usize]) -> Option<DeckPath> {
+        let (&root, path) = editor_path.split_first()?;
+        let slot = match root {
+            0 => return None,
+            1 => DeckSlot::Passive,
+            cooldown_idx => DeckSlot::Cooldown(cooldown_idx - 2),
+        };
+        Some(DeckPath::new(slot, path.to_vec()))
+    }
+
+    pub fn contains(&self, other: &DeckPath) -> bool {
+        self.slot == other.slot && other.path.starts_with(&self.path)
+    }
+
+    /// The card methods pop indices from the end of the path, so they take it leaf first
+    fn to_card_path(&self) -> Vec<usize> {
+        self.path.iter().rev().copied().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DeckEdit {
+    /// Moves a card out of one place in the deck into another
+    Move {
+        from: DeckPath,
+        to: DeckPath,
+    },
+    /// Inserts a new card, such as one from the palette
+    Insert {
+        to: DeckPath,
+        card: DragableCard,
+    },
+    /// Removes a card, or a whole cooldown when the path points at the cooldown itself
+    Remove {
+        from: DeckPath,
+    },
+    Increment {
+        at: DeckPath,
+    },
+    Decrement {
+        at: DeckPath,
+    },
+    SetValue {
+        at: DeckPath,
+        value: i64,
+    },
+    /// Replaces the card at a path as is, without merging stacks or removing empty cards
+    Set {
+        at: DeckPath,
+        card: DragableCard,
+    },
+    SetKeybind {
+        cooldown: usize,
+        ability: usize,
+        keybind: Keybind,
+    },
+    InsertCooldown {
+        idx: usize,
+        cooldown: Cooldown,
+    },
+    SetCooldown {
+        idx: usize,
+        cooldown: Cooldown,
+    },
+    SetPassive {
+        passive: PassiveCard,
+    },
+    /// Replaces the whole deck, such as with one typed into the text editor
+    SetDeck {
+        deck: Box<Deck>,
+    },
+}
+
+impl Deck {
+    /// Applies an edit, leaving the deck untouched if the edit is invalid.
+    /// Returns the removed card for `DeckEdit::Remove`.
+    pub fn apply_edit(&mut self, edit: DeckEdit) -> Result<Option<DragableCard>, String> {
+        self.apply_edit_with_inverse(edit)
+            .map(|(removed, _)| removed)
+    }
+
+    /// Like `apply_edit`, also returning the edits that undo it in the order they need to be applied
+    pub fn apply_edit_with_inverse(
+        &mut self,
+        edit: DeckEdit,
+    ) -> Result<(Option<DragableCard>, Vec<DeckEdit>), String> {
+        let inverse = self.get_inverse(&edit)?;
+        match self.apply_edit_in_place(edit) {
+            Ok(removed) => Ok((removed, inverse)),
+            Err(e) => {
+                // A move takes, inserts and cleans up in turn, so put back whatever went through before the failure
+                for undo_edit in inverse {
+                    self.apply_edit_in_place(undo_edit)
+                        .map_err(|undo_error| format!("{} and could not be undone: {}", e, undo_error))?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Edits that put back everything `edit` can change, read from the deck before it is applied
+    fn get_inverse(&self, edit: &DeckEdit) -> Result<Vec<DeckEdit>, String> {
+        Ok(match edit {
+            DeckEdit::Move { from, to } => {
+                let from_container = self.get_container(from);
+                if from_container.slot == to.slot {
+                    vec![self.get_restore(&self.get_common_container(&from_container, to))?]
+                } else {
+                    vec![self.get_restore(&from_container)?, self.get_restore(to)?]
+                }
+            }
+            DeckEdit::Insert { to, .. } => vec![self.get_restore(to)?],
+            DeckEdit::Remove { from } if from.path.is_empty() => match from.slot {
+                DeckSlot::Cooldown(idx) => vec![DeckEdit::InsertCooldown {
+                    idx,
+                    cooldown: self.get_cooldown(idx)?.clone(),
+                }],
+                DeckSlot::Passive => return Err(format!("Cannot remove {:?}", from.slot)),
+            },
+            DeckEdit::Remove { from } => vec![self.get_restore(&self.get_container(from))?],
+            DeckEdit::Increment { at }
+            | DeckEdit::Decrement { at }
+            | DeckEdit::SetValue { at, .. }
+            | DeckEdit::Set { at, .. } => vec![self.get_restore(at)?],
+            DeckEdit::SetKeybind {
+                cooldown, ability, ..
+            } => vec![DeckEdit::SetKeybind {
+                cooldown: *cooldown,
+                ability: *ability,
+                keybind: self.get_ability(*cooldown, *ability)?.keybind.clone(),
+            }],
+            DeckEdit::InsertCooldown { idx, .. } => vec![DeckEdit::Remove {
+                from: DeckPath::new(DeckSlot::Cooldown(*idx), vec![]),
+            }],
+            DeckEdit::SetCooldown { idx, .. } => {
+                vec![self.get_restore(&DeckPath::new(DeckSlot::Cooldown(*idx), vec![]))?]
+            }
+            DeckEdit::SetPassive { .. } => {
+                vec![self.get_restore(&DeckPath::new(DeckSlot::Passive, vec![]))?]
+            }
+            DeckEdit::SetDeck { .. } => vec![DeckEdit::SetDeck {
+                deck: Box::new(self.clone()),
+            }],
+        })
+    }
+
+    /// An edit that puts back the card or slot at a path as it is now
+    fn get_restore(&self, path: &DeckPath) -> Result<DeckEdit, String> {
+        if !path.path.is_empty() {
+            return Ok(DeckEdit::Set {
+                at: path.clone(),
+                card: self
+                    .get_card(path)
+                    .ok_or_else(|| format!("Nothing to edit at {:?}", path))?,
+            });
+        }
+        Ok(match path.slot {
+            DeckSlot::Passive => DeckEdit::SetPassive {
+                passive: self.passive.clone(),
+            },
+            DeckSlot::Cooldown(idx) => DeckEdit::SetCooldown {
+                idx,
+                cooldown: self.get_cooldown(idx)?.clone(),
+            },
+        })
+    }
+
+    /// The closest card or slot holding the card at a path, which is what removing the card changes
+    fn get_container(&self, path: &DeckPath) -> DeckPath {
+        let mut container = path.clone();
+        container.path.pop();
+        self.get_card_or_slot(container)
+    }
+
+    /// The closest card or slot holding both paths
+    fn get_common_container(&self, a: &DeckPath, b: &DeckPath) -> DeckPath {
+        let common_path = a
+            .path
+            .iter()
+            .zip(b.path.iter())
+            .take_while(|(a, b)| a == b)
+            .map(|(idx, _)| *idx)
+            .collect();
+        self.get_card_or_slot(DeckPath::new(a.slot, common_path))
+    }
+
+    /// Shortens a path until it points at a card, cooldowns and multicasts have group indices that are not cards
+    fn get_card_or_slot(&self, mut path: DeckPath) -> DeckPath {
+        while !path.path.is_empty() && self.get_card(&path).is_none() {
+            path.path.pop();
+        }
+        path
+    }
+
+    fn apply_edit_in_place(&mut self, edit: DeckEdit) -> Result<Option<DragableCard>, String> {
+        match edit {
+            DeckEdit::Move { from, to } => {
+                let card = self
+                    .get_card(&from)
+                    .filter(|card| !card.has_placeholder())
+                    .ok_or_else(|| format!("Nothing to move at {:?}", from))?;
+                if from.contains(&to) {
+                    return Err("Cannot move a card into itself".to_string());
+                }
+                if let Some(DragableCard::Direction(direction)) = self.get_card(&to) {
+                    if !matches!(direction, DirectionCard::None) {
+                        return Err(format!("Direction {} is already set", direction));
+                    }
+                }
+                self.check_drop(&to, &card)?;
+                let card = self.take_card(&from)?;
+                self.insert_card(&to, card)?;
+                self.cleanup_card(&from)?;
+                Ok(None)
+            }
+            DeckEdit::Insert { to, card } => {
+                if card.has_placeholder() {
+                    return Err(format!("Cannot insert incomplete card {:?}", card));
+                }
+                self.check_drop(&to, &card)?;
+                self.insert_card(&to, card)?;
+                Ok(None)
+            }
+            DeckEdit::Remove { from } => {
+                if from.path.is_empty() {
+                    return match from.slot {
+                        DeckSlot::Cooldown(idx) if idx < self.cooldowns.len() => {
+                            self.cooldowns.remove(idx);
+                            Ok(None)
+                        }
+                        _ => Err(format!("Cannot remove {:?}", from.slot)),
+                    };
+                }
+                if self.get_card(&from).is_none() {
+                    return Err(format!("Nothing to remove at {:?}", from));
+                }
+                let card = self.take_card(&from)?;
+                self.cleanup_card(&from)?;
+                Ok(Some(card))
+            }
+            DeckEdit::Increment { at } => self.modify_card(&at, ModificationType::Add),
+            DeckEdit::Decrement { at } => self.modify_card(&at, ModificationType::Remove),
+            DeckEdit::SetValue { at, value } => self.modify_card(&at, ModificationType::Set(value)),
+            DeckEdit::Set { at, card } => {
+                let mut card_path = at.to_card_path();
+                match at.slot {
+                    DeckSlot::Passive => self.passive.replace_from_path(&mut card_path, card)?,
+                    DeckSlot::Cooldown(idx) => self
+                        .get_cooldown_mut(idx)?
+                        .replace_from_path(&mut card_path, card)?,
+                }
+                Ok(None)
+            }
+            DeckEdit::SetKeybind {
+                cooldown,
+                ability,
+                keybind,
+            } => {
+                let cooldown = self.get_cooldown_mut(cooldown)?;
+                let ability = get_item(&mut cooldown.abilities, ability)?;
+                ability.keybind = keybind;
+                Ok(None)
+            }
+            DeckEdit::InsertCooldown { idx, cooldown } => {
+                if idx > self.cooldowns.len() {
+                    return Err(format!("Cannot insert cooldown {}", idx));
+                }
+                self.cooldowns.insert(idx, cooldown);
+                Ok(None)
+            }
+            DeckEdit::SetCooldown { idx, cooldown } => {
+                *self.get_cooldown_mut(idx)? = cooldown;
+                Ok(None)
+            }
+            DeckEdit::SetPassive { passive } => {
+                self.passive = passive;
+                Ok(None)
+            }
+            DeckEdit::SetDeck { deck } => {
+                *self = *deck;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Checks that a deck built outside the editor, such as one parsed from text, has no palette or placeholder cards
+    pub fn check_complete(&self) -> Result<(), String> {
+        if let Some(effect) = self.passive.passive_effects.iter().find(|e| e.has_placeholder()) {
+            return Err(format!("Incomplete passive effect {:?}", effect));
+        }
+        for (cooldown_idx, cooldown) in self.cooldowns.iter().enumerate() {
+            if cooldown
+                .modifiers
+                .iter()
+                .any(|modifier| matches!(modifier, CooldownModifier::None))
+            {
+                return Err(format!("Cooldown {} has an empty modifier", cooldown_idx + 1));
+            }
+            if cooldown.abilities.iter().any(|ability| ability.card.has_placeholder()) {
+                return Err(format!("Cooldown {} has an incomplete card", cooldown_idx + 1));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_card(&self, path: &DeckPath) -> Option<DragableCard> {
+        let mut card_path = path.to_card_path();
+        match path.slot {
+            DeckSlot::Passive => self.passive.get_from_path(&mut card_path),
+            DeckSlot::Cooldown(idx) => self.cooldowns.get(idx)?.get_from_path(&mut card_path),
+        }
+    }
+
+    pub fn get_drop_type(&self, path: &DeckPath) -> Option<DropableType> {
+        if path.path.is_empty() {
+            return match path.slot {
+                DeckSlot::Passive => Some(DropableType::BaseStatusEffects),
+                DeckSlot::Cooldown(idx) => {
+                    (idx < self.cooldowns.len()).then_some(DropableType::Cooldown)
+                }
+            };
+        }
+        match self.get_card(path)? {
+            DragableCard::BaseCard(BaseCard::Projectile(_)) => Some(DropableType::BaseProjectile),
+            DragableCard::BaseCard(BaseCard::MultiCast(..)) => {
+                Some(DropableType::MultiCastBaseCard)
+            }
+            DragableCard::BaseCard(BaseCard::StatusEffects(..)) => {
+                Some(DropableType::BaseStatusEffects)
+            }
+            DragableCard::BaseCard(BaseCard::None) => Some(DropableType::BaseNone),
+            DragableCard::Direction(_) => Some(DropableType::Direction),
+            _ => None,
+        }
+    }
+
+    fn check_drop(&self, to: &DeckPath, card: &DragableCard) -> Result<(), String> {
+        let drop_type = self
+            .get_drop_type(to)
+            .ok_or_else(|| format!("Cannot drop onto {:?}", to))?;
+        if !is_valid_drag(&card.get_type(), &drop_type) {
+            return Err(format!(
+                "Cannot drop {:?} onto {:?}",
+                card.get_type(),
+                drop_type
+            ));
+        }
+        Ok(())
+    }
+
+    fn get_cooldown(&self, idx: usize) -> Result<&Cooldown, String> {
+        self.cooldowns.get(idx).ok_or_else(|| {
+            format!("Cooldown {} is out of range for {} cooldowns", idx, self.cooldowns.len())
+        })
+    }
+
+    fn get_ability(&self, cooldown: usize, ability: usize) -> Result<&Ability, String> {
+        let abilities = &self.get_cooldown(cooldown)?.abilities;
+        abilities.get(ability).ok_or_else(|| {
+            format!("Ability {} is out of range for {} abilities", ability, abilities.len())
+        })
+    }
+
+    fn get_cooldown_mut(&mut self, idx: usize) -> Result<&mut Cooldown, String> {
+        let len = self.cooldowns.len();
+        self.cooldowns
+            .get_mut(idx)
+            .ok_or_else(|| format!("Cooldown {} is out of range for {} cooldowns", idx, len))
+    }
+
+    fn take_card(&mut self, path: &DeckPath) -> Result<DragableCard, String> {
+        let mut card_path = path.to_card_path();
+        match path.slot {
+            DeckSlot::Passive => self.passive.take_from_path(&mut card_path),
+            DeckSlot::Cooldown(idx) => self.get_cooldown_mut(idx)?.take_from_path(&mut card_path),
+        }
+    }
+
+    fn insert_card(&mut self, path: &DeckPath, card: DragableCard) -> Result<(), String> {
+        let mut card_path = path.to_card_path();
+        match path.slot {
+            DeckSlot::Passive => self.passive.insert_to_path(&mut card_path, card),
+            DeckSlot::Cooldown(idx) => self
+                .get_cooldown_mut(idx)?
+                .insert_to_path(&mut card_path, card),
+        }
+    }
+
+    fn cleanup_card(&mut self, path: &DeckPath) -> Result<(), String> {
+        let mut card_path = path.to_card_path();
+        match path.slot {
+            DeckSlot::Passive => self.passive.cleanup(&mut card_path),
+            DeckSlot::Cooldown(idx) => self.get_cooldown_mut(idx)?.cleanup(&mut card_path),
+        }
+    }
+
+    fn modify_card(
+        &mut self,
+        path: &DeckPath,
+        modification_type: ModificationType,
+    ) -> Result<Option<DragableCard>, String> {
+        match self.get_card(path) {
+            None => return Err(format!("Nothing to modify at {:?}", path)),
+            // Directions have no count and share their path with the card holding them
+            Some(DragableCard::Direction(_)) => return Ok(None),
+            Some(_) => {}
+        }
+        let mut card_path = path.to_card_path();
+        match path.slot {
+            DeckSlot::Passive => self
+                .passive
+                .modify_from_path(&mut card_path, modification_type)?,
+            DeckSlot::Cooldown(idx) => self
+                .get_cooldown_mut(idx)?
+                .modify_from_path(&mut card_path, modification_type)?,
+        }
+        Ok(None)
+    }
+}
+
+impl DragableCard {
+    pub fn get_type(&self) -> DragableType {
+        match self {
+            DragableCard::BaseCard(_) => DragableType::BaseCard,
+            DragableCard::CooldownModifier(_) => DragableType::CooldownModifier,
+            DragableCard::MultiCastModifier(_) => DragableType::MultiCastModifier,
+            DragableCard::ProjectileModifier(_) => DragableType::ProjectileModifier,
+            DragableCard::StatusEffect(_) => DragableType::StatusEffect,
+            DragableCard::Direction(_) => DragableType::Direction,
+        }
+    }
+
+    /// Whether this card is or contains an empty placeholder that should never end up in a deck
+    pub fn has_placeholder(&self) -> bool {
+        match self {
+            DragableCard::BaseCard(BaseCard::None) => true,
+            DragableCard::BaseCard(card) => card.has_placeholder(),
+            DragableCard::CooldownModifier(modifier) => {
+                matches!(modifier, CooldownModifier::None)
+            }
+            DragableCard::MultiCastModifier(modifier) => {
+                matches!(modifier, MultiCastModifier::None)
+            }
+            DragableCard::ProjectileModifier(modifier) => modifier.has_placeholder(),
+            DragableCard::StatusEffect(effect) => effect.has_placeholder(),
+            DragableCard::Direction(direction) => matches!(direction, DirectionCard::None),
+        }
+    }
+}
+
+impl BaseCard {
+    pub fn has_placeholder(&self) -> bool {
+        match self {
+            BaseCard::Palette(_) => true,
+            BaseCard::Projectile(modifiers) => modifiers.iter().any(|m| m.has_placeholder()),
+            BaseCard::MultiCast(cards, modifiers) => {
+                cards.iter().any(|card| card.has_placeholder())
+                    || modifiers
+                        .iter()
+                        .any(|modifier| matches!(modifier, MultiCastModifier::None))
+            }
+            BaseCard::StatusEffects(_, effects) => effects.iter().any(|e| e.has_placeholder()),
+            BaseCard::CreateMaterial(_)
+            | BaseCard::Effect(_)
+            | BaseCard::Trigger(_)
+            | BaseCard::None => false,
+        }
+    }
+}
+
+impl ProjectileModifier {
+    fn has_placeholder(&self) -> bool {
+        match self {
+            ProjectileModifier::None => true,
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => card.has_placeholder(),
+            _ => false,
+        }
+    }
+}
+
+impl StatusEffect {
+    fn has_placeholder(&self) -> bool {
+        match self {
+            StatusEffect::None => true,
+            StatusEffect::OnHit(card) => card.has_placeholder(),
+            _ => false,
+        }
+    }
+}
+
+/// Path based editing shared by every card type. Paths are passed leaf first so each level pops its own index.
+/// Paths that do not match the card give an error, the card may be partly edited by then so callers should work on a copy.
+pub trait EditableCard {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String>;
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String>;
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String>;
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String>;
+    /// Swaps the card at the path for another of the same kind, following the same path as `get_from_path`
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String>;
+    /// Returns a copy of what `take_from_path` would take, or `None` if the path does not exist
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard>;
+}
+
+fn pop_index(path: &mut Vec<usize>) -> Result<usize, String> {
+    path.pop()
+        .ok_or_else(|| "Path ends before reaching a card".to_string())
+}
+
+fn expect_index(path: &mut Vec<usize>, expected: usize) -> Result<(), String> {
+    match pop_index(path)? {
+        idx if idx == expected => Ok(()),
+        idx => Err(format!("Invalid path index {}, expected {}", idx, expected)),
+    }
+}
+
+fn expect_end(path: &[usize]) -> Result<(), String> {
+    if path.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Path {:?} continues past the card", path))
+    }
+}
+
+fn set_count<T: TryFrom<i64>>(count: &mut T, value: i64) {
+    if let Ok(value) = T::try_from(value) {
+        *count = value;
+    }
+}
+
+fn get_item<T>(items: &mut [T], idx: usize) -> Result<&mut T, String> {
+    let len = items.len();
+    items
+        .get_mut(idx)
+        .ok_or_else(|| format!("Path index {} is out of range for {} cards", idx, len))
+}
+
+impl EditableCard for Cooldown {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        self.cooldown_value = None;
+        match pop_index(path)? {
+            0 => {
+                let idx = pop_index(path)?;
+                get_item(&mut self.modifiers, idx)?.modify_from_path(path, modification_type)
+            }
+            1 => {
+                let idx = pop_index(path)?;
+                let ability = get_item(&mut self.abilities, idx)?;
+                ability.card.modify_from_path(path, modification_type)?;
+                ability.invalidate_cooldown_cache();
+                Ok(())
+            }
+            type_idx => Err(format!("Invalid cooldown path index {}", type_idx)),
+        }
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        self.cooldown_value = None;
+        match pop_index(path)? {
+            0 => {
+                let idx = pop_index(path)?;
+                get_item(&mut self.modifiers, idx)?.take_from_path(path)
+            }
+            1 => {
+                let idx = pop_index(path)?;
+                let ability = get_item(&mut self.abilities, idx)?;
+                let result = if path.is_empty() {
+                    DragableCard::BaseCard(std::mem::replace(&mut ability.card, BaseCard::None))
+                } else {
+                    ability.card.take_from_path(path)?
+                };
+                ability.invalidate_cooldown_cache();
+                Ok(result)
+            }
+            type_idx => Err(format!("Invalid cooldown path index {}", type_idx)),
+        }
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        self.cooldown_value = None;
+        if path.is_empty() {
+            match item {
+                DragableCard::BaseCard(item) => {
+                    self.abilities.push(Ability {
+                        card: item,
+                        ..Default::default()
+                    });
+                }
+                DragableCard::CooldownModifier(modifier_item) => {
+                    let mut combined = false;
+                    match modifier_item.clone() {
+                        CooldownModifier::SimpleCooldownModifier(last_type, last_s) => {
+                            for modifier in self.modifiers.iter_mut() {
+                                match modifier {
+                                    CooldownModifier::SimpleCooldownModifier(current_type, s)
+                                        if *current_type == last_type =>
+                                    {
+                                        *s += last_s;
+                                        combined = true;
+                                        break;
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        CooldownModifier::SignedSimpleCooldownModifier(last_type, last_s) => {
+                            for modifier in self.modifiers.iter_mut() {
+                                match modifier {
+                                    CooldownModifier::SignedSimpleCooldownModifier(
+                                        current_type,
+                                        s,
+                                    ) if *current_type == last_type => {
+                                        *s += last_s;
+                                        combined = true;
+                                        break;
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    if !combined {
+                        self.modifiers.push(modifier_item);
+                    }
+                }
+                item => {
+                    return Err(format!("Cannot insert {:?} into a cooldown", item.get_type()));
+                }
+            }
+            Ok(())
+        } else {
+            expect_index(path, 1)?;
+            let idx = pop_index(path)?;
+            let ability = get_item(&mut self.abilities, idx)?;
+            ability.card.insert_to_path(path, item)?;
+            ability.invalidate_cooldown_cache();
+            Ok(())
+        }
+    }
+
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String> {
+        if path.is_empty() {
+            return Ok(());
+        }
+        match pop_index(path)? {
+            0 => {
+                let idx = pop_index(path)?;
+                expect_end(path)?;
+                let is_empty = match get_item(&mut self.modifiers, idx)? {
+                    CooldownModifier::None => true,
+                    CooldownModifier::SimpleCooldownModifier(_, s) => *s == 0,
+                    CooldownModifier::SignedSimpleCooldownModifier(_, s) => *s == 0,
+                    _ => false,
+                };
+                if is_empty {
+                    self.modifiers.remove(idx);
+                }
+            }
+            1 => {
+                let idx = pop_index(path)?;
+                let ability = get_item(&mut self.abilities, idx)?;
+                if path.is_empty() {
+                    if matches!(ability.card, BaseCard::None) && self.abilities.len() > 1 {
+                        self.abilities.remove(idx);
+                    }
+                } else {
+                    ability.card.cleanup(path)?;
+                    ability.invalidate_cooldown_cache();
+                }
+            }
+            type_idx => return Err(format!("Invalid cooldown path index {}", type_idx)),
+        }
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        self.cooldown_value = None;
+        match pop_index(path)? {
+            0 => {
+                let idx = pop_index(path)?;
+                get_item(&mut self.modifiers, idx)?.replace_from_path(path, item)
+            }
+            1 => {
+                let idx = pop_index(path)?;
+                let ability = get_item(&mut self.abilities, idx)?;
+                ability.card.replace_from_path(path, item)?;
+                ability.invalidate_cooldown_cache();
+                Ok(())
+            }
+            type_idx => Err(format!("Invalid cooldown path index {}", type_idx)),
+        }
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        match path.pop()? {
+            0 => self.modifiers.get(path.pop()?)?.get_from_path(path),
+            1 => self.abilities.get(path.pop()?)?.card.get_from_path(path),
+            _ => None,
+        }
+    }
+}
+
+impl EditableCard for CooldownModifier {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        expect_end(path)?;
+        match self {
+            CooldownModifier::SimpleCooldownModifier(_, v) => match modification_type {
+                ModificationType::Add => *v += 1,
+                ModificationType::Remove => {
+                    if *v > 1 {
+                        *v -= 1
+                    }
+                }
+                ModificationType::Set(new_value) => set_count(v, new_value),
+            },
+            CooldownModifier::SignedSimpleCooldownModifier(_, v) => match modification_type {
+                ModificationType::Add => *v += 1,
+                ModificationType::Remove => *v -= 1,
+                ModificationType::Set(new_value) => set_count(v, new_value),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        expect_end(path)?;
+        Ok(DragableCard::CooldownModifier(std::mem::replace(
+            self,
+            CooldownModifier::None,
+        )))
+    }
+
+    fn insert_to_path(&mut self, _path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        Err(format!(
+            "Cannot insert {:?} into a cooldown modifier",
+            item.get_type()
+        ))
+    }
+
+    fn cleanup(&mut self, _path: &mut Vec<usize>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        expect_end(path)?;
+        let DragableCard::CooldownModifier(item) = item else {
+            return Err(format!("Cannot replace a cooldown modifier with {:?}", item.get_type()));
+        };
+        *self = item;
+        Ok(())
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        path.is_empty()
+            .then(|| DragableCard::CooldownModifier(self.clone()))
+    }
+}
+
+impl EditableCard for PassiveCard {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        if path.is_empty() {
+            return Ok(());
+        }
+        let effect_idx = pop_index(path)?;
+        get_item(&mut self.passive_effects, effect_idx)?.modify_from_path(path, modification_type)
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        let effect_idx = pop_index(path)?;
+        let effect = get_item(&mut self.passive_effects, effect_idx)?;
+        if path.is_empty() {
+            return Ok(DragableCard::StatusEffect(std::mem::replace(
+                effect,
+                StatusEffect::None,
+            )));
+        }
+        effect.take_from_path(path)
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        if path.is_empty() {
+            let DragableCard::StatusEffect(item) = item else {
+                return Err(format!(
+                    "Cannot insert {:?} into the passive",
+                    item.get_type()
+                ));
+            };
+            if let StatusEffect::SimpleStatusEffect(last_ty, last_s) = item.clone() {
+                let mut combined = false;
+                for effect in self.passive_effects.iter_mut() {
+                    match effect {
+                        StatusEffect::SimpleStatusEffect(ty, s) => {
+                            if last_ty == *ty {
+                                *s += last_s;
+                                combined = true;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if !combined {
+                    self.passive_effects.push(item);
+                }
+            } else {
+                self.passive_effects.push(item);
+            }
+
+            self.passive_effects.retain(|effect| match effect {
+                StatusEffect::SimpleStatusEffect(_, s) => *s != 0,
+                _ => true,
+            });
+            Ok(())
+        } else {
+            let idx = pop_index(path)?;
+            expect_index(path, 0)?;
+            get_item(&mut self.passive_effects, idx)?.insert_to_path(path, item)
+        }
+    }
+
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String> {
+        if path.len() <= 1 {
+            self.passive_effects.retain(|effect| match effect {
+                StatusEffect::None => false,
+                _ => true,
+            });
+        } else {
+            let idx = pop_index(path)?;
+            expect_index(path, 0)?;
+            match get_item(&mut self.passive_effects, idx)? {
+                StatusEffect::OnHit(card_box) => card_box.cleanup(path)?,
+                StatusEffect::SimpleStatusEffect(SimpleStatusEffectType::IncreaseGravity(_), _) => {
+                }
+                invalid => {
+                    return Err(format!("Cannot follow path {} into {:?}", idx, invalid));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        let effect_idx = pop_index(path)?;
+        get_item(&mut self.passive_effects, effect_idx)?.replace_from_path(path, item)
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        self.passive_effects.get(path.pop()?)?.get_from_path(path)
+    }
+}
+
+impl EditableCard for MultiCastModifier {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        expect_end(path)?;
+        match self {
+            MultiCastModifier::None => {}
+            MultiCastModifier::Spread(value) => match modification_type {
+                ModificationType::Add => *value += 1,
+                ModificationType::Remove => {
+                    if *value > 1 {
+                        *value -= 1
+                    }
+                }
+                ModificationType::Set(new_value) => set_count(value, new_value),
+            },
+            MultiCastModifier::Duplication(value) => match modification_type {
+                ModificationType::Add => *value += 1,
+                ModificationType::Remove => {
+                    if *value > 1 {
+                        *value -= 1
+                    }
+                }
+                ModificationType::Set(new_value) => set_count(value, new_value),
+            },
+        }
+        Ok(())
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        expect_end(path)?;
+        Ok(DragableCard::MultiCastModifier(std::mem::replace(
+            self,
+            MultiCastModifier::None,
+        )))
+    }
+
+    fn insert_to_path(&mut self, _path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        Err(format!(
+            "Cannot insert {:?} into a multicast modifier",
+            item.get_type()
+        ))
+    }
+
+    fn cleanup(&mut self, _path: &mut Vec<usize>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        expect_end(path)?;
+        let DragableCard::MultiCastModifier(item) = item else {
+            return Err(format!("Cannot replace a multicast modifier with {:?}", item.get_type()));
+        };
+        *self = item;
+        Ok(())
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        path.is_empty()
+            .then(|| DragableCard::MultiCastModifier(self.clone()))
+    }
+}
+
+impl EditableCard for ProjectileModifier {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        if path.is_empty() {
+            match self {
+                ProjectileModifier::SimpleModify(_type, value) => match modification_type {
+                    ModificationType::Add => *value += 1,
+                    ModificationType::Remove => *value -= 1,
+                    ModificationType::Set(new_value) => set_count(value, new_value),
+                },
+                ProjectileModifier::Charge(_type, value) => match modification_type {
+                    ModificationType::Add => *value += 1,
+                    ModificationType::Remove => {
+                        if *value > 1 {
+                            *value -= 1
+                        }
+                    }
+                    ModificationType::Set(new_value) => set_count(value, new_value),
+                },
+                ProjectileModifier::Trail(frequency, _card) => match modification_type {
+                    ModificationType::Add => *frequency += 1,
+                    ModificationType::Remove => {
+                        if *frequency > 1 {
+                            *frequency -= 1
+                        }
+                    }
+                    ModificationType::Set(new_value) => set_count(frequency, new_value),
+                },
+                ProjectileModifier::OnTrigger(id, _card) => match modification_type {
+                    ModificationType::Add => *id += 1,
+                    ModificationType::Remove => {
+                        if *id > 0 {
+                            *id -= 1
+                        }
+                    }
+                    ModificationType::Set(new_value) => set_count(id, new_value),
+                },
+                ProjectileModifier::FriendlyFire
+                | ProjectileModifier::LockToOwner(_)
+                | ProjectileModifier::NoEnemyFire
+                | ProjectileModifier::PiercePlayers
+                | ProjectileModifier::OnHeadshot(_)
+                | ProjectileModifier::OnHit(_)
+                | ProjectileModifier::OnExpiry(_)
+                | ProjectileModifier::WallBounce
+                | ProjectileModifier::None => {}
+            }
+            Ok(())
+        } else {
+            expect_index(path, 0)?;
+            match self {
+                ProjectileModifier::OnHit(card)
+                | ProjectileModifier::OnHeadshot(card)
+                | ProjectileModifier::OnExpiry(card)
+                | ProjectileModifier::OnTrigger(_, card)
+                | ProjectileModifier::Trail(_, card) => {
+                    card.modify_from_path(path, modification_type)
+                }
+                invalid => Err(format!("Cannot follow path into {:?}", invalid)),
+            }
+        }
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        if path.is_empty() {
+            return Ok(DragableCard::ProjectileModifier(std::mem::replace(
+                self,
+                ProjectileModifier::None,
+            )));
+        }
+        expect_index(path, 0)?;
+        match self {
+            ProjectileModifier::LockToOwner(direction) => direction.take_from_path(path),
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => {
+                if path.is_empty() {
+                    Ok(DragableCard::BaseCard(std::mem::replace(card, BaseCard::None)))
+                } else {
+                    card.take_from_path(path)
+                }
+            }
+            invalid => Err(format!("Cannot take from {:?}", invalid)),
+        }
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        expect_index(path, 0)?;
+        match self {
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => card.insert_to_path(path, item),
+            ProjectileModifier::LockToOwner(direction) => direction.insert_to_path(path, item),
+            invalid => Err(format!("Cannot insert into {:?}", invalid)),
+        }
+    }
+
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String> {
+        expect_index(path, 0)?;
+        match self {
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => card.cleanup(path),
+            ProjectileModifier::LockToOwner(direction) => direction.cleanup(path),
+            invalid => Err(format!(
+                "Cannot follow path {:?} into {:?}",
+                path, invalid
+            )),
+        }
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        if path.is_empty() {
+            let DragableCard::ProjectileModifier(item) = item else {
+                return Err(format!(
+                    "Cannot replace a projectile modifier with {:?}",
+                    item.get_type()
+                ));
+            };
+            *self = item;
+            return Ok(());
+        }
+        expect_index(path, 0)?;
+        match self {
+            ProjectileModifier::LockToOwner(direction) => direction.replace_from_path(path, item),
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => card.replace_from_path(path, item),
+            invalid => Err(format!("Cannot follow path into {:?}", invalid)),
+        }
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        if path.is_empty() {
+            return Some(DragableCard::ProjectileModifier(self.clone()));
+        }
+        if path.pop()? != 0 {
+            return None;
+        }
+        match self {
+            ProjectileModifier::LockToOwner(direction) => direction.get_from_path(path),
+            ProjectileModifier::OnHit(card)
+            | ProjectileModifier::OnHeadshot(card)
+            | ProjectileModifier::OnExpiry(card)
+            | ProjectileModifier::OnTrigger(_, card)
+            | ProjectileModifier::Trail(_, card) => card.get_from_path(path),
+            _ => None,
+        }
+    }
+}
+
+impl EditableCard for StatusEffect {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        match self {
+            StatusEffect::SimpleStatusEffect(_, stacks) => match modification_type {
+                ModificationType::Add => *stacks += 1,
+                ModificationType::Remove => *stacks -= 1,
+                ModificationType::Set(new_value) => set_count(stacks, new_value),
+            },
+            StatusEffect::UnsignedSimpleStatusEffect(_, stacks) => match modification_type {
+                ModificationType::Add => *stacks += 1,
+                ModificationType::Remove => {
+                    if *stacks > 0 {
+                        *stacks -= 1
+                    }
+                }
+                ModificationType::Set(new_value) => set_count(stacks, new_value),
+            },
+            StatusEffect::None
+            | StatusEffect::Invincibility
+            | StatusEffect::Trapped
+            | StatusEffect::Lockout
+            | StatusEffect::Stun => {}
+            StatusEffect::OnHit(card) => {
+                if path.is_empty() {
+                    return Ok(());
+                }
+                expect_index(path, 0)?;
+                return card.modify_from_path(path, modification_type);
+            }
+        }
+        Ok(())
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        match self {
+            StatusEffect::OnHit(card) => {
+                expect_index(path, 0)?;
+                card.take_from_path(path)
+            }
+            StatusEffect::SimpleStatusEffect(
+                SimpleStatusEffectType::IncreaseGravity(direction),
+                _,
+            ) => {
+                expect_index(path, 0)?;
+                direction.take_from_path(path)
+            }
+            invalid => Err(format!("Cannot take from {:?}", invalid)),
+        }
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        match self {
+            StatusEffect::OnHit(card) => card.insert_to_path(path, item),
+            StatusEffect::SimpleStatusEffect(
+                SimpleStatusEffectType::IncreaseGravity(direction),
+                _,
+            ) => direction.insert_to_path(path, item),
+            invalid => Err(format!("Cannot insert into {:?}", invalid)),
+        }
+    }
+
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String> {
+        expect_index(path, 0)?;
+        match self {
+            StatusEffect::OnHit(card) => card.cleanup(path),
+            StatusEffect::SimpleStatusEffect(
+                SimpleStatusEffectType::IncreaseGravity(direction),
+                _,
+            ) => direction.cleanup(path),
+            _ => Ok(()),
+        }
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        if path.is_empty() {
+            let DragableCard::StatusEffect(item) = item else {
+                return Err(format!("Cannot replace a status effect with {:?}", item.get_type()));
+            };
+            *self = item;
+            return Ok(());
+        }
+        expect_index(path, 0)?;
+        match self {
+            StatusEffect::OnHit(card) => card.replace_from_path(path, item),
+            StatusEffect::SimpleStatusEffect(
+                SimpleStatusEffectType::IncreaseGravity(direction),
+                _,
+            ) => direction.replace_from_path(path, item),
+            invalid => Err(format!("Cannot follow path into {:?}", invalid)),
+        }
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        if path.is_empty() {
+            return Some(DragableCard::StatusEffect(self.clone()));
+        }
+        if path.pop()? != 0 {
+            return None;
+        }
+        match self {
+            StatusEffect::OnHit(card) => card.get_from_path(path),
+            StatusEffect::SimpleStatusEffect(
+                SimpleStatusEffectType::IncreaseGravity(direction),
+                _,
+            ) => direction.get_from_path(path),
+            _ => None,
+        }
+    }
+}
+
+impl EditableCard for DirectionCard {
+    fn modify_from_path(
+        &mut self,
+        _path: &mut Vec<usize>,
+        _modification_type: ModificationType,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        expect_end(path)?;
+        Ok(DragableCard::Direction(std::mem::replace(
+            self,
+            DirectionCard::None,
+        )))
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        expect_end(path)?;
+        let DragableCard::Direction(new_direction) = item else {
+            return Err(format!(
+                "Cannot insert {:?} into a direction",
+                item.get_type()
+            ));
+        };
+        *self = new_direction;
+        Ok(())
+    }
+
+    fn cleanup(&mut self, _path: &mut Vec<usize>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        self.insert_to_path(path, item)
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        path.is_empty()
+            .then(|| DragableCard::Direction(self.clone()))
+    }
+}
+
+impl EditableCard for BaseCard {
+    fn modify_from_path(
+        &mut self,
+        path: &mut Vec<usize>,
+        modification_type: ModificationType,
+    ) -> Result<(), String> {
+        match self {
+            BaseCard::Projectile(_) | BaseCard::MultiCast(..) if path.is_empty() => {}
+            BaseCard::Projectile(modifiers) => {
+                let idx = pop_index(path)?;
+                get_item(modifiers, idx)?.modify_from_path(path, modification_type)?;
+            }
+            BaseCard::MultiCast(cards, modifiers) => match pop_index(path)? {
+                0 => {
+                    let idx = pop_index(path)?;
+                    get_item(modifiers, idx)?.modify_from_path(path, modification_type)?;
+                }
+                1 => {
+                    let idx = pop_index(path)?;
+                    get_item(cards, idx)?.modify_from_path(path, modification_type)?;
+                }
+                type_idx => return Err(format!("Invalid multicast path index {}", type_idx)),
+            },
+            BaseCard::CreateMaterial(_) => {}
+            BaseCard::StatusEffects(duration, effects) => {
+                if path.is_empty() {
+                    match modification_type {
+                        ModificationType::Add => *duration += 1,
+                        ModificationType::Remove => {
+                            if *duration > 1 {
+                                *duration -= 1
+                            }
+                        }
+                        ModificationType::Set(new_value) => set_count(duration, new_value),
+                    }
+                } else {
+                    let effect_idx = pop_index(path)?;
+                    get_item(effects, effect_idx)?.modify_from_path(path, modification_type)?;
+                }
+            }
+            BaseCard::Effect(effect) => {
+                expect_end(path)?;
+                match effect {
+                    Effect::Damage(damage) => match modification_type {
+                        ModificationType::Add => *damage += 1,
+                        ModificationType::Remove => *damage -= 1,
+                        ModificationType::Set(new_value) => set_count(damage, new_value),
+                    },
+                    Effect::Knockback(knockback, _) => match modification_type {
+                        ModificationType::Add => *knockback += 1,
+                        ModificationType::Remove => *knockback -= 1,
+                        ModificationType::Set(new_value) => set_count(knockback, new_value),
+                    },
+                    Effect::Cleanse => {}
+                    Effect::Teleport => {}
+                }
+            }
+            BaseCard::Trigger(id) => match modification_type {
+                ModificationType::Add => *id += 1,
+                ModificationType::Remove => {
+                    if *id > 0 {
+                        *id -= 1
+                    }
+                }
+                ModificationType::Set(new_value) => set_count(id, new_value),
+            },
+            BaseCard::None | BaseCard::Palette(..) => {}
+        }
+        Ok(())
+    }
+
+    fn take_from_path(&mut self, path: &mut Vec<usize>) -> Result<DragableCard, String> {
+        if path.is_empty() {
+            return Ok(DragableCard::BaseCard(std::mem::replace(
+                self,
+                BaseCard::None,
+            )));
+        }
+        match self {
+            BaseCard::Projectile(modifiers) => {
+                let idx = pop_index(path)?;
+                get_item(modifiers, idx)?.take_from_path(path)
+            }
+            BaseCard::MultiCast(cards, modifiers) => match pop_index(path)? {
+                0 => {
+                    let idx = pop_index(path)?;
+                    get_item(modifiers, idx)?.take_from_path(path)
+                }
+                1 => {
+                    let idx = pop_index(path)?;
+                    let card = get_item(cards, idx)?;
+                    if path.is_empty() {
+                        Ok(DragableCard::BaseCard(std::mem::replace(card, BaseCard::None)))
+                    } else {
+                        card.take_from_path(path)
+                    }
+                }
+                type_idx => Err(format!("Invalid multicast path index {}", type_idx)),
+            },
+            BaseCard::StatusEffects(_, effects) => {
+                let effect_idx = pop_index(path)?;
+                let effect = get_item(effects, effect_idx)?;
+                if path.is_empty() {
+                    return Ok(DragableCard::StatusEffect(std::mem::replace(
+                        effect,
+                        StatusEffect::None,
+                    )));
+                }
+                effect.take_from_path(path)
+            }
+            BaseCard::Palette(cards) => {
+                let card_idx = pop_index(path)?;
+                Ok(get_item(cards, card_idx)?.clone())
+            }
+            BaseCard::Effect(Effect::Knockback(_, direction)) => {
+                expect_index(path, 0)?;
+                Ok(DragableCard::Direction(std::mem::replace(
+                    direction,
+                    DirectionCard::None,
+                )))
+            }
+            invalid_take @ (BaseCard::CreateMaterial(_)
+            | BaseCard::None
+            | BaseCard::Trigger(_)
+            | BaseCard::Effect(_)) => Err(format!("Cannot take from {:?}", invalid_take)),
+        }
+    }
+
+    fn insert_to_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        match self {
+            BaseCard::Projectile(modifiers) => {
+                if path.is_empty() {
+                    let DragableCard::ProjectileModifier(item) = item else {
+                        return Err(format!(
+                            "Cannot insert {:?} into a projectile",
+                            item.get_type()
+                        ));
+                    };
+                    if let ProjectileModifier::SimpleModify(last_ty, last_s) = item.clone() {
+                        let mut combined = false;
+                        for modifier in modifiers.iter_mut() {
+                            match modifier {
+                                ProjectileModifier::SimpleModify(ty, s) => {
+                                    if last_ty == *ty {
+                                        *s += last_s;
+                                        combined = true;
+                                        break;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        if !combined {
+                            modifiers.push(item);
+                        }
+                    } else if let ProjectileModifier::Charge(last_ty, last_s) = item.clone() {
+                        let mut combined = false;
+                        for modifier in modifiers.iter_mut() {
+                            if let ProjectileModifier::Charge(ty, s) = modifier {
+                                if last_ty == *ty {
+                                    *s += last_s;
+                                    combined = true;
+                                    break;
+                                }
+                            }
+                        }
+                        if !combined {
+                            modifiers.push(item);
+                        }
+                    } else {
+                        modifiers.push(item);
+                    }
+
+                    modifiers.retain(|modifier| match modifier {
+                        ProjectileModifier::SimpleModify(_, s) => *s != 0,
+                        _ => true,
+                    });
+                    Ok(())
+                } else {
+                    let idx = pop_index(path)?;
+                    get_item(modifiers, idx)?.insert_to_path(path, item)
+                }
+            }
+            BaseCard::MultiCast(cards, modifiers) => {
+                if path.is_empty() {
+                    match item {
+                        DragableCard::BaseCard(item) => cards.push(item),
+                        DragableCard::MultiCastModifier(modifier_item) => {
+                            let mut combined = false;
+                            match modifier_item.clone() {
+                                MultiCastModifier::None => {}
+                                MultiCastModifier::Duplication(last_s) => {
+                                    for modifier in modifiers.iter_mut() {
+                                        match modifier {
+                                            MultiCastModifier::Duplication(s) => {
+                                                *s += last_s;
+                                                combined = true;
+                                                break;
+                                            }
+                                            _ => {}
+                                        }
+                                    }
+                                }
+                                MultiCastModifier::Spread(last_s) => {
+                                    for modifier in modifiers.iter_mut() {
+                                        match modifier {
+                                            MultiCastModifier::Spread(s) => {
+                                                *s += last_s;
+                                                combined = true;
+                                                break;
+                                            }
+                                            _ => {}
+                                        }
+                                    }
+                                }
+                            }
+
+                            if !combined {
+                                modifiers.push(modifier_item);
+                            }
+                        }
+                        item => {
+                            return Err(format!(
+                                "Cannot insert {:?} into a multicast",
+                                item.get_type()
+                            ));
+                        }
+                    }
+                    Ok(())
+                } else {
+                    expect_index(path, 1)?;
+                    let idx = pop_index(path)?;
+                    get_item(cards, idx)?.insert_to_path(path, item)
+                }
+            }
+            BaseCard::StatusEffects(_, effects) => {
+                if path.is_empty() {
+                    let DragableCard::StatusEffect(item) = item else {
+                        return Err(format!(
+                            "Cannot insert {:?} into status effects",
+                            item.get_type()
+                        ));
+                    };
+                    if let StatusEffect::SimpleStatusEffect(last_ty, last_s) = item.clone() {
+                        let mut combined = false;
+                        for effect in effects.iter_mut() {
+                            match effect {
+                                StatusEffect::SimpleStatusEffect(ty, s) => {
+                                    if last_ty == *ty {
+                                        *s += last_s;
+                                        combined = true;
+                                        break;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        if !combined {
+                            effects.push(item);
+                        }
+                    } else {
+                        effects.push(item);
+                    }
+
+                    effects.retain(|effect| match effect {
+                        StatusEffect::SimpleStatusEffect(_, s) => *s != 0,
+                        _ => true,
+                    });
+                    Ok(())
+                } else {
+                    let idx = pop_index(path)?;
+                    expect_index(path, 0)?;
+                    get_item(effects, idx)?.insert_to_path(path, item)
+                }
+            }
+            BaseCard::Effect(Effect::Knockback(_, direction)) => {
+                expect_index(path, 0)?;
+                direction.insert_to_path(path, item)
+            }
+            BaseCard::None => {
+                expect_end(path)?;
+                let DragableCard::BaseCard(item) = item else {
+                    return Err(format!(
+                        "Cannot insert {:?} into an empty card",
+                        item.get_type()
+                    ));
+                };
+                *self = item;
+                Ok(())
+            }
+            c => Err(format!("Cannot insert into {:?}", c)),
+        }
+    }
+
+    fn cleanup(&mut self, path: &mut Vec<usize>) -> Result<(), String> {
+        match self {
+            BaseCard::Projectile(modifiers) => {
+                if path.len() <= 1 {
+                    modifiers.retain(|modifier| match modifier {
+                        ProjectileModifier::None => false,
+                        _ => true,
+                    });
+                } else {
+                    let idx = pop_index(path)?;
+                    get_item(modifiers, idx)?.cleanup(path)?;
+                }
+            }
+            BaseCard::MultiCast(cards, modifiers) => {
+                if path.is_empty() {
+                    cards.retain(|card| !matches!(card, BaseCard::None));
+                } else {
+                    match pop_index(path)? {
+                        0 => {
+                            let idx = pop_index(path)?;
+                            expect_end(path)?;
+                            let is_empty = match get_item(modifiers, idx)? {
+                                MultiCastModifier::None => true,
+                                MultiCastModifier::Duplication(s) => *s == 0,
+                                MultiCastModifier::Spread(s) => *s == 0,
+                            };
+                            if is_empty {
+                                modifiers.remove(idx);
+                            }
+                        }
+                        1 => {
+                            let idx = pop_index(path)?;
+                            let card = get_item(cards, idx)?;
+                            if path.is_empty() {
+                                if matches!(card, BaseCard::None) {
+                                    cards.remove(idx);
+                                }
+                            } else {
+                                card.cleanup(path)?;
+                            }
+                        }
+                        type_idx => {
+                            return Err(format!("Invalid multicast path index {}", type_idx));
+                        }
+                    }
+                }
+            }
+            BaseCard::StatusEffects(_, effects) => {
+                if path.len() <= 1 {
+                    effects.retain(|effect| match effect {
+                        StatusEffect::None => false,
+                        _ => true,
+                    });
+                } else {
+                    let idx = pop_index(path)?;
+                    expect_index(path, 0)?;
+                    match get_item(effects, idx)? {
+                        StatusEffect::OnHit(card) => card.cleanup(path)?,
+                        StatusEffect::SimpleStatusEffect(
+                            SimpleStatusEffectType::IncreaseGravity(_),
+                            _,
+                        ) => {}
+                        invalid => {
+                            return Err(format!("Cannot follow path {} into {:?}", idx, invalid));
+                        }
+                    }
+                }
+            }
+            BaseCard::None => expect_end(path)?,
+            BaseCard::Effect(Effect::Knockback(_, _)) => {}
+            c => return Err(format!("Cannot clean up {:?}", c)),
+        }
+        Ok(())
+    }
+
+    fn replace_from_path(&mut self, path: &mut Vec<usize>, item: DragableCard) -> Result<(), String> {
+        if path.is_empty() {
+            let DragableCard::BaseCard(item) = item else {
+                return Err(format!("Cannot replace a card with {:?}", item.get_type()));
+            };
+            *self = item;
+            return Ok(());
+        }
+        match self {
+            BaseCard::Projectile(modifiers) => {
+                let idx = pop_index(path)?;
+                get_item(modifiers, idx)?.replace_from_path(path, item)
+            }
+            BaseCard::MultiCast(cards, modifiers) => match pop_index(path)? {
+                0 => {
+                    let idx = pop_index(path)?;
+                    get_item(modifiers, idx)?.replace_from_path(path, item)
+                }
+                1 => {
+                    let idx = pop_index(path)?;
+                    get_item(cards, idx)?.replace_from_path(path, item)
+                }
+                type_idx => Err(format!("Invalid multicast path index {}", type_idx)),
+            },
+            BaseCard::StatusEffects(_, effects) => {
+                let effect_idx = pop_index(path)?;
+                get_item(effects, effect_idx)?.replace_from_path(path, item)
+            }
+            BaseCard::Effect(Effect::Knockback(_, direction)) => {
+                expect_index(path, 0)?;
+                direction.replace_from_path(path, item)
+            }
+            invalid => Err(format!("Cannot follow path into {:?}", invalid)),
+        }
+    }
+
+    fn get_from_path(&self, path: &mut Vec<usize>) -> Option<DragableCard> {
+        if path.is_empty() {
+            return Some(DragableCard::BaseCard(self.clone()));
+        }
+        match self {
+            BaseCard::Projectile(modifiers) => modifiers.get(path.pop()?)?.get_from_path(path),
+            BaseCard::MultiCast(cards, modifiers) => match path.pop()? {
+                0 => modifiers.get(path.pop()?)?.get_from_path(path),
+                1 => cards.get(path.pop()?)?.get_from_path(path),
+                _ => None,
+            },
+            BaseCard::StatusEffects(_, effects) => effects.get(path.pop()?)?.get_from_path(path),
+            BaseCard::Effect(Effect::Knockback(_, direction)) => {
+                if path.pop()? != 0 {
+                    return None;
+                }
+                direction.get_from_path(path)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::{collection::vec, prelude::*, sample::Index};
+
+    use super::*;
+    use crate::card_editor::PaletteState;
+
+    /// Highest index tried at each level when listing the paths of a deck
+    const MAX_PATH_INDEX: usize = 8;
+    const PALETTE_KEY: &str = "Palette";
+    const EMPTY_EFFECT_KEY: &str = "StatusEffect::None";
+
+    fn movement_deck() -> Deck {
+        ron::from_str(include_str!("../decks/movement.ron")).unwrap()
+    }
+
+    fn palette_cards() -> Vec<DragableCard> {
+        PaletteState::ALL
+            .iter()
+            .flat_map(|palette| palette.get_cards(&[]))
+            .collect()
+    }
+
+    /// Every path with a card, plus the slot roots. Cooldowns and multicasts have a group index before the card index,
+    /// so a level without a card is skipped once.
+    fn deck_paths(deck: &Deck) -> Vec<DeckPath> {
+        let slots =
+            std::iter::once(DeckSlot::Passive).chain((0..deck.cooldowns.len()).map(DeckSlot::Cooldown));
+        let mut paths = vec![];
+        for slot in slots {
+            let mut unvisited = vec![vec![]];
+            while let Some(path) = unvisited.pop() {
+                for idx in 0..MAX_PATH_INDEX {
+                    let child = [path.clone(), vec![idx]].concat();
+                    let children = if deck.get_card(&DeckPath::new(slot, child.clone())).is_some() {
+                        vec![child]
+                    } else {
+                        (0..MAX_PATH_INDEX)
+                            .map(|inner| [child.clone(), vec![inner]].concat())
+                            .filter(|grandchild| {
+                                deck.get_card(&DeckPath::new(slot, grandchild.clone())).is_some()
+                            })
+                            .collect()
+                    };
+                    unvisited.extend(children.iter().cloned());
+                    paths.extend(children.into_iter().map(|child| DeckPath::new(slot, child)));
+                }
+            }
+            paths.push(DeckPath::new(slot, vec![]));
+        }
+        paths
+    }
+
+    /// How many of each card the deck holds, with stacks of cards that merge summed up.
+    /// Directions are left out since one direction can stand for a whole stack.
+    fn card_counts(deck: &Deck) -> BTreeMap<String, i64> {
+        fn add(counts: &mut BTreeMap<String, i64>, key: String, amount: i64) {
+            *counts.entry(key).or_default() += amount;
+        }
+        fn count_card(counts: &mut BTreeMap<String, i64>, card: &BaseCard) {
+            match card {
+                BaseCard::None => {}
+                BaseCard::Palette(_) => add(counts, PALETTE_KEY.to_string(), 1),
+                BaseCard::Projectile(modifiers) => {
+                    add(counts, "Projectile".to_string(), 1);
+                    for modifier in modifiers {
+                        count_projectile_modifier(counts, modifier);
+                    }
+                }
+                BaseCard::MultiCast(cards, modifiers) => {
+                    add(counts, "MultiCast".to_string(), 1);
+                    for card in cards {
+                        count_card(counts, card);
+                    }
+                    for modifier in modifiers {
+                        match modifier {
+                            MultiCastModifier::None => {}
+                            MultiCastModifier::Spread(s) => {
+                                add(counts, "Spread".to_string(), *s as i64)
+                            }
+                            MultiCastModifier::Duplication(s) => {
+                                add(counts, "Duplication".to_string(), *s as i64)
+                            }
+                        }
+                    }
+                }
+                BaseCard::StatusEffects(duration, effects) => {
+                    add(counts, format!("StatusEffects({})", duration), 1);
+                    for effect in effects {
+                        count_status_effect(counts, effect);
+                    }
+                }
+                BaseCard::Effect(Effect::Knockback(knockback, _)) => {
+                    add(counts, "Knockback".to_string(), *knockback as i64)
+                }
+                card => add(counts, format!("{:?}", card), 1),
+            }
+        }
+        fn count_projectile_modifier(
+            counts: &mut BTreeMap<String, i64>,
+            modifier: &ProjectileModifier,
+        ) {
+            match modifier {
+                ProjectileModifier::None | ProjectileModifier::LockToOwner(_) => {}
+                ProjectileModifier::SimpleModify(ty, s) => {
+                    add(counts, format!("SimpleModify({:?})", ty), *s as i64)
+                }
+                ProjectileModifier::Charge(ty, s) => {
+                    add(counts, format!("Charge({:?})", ty), *s as i64)
+                }
+                ProjectileModifier::OnHit(card)
+                | ProjectileModifier::OnHeadshot(card)
+                | ProjectileModifier::OnExpiry(card)
+                | ProjectileModifier::OnTrigger(_, card)
+                | ProjectileModifier::Trail(_, card) => {
+                    let name = format!("{:?}", modifier);
+                    add(counts, name[..name.find('(').unwrap()].to_string(), 1);
+                    count_card(counts, card);
+                }
+                modifier => add(counts, format!("{:?}", modifier), 1),
+            }
+        }
+        fn count_status_effect(counts: &mut BTreeMap<String, i64>, effect: &StatusEffect) {
+            match effect {
+                StatusEffect::None => add(counts, EMPTY_EFFECT_KEY.to_string(), 1),
+                StatusEffect::SimpleStatusEffect(ty, s) => {
+                    add(counts, format!("SimpleStatusEffect({:?})", ty), *s as i64)
+                }
+                StatusEffect::UnsignedSimpleStatusEffect(ty, s) => {
+                    add(counts, format!("UnsignedSimpleStatusEffect({:?})", ty), *s as i64)
+                }
+                StatusEffect::OnHit(card) => {
+                    add(counts, "OnHitEffect".to_string(), 1);
+                    count_card(counts, card);
+                }
+                effect => add(counts, format!("{:?}", effect), 1),
+            }
+        }
+
+        let mut counts = BTreeMap::new();
+        for effect in deck.passive.passive_effects.iter() {
+            count_status_effect(&mut counts, effect);
+        }
+        for cooldown in deck.cooldowns.iter() {
+            for modifier in cooldown.modifiers.iter() {
+                match modifier {
+                    CooldownModifier::None => {}
+                    CooldownModifier::SimpleCooldownModifier(ty, s) => {
+                        add(&mut counts, format!("SimpleCooldownModifier({:?})", ty), *s as i64)
+                    }
+                    CooldownModifier::SignedSimpleCooldownModifier(ty, s) => add(
+                        &mut counts,
+                        format!("SignedSimpleCooldownModifier({:?})", ty),
+                        *s as i64,
+                    ),
+                    modifier => add(&mut counts, format!("{:?}", modifier), 1),
+                }
+            }
+            for ability in cooldown.abilities.iter() {
+                count_card(&mut counts, &ability.card);
+            }
+        }
+        counts.retain(|_, count| *count != 0);
+        counts
+    }
+
+    #[derive(Debug, Clone)]
+    enum Target {
+        /// One of the paths in the deck
+        Existing(Index),
+        /// Any path, which is usually invalid
+        Raw(usize, Vec<usize>),
+    }
+
+    impl Target {
+        fn get_path(&self, paths: &[DeckPath]) -> DeckPath {
+            match self {
+                Target::Existing(idx) => idx.get(paths).clone(),
+                Target::Raw(0, path) => DeckPath::new(DeckSlot::Passive, path.clone()),
+                Target::Raw(slot, path) => DeckPath::new(DeckSlot::Cooldown(slot - 1), path.clone()),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Step {
+        Move(Target, Target),
+        Insert(Index, Target),
+        Remove(Target),
+        Increment(Target),
+        Decrement(Target),
+        SetValue(Target, i64),
+    }
+
+    impl Step {
+        fn get_edit(&self, deck: &Deck, palette: &[DragableCard]) -> DeckEdit {
+            let paths = deck_paths(deck);
+            match self {
+                Step::Move(from, to) => DeckEdit::Move {
+                    from: from.get_path(&paths),
+                    to: to.get_path(&paths),
+                },
+                Step::Insert(card, to) => DeckEdit::Insert {
+                    to: to.get_path(&paths),
+                    card: card.get(palette).clone(),
+                },
+                Step::Remove(from) => DeckEdit::Remove {
+                    from: from.get_path(&paths),
+                },
+                Step::Increment(at) => DeckEdit::Increment {
+                    at: at.get_path(&paths),
+                },
+                Step::Decrement(at) => DeckEdit::Decrement {
+                    at: at.get_path(&paths),
+                },
+                Step::SetValue(at, value) => DeckEdit::SetValue {
+                    at: at.get_path(&paths),
+                    value: *value,
+                },
+            }
+        }
+    }
+
+    fn target() -> impl Strategy<Value = Target> {
+        prop_oneof![
+            4 => any::<Index>().prop_map(Target::Existing),
+            1 => (0..4usize, vec(0..4usize, 0..5)).prop_map(|(slot, path)| Target::Raw(slot, path)),
+        ]
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            4 => (target(), target()).prop_map(|(from, to)| Step::Move(from, to)),
+            2 => (any::<Index>(), target()).prop_map(|(card, to)| Step::Insert(card, to)),
+            1 => target().prop_map(Step::Remove),
+            1 => target().prop_map(Step::Increment),
+            1 => target().prop_map(Step::Decrement),
+            1 => (target(), -3..6i64).prop_map(|(at, value)| Step::SetValue(at, value)),
+        ]
+    }
+
+    #[test]
+    fn invalid_paths_are_errors() {
+        let mut deck = movement_deck();
+        let before = ron::to_string(&deck).unwrap();
+        let edits = [
+            DeckEdit::Move {
+                from: DeckPath::new(DeckSlot::Cooldown(0), vec![1, 0]),
+                to: DeckPath::new(DeckSlot::Cooldown(99), vec![]),
+            },
+            DeckEdit::Remove {
+                from: DeckPath::new(DeckSlot::Cooldown(0), vec![1, 99, 0]),
+            },
+            DeckEdit::Increment {
+                at: DeckPath::new(DeckSlot::Cooldown(0), vec![7]),
+            },
+            DeckEdit::Insert {
+                to: DeckPath::new(DeckSlot::Cooldown(0), vec![0, 0]),
+                card: DragableCard::BaseCard(BaseCard::Trigger(0)),
+            },
+        ];
+        for edit in edits {
+            assert!(deck.apply_edit(edit.clone()).is_err(), "{:?}", edit);
+            assert_eq!(ron::to_string(&deck).unwrap(), before);
+        }
+    }
+
+    #[test]
+    fn slot_edits_are_undone_by_their_inverse() {
+        let mut deck = movement_deck();
+        let mut other_deck = movement_deck();
+        other_deck.cooldowns.truncate(1);
+        let edits = [
+            DeckEdit::InsertCooldown {
+                idx: 1,
+                cooldown: Cooldown::empty(),
+            },
+            DeckEdit::SetCooldown {
+                idx: 0,
+                cooldown: Cooldown::empty(),
+            },
+            DeckEdit::SetPassive {
+                passive: PassiveCard {
+                    passive_effects: vec![StatusEffect::SimpleStatusEffect(
+                        SimpleStatusEffectType::Speed,
+                        1,
+                    )],
+                },
+            },
+            DeckEdit::SetKeybind {
+                cooldown: 0,
+                ability: 0,
+                keybind: Keybind::parse("KeyQ & !Ground").unwrap(),
+            },
+            DeckEdit::SetDeck {
+                deck: Box::new(other_deck),
+            },
+        ];
+        for edit in edits {
+            let before = ron::to_string(&deck).unwrap();
+            let (_, inverse) = deck.apply_edit_with_inverse(edit.clone()).unwrap();
+            assert_ne!(ron::to_string(&deck).unwrap(), before, "{:?}", edit);
+            for inverse_edit in inverse {
+                deck.apply_edit(inverse_edit).unwrap();
+            }
+            assert_eq!(ron::to_string(&deck).unwrap(), before, "{:?}", edit);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn random_edits_keep_the_deck_valid(steps in vec(step(), 1..24)) {
+            let mut deck = movement_deck();
+            let palette = palette_cards();
+            for step in steps {
+                let edit = step.get_edit(&deck, &palette);
+                let before = ron::to_string(&deck).unwrap();
+                let counts_before = card_counts(&deck);
+                let is_card_move = match &edit {
+                    DeckEdit::Move { from, .. } => {
+                        !matches!(deck.get_card(from), Some(DragableCard::Direction(_)))
+                    }
+                    _ => false,
+                };
+                match deck.apply_edit_with_inverse(edit.clone()) {
+                    Ok((_, inverse)) => {
+                        if is_card_move {
+                            prop_assert_eq!(&card_counts(&deck), &counts_before, "{:?}", edit);
+                        }
+                        let mut undone = deck.clone();
+                        for inverse_edit in inverse {
+                            prop_assert!(undone.apply_edit(inverse_edit).is_ok(), "{:?}", edit);
+                        }
+                        prop_assert_eq!(&ron::to_string(&undone).unwrap(), &before, "{:?}", edit);
+                    }
+                    Err(_) => prop_assert_eq!(&ron::to_string(&deck).unwrap(), &before),
+                }
+                let counts = card_counts(&deck);
+                prop_assert!(!counts.contains_key(PALETTE_KEY), "{:?}", edit);
+                prop_assert!(!counts.contains_key(EMPTY_EFFECT_KEY), "{:?}", edit);
+            }
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    card_editor::{ComparedDeck, DeckComparison},
+    card_system::Deck,
+    deck_storage::{get_dock_path, load_deck, save_deck_and_dock, write_deck},
+    edit_history::EditHistory,
+    gui::{GuiElement, GuiState},
+    settings_manager::Settings,
+    utils::recurse_files,
+};
+use egui::{Color32, RichText, Ui};
+
+struct DeckPreview {
+    ability_count: usize,
+    total_impact: f32,
+    /// Why the deck cannot be used, or `None` if it is valid
+    invalid_reason: Option<String>,
+    unreasonable_reason: Option<String>,
+}
+
+struct DeckEntry {
+    path: PathBuf,
+    /// Resolved once when scanning, so finding the active deck doesn't touch the disk every frame
+    canonical_path: PathBuf,
+    name: String,
+    preview: DeckPreview,
+}
+
+enum DeckAction {
+    SetActive(PathBuf),
+    Edit(PathBuf),
+    Duplicate(PathBuf),
+    StartRename(PathBuf),
+    Rename(PathBuf, String),
+    Delete(PathBuf),
+    Compare(PathBuf),
+}
+
+/// Browser over the decks in `settings.card_dir`
+#[derive(Default)]
+pub struct DeckPicker {
+    /// Cached scan of the deck directory, cleared whenever a file is changed
+    entries: Option<Vec<DeckEntry>>,
+    new_deck_name: String,
+    renaming: Option<(PathBuf, String)>,
+    pending_delete: Option<PathBuf>,
+}
+
+impl DeckPicker {
+    pub fn refresh(&mut self) {
+        self.entries = None;
+    }
+}
+
+fn get_preview(path: &Path) -> DeckPreview {
+    match load_deck(path) {
+        Ok(deck) => DeckPreview {
+            ability_count: deck
+                .cooldowns
+                .iter()
+                .map(|cooldown| cooldown.abilities.len())
+                .sum(),
+            total_impact: deck.get_total_impact(),
+            invalid_reason: deck.check_complete().err(),
+            unreasonable_reason: deck.get_unreasonable_reason(),
+        },
+        Err(e) => DeckPreview {
+            ability_count: 0,
+            total_impact: 0.0,
+            invalid_reason: Some(e),
+            unreasonable_reason: None,
+        },
+    }
+}
+
+/// Every deck file in `card_dir`, leaving out the dock
+pub fn list_deck_files(card_dir: &str) -> Result<Vec<PathBuf>, String> {
+    let files = recurse_files(card_dir)
+        .map_err(|e| format!("Cannot read deck directory {}: {}", card_dir, e))?;
+    let dock_path = get_dock_path(card_dir);
+    Ok(files
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter(|path| *path != dock_path)
+        .collect())
+}
+
+pub fn get_deck_name(card_dir: &str, path: &Path) -> String {
+    path.strip_prefix(card_dir)
+        .unwrap_or(path)
+        .with_extension("")
+        .display()
+        .to_string()
+}
+
+fn scan_decks(card_dir: &str) -> Result<Vec<DeckEntry>, String> {
+    let mut entries: Vec<DeckEntry> = list_deck_files(card_dir)?
+        .into_iter()
+        .map(|path| DeckEntry {
+            name: get_deck_name(card_dir, &path),
+            preview: get_preview(&path),
+            canonical_path: canonicalize(&path),
+            path,
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Finds a file name in the same directory that is not taken yet, e.g. `movement copy (2).ron`
+fn get_free_path(directory: &Path, name: &str) -> PathBuf {
+    let mut path = directory.join(format!("{}.ron", name));
+    let mut copy_idx = 2;
+    while path.exists() {
+        path = directory.join(format!("{} ({}).ron", name, copy_idx));
+        copy_idx += 1;
+    }
+    path
+}
+
+fn get_duplicate_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    get_free_path(
+        path.parent().unwrap_or(Path::new("")),
+        &format!("{} copy", stem),
+    )
+}
+
+/// Where a deck ends up after renaming, or `None` if the name did not change
+fn get_rename_path(path: &Path, name: &str) -> Result<Option<PathBuf>, String> {
+    let name = validate_deck_name(name)?;
+    let new_path = path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(format!("{}.ron", name));
+    if new_path == path {
+        return Ok(None);
+    }
+    if new_path.exists() {
+        return Err(format!("{} already exists", new_path.display()));
+    }
+    Ok(Some(new_path))
+}
+
+fn validate_deck_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Deck name cannot be empty".to_string());
+    }
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid deck name {}", name));
+    }
+    Ok(name)
+}
+
+/// Resolves `./` and symlinks so the same file compares equal however it was written.
+/// Paths that don't exist yet can't be resolved and are kept as they are.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_active_deck(settings: &Settings, path: &Path) -> bool {
+    canonicalize(Path::new(&settings.card_file)) == canonicalize(path)
+}
+
+fn set_active_deck(gui_state: &mut GuiState, settings: &mut Settings, path: &Path) -> bool {
+    // Write pending edits to the old deck before it is replaced
+    if gui_state.deck_autosave.is_deck_dirty() {
+        save_deck_and_dock(gui_state, settings);
+    }
+    let deck = match load_deck(path).and_then(|deck| deck.check_complete().map(|_| deck)) {
+        Ok(deck) => deck,
+        Err(e) => {
+            gui_state.errors.push(e);
+            return false;
+        }
+    };
+    gui_state.gui_deck = deck.clone();
+    gui_state.render_deck = deck;
+    gui_state.deck_history = EditHistory::default();
+    gui_state.deck_autosave.mark_deck_changed();
+    gui_state.deck_autosave.mark_deck_saved();
+    settings.card_file = path.display().to_string();
+    if let Err(e) = gui_state.settings_layers.save(settings) {
+        gui_state
+            .errors
+            .push(format!("Failed to save settings: {}", e));
+    }
+    true
+}
+
+fn apply_action(gui_state: &mut GuiState, settings: &mut Settings, action: DeckAction) {
+    let result = match action {
+        DeckAction::SetActive(path) => {
+            if set_active_deck(gui_state, settings, &path) {
+                gui_state.push_message(format!("Active deck set to {}", path.display()));
+            }
+            Ok(())
+        }
+        DeckAction::Edit(path) => {
+            if is_active_deck(settings, &path) || set_active_deck(gui_state, settings, &path) {
+                gui_state.menu_stack.pop();
+                gui_state.menu_stack.push(GuiElement::CardEditor);
+            }
+            Ok(())
+        }
+        DeckAction::Compare(path) => load_deck(&path).map(|deck| {
+            gui_state.deck_comparison = Some(DeckComparison {
+                decks: [
+                    ComparedDeck::current(&gui_state.render_deck),
+                    ComparedDeck::new(get_deck_name(&settings.card_dir, &path), Some(path), deck),
+                ],
+            });
+            gui_state.menu_stack.push(GuiElement::DeckCompare);
+        }),
+        DeckAction::Duplicate(path) => fs::copy(&path, get_duplicate_path(&path))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to duplicate {}: {}", path.display(), e)),
+        DeckAction::StartRename(path) => {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            gui_state.deck_picker.renaming = Some((path, name));
+            return;
+        }
+        DeckAction::Rename(path, name) => get_rename_path(&path, &name).and_then(|new_path| {
+            let Some(new_path) = new_path else {
+                return Ok(());
+            };
+            fs::rename(&path, &new_path)
+                .map_err(|e| format!("Failed to rename {}: {}", path.display(), e))?;
+            if is_active_deck(settings, &path) {
+                settings.card_file = new_path.display().to_string();
+                gui_state
+                    .settings_layers
+                    .save(settings)
+                    .map_err(|e| format!("Failed to save settings: {}", e))?;
+            }
+            Ok(())
+        }),
+        DeckAction::Delete(path) => {
+            if is_active_deck(settings, &path) {
+                Err("Cannot delete the active deck".to_string())
+            } else {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+            }
+        }
+    };
+    if let Err(e) = result {
+        gui_state.errors.push(e);
+    }
+    gui_state.deck_picker.refresh();
+}
+
+fn create_deck(gui_state: &mut GuiState, settings: &Settings) -> Result<(), String> {
+    let name = validate_deck_name(&gui_state.deck_picker.new_deck_name)?;
+    let path = Path::new(&settings.card_dir).join(format!("{}.ron", name));
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    fs::create_dir_all(&settings.card_dir)
+        .map_err(|e| format!("Cannot create deck directory {}: {}", settings.card_dir, e))?;
+    write_deck(&path, &Deck::empty())?;
+    gui_state.deck_picker.new_deck_name.clear();
+    Ok(())
+}
+
+fn draw_entry(
+    ui: &mut Ui,
+    picker: &mut DeckPicker,
+    entry: &DeckEntry,
+    is_active: bool,
+) -> Option<DeckAction> {
+    let mut action = None;
+    let name_text = if is_active {
+        RichText::new(format!("{} (active)", entry.name)).color(Color32::LIGHT_GREEN)
+    } else {
+        RichText::new(&entry.name).color(Color32::WHITE)
+    };
+    match &mut picker.renaming {
+        Some((path, name)) if *path == entry.path => {
+            let response = ui.text_edit_singleline(name);
+            // Enter confirms the rename, clicking away or escape cancels it
+            if response.lost_focus() {
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    action = Some(DeckAction::Rename(path.clone(), name.clone()));
+                }
+                picker.renaming = None;
+            } else if !response.has_focus() {
+                response.request_focus();
+            }
+        }
+        _ => {
+            ui.label(name_text);
+        }
+    }
+    ui.label(entry.preview.ability_count.to_string());
+    ui.label(format!("{:.2}", entry.preview.total_impact));
+    match (
+        &entry.preview.invalid_reason,
+        &entry.preview.unreasonable_reason,
+    ) {
+        (Some(reason), _) => {
+            ui.colored_label(Color32::RED, "Invalid")
+                .on_hover_text(reason);
+        }
+        (None, Some(reason)) => {
+            ui.colored_label(Color32::YELLOW, "Unreasonable")
+                .on_hover_text(reason);
+        }
+        (None, None) => {
+            ui.colored_label(Color32::GREEN, "Valid");
+        }
+    }
+    ui.horizontal(|ui| {
+        let is_valid = entry.preview.invalid_reason.is_none();
+        if ui
+            .add_enabled(!is_active && is_valid, egui::Button::new("Set Active"))
+            .clicked()
+        {
+            action = Some(DeckAction::SetActive(entry.path.clone()));
+        }
+        if ui
+            .add_enabled(is_valid, egui::Button::new("Edit"))
+            .clicked()
+        {
+            action = Some(DeckAction::Edit(entry.path.clone()));
+        }
+        if ui
+            .button("Compare")
+            .on_hover_text("Compare with the deck being edited")
+            .clicked()
+        {
+            action = Some(DeckAction::Compare(entry.path.clone()));
+        }
+        if ui.button("Duplicate").clicked() {
+            action = Some(DeckAction::Duplicate(entry.path.clone()));
+        }
+        if ui.button("Rename").clicked() {
+            action = Some(DeckAction::StartRename(entry.path.clone()));
+        }
+        if picker.pending_delete.as_ref() == Some(&entry.path) {
+            if ui
+                .button(RichText::new("Confirm Delete").color(Color32::RED))
+                .clicked()
+            {
+                action = Some(DeckAction::Delete(entry.path.clone()));
+                picker.pending_delete = None;
+            }
+            if ui.button("Cancel").clicked() {
+                picker.pending_delete = None;
+            }
+        } else if ui
+            .add_enabled(!is_active, egui::Button::new("Delete"))
+            .clicked()
+        {
+            picker.pending_delete = Some(entry.path.clone());
+        }
+    });
+    action
+}
+
+pub fn deck_picker(ui: &mut Ui, gui_state: &mut GuiState, settings: &mut Settings) {
+    ui.label(RichText::new("Deck Picker").color(Color32::WHITE));
+    if gui_state.deck_picker.entries.is_none() {
+        match scan_decks(&settings.card_dir) {
+            Ok(entries) => gui_state.deck_picker.entries = Some(entries),
+            Err(e) => {
+                gui_state.errors.push(e);
+                gui_state.deck_picker.entries = Some(vec![]);
+            }
+        }
+    }
+
+    let mut action = None;
+    let picker = &mut gui_state.deck_picker;
+    let entries = picker.entries.take().unwrap_or_default();
+    if entries.is_empty() {
+        ui.label(format!("No decks found in {}", settings.card_dir));
+    } else {
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() / 2.0)
+            .show(ui, |ui| {
+                egui::Grid::new("deck_picker_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Deck");
+                        ui.label("Abilities");
+                        ui.label("Total Impact");
+                        ui.label("Validity");
+                        ui.end_row();
+                        let active_path = canonicalize(Path::new(&settings.card_file));
+                        for entry in entries.iter() {
+                            let is_active = entry.canonical_path == active_path;
+                            if let Some(entry_action) = draw_entry(ui, picker, entry, is_active) {
+                                action = Some(entry_action);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+    picker.entries = Some(entries);
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut gui_state.deck_picker.new_deck_name)
+                .hint_text("New deck name")
+                .desired_width(150.0),
+        );
+        if ui.button("New Deck").clicked() {
+            if let Err(e) = create_deck(gui_state, settings) {
+                gui_state.errors.push(e);
+            }
+            gui_state.deck_picker.refresh();
+        }
+        if ui.button("Refresh").clicked() {
+            gui_state.deck_picker.refresh();
+        }
+    });
+
+    if let Some(action) = action {
+        apply_action(gui_state, settings, action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::get_test_dir;
+
+    #[test]
+    fn deck_names_cannot_be_empty_or_leave_the_directory() {
+        assert_eq!(validate_deck_name("  movement "), Ok("movement"));
+        assert!(validate_deck_name("").is_err());
+        assert!(validate_deck_name("   ").is_err());
+        assert!(validate_deck_name("decks/movement").is_err());
+        assert!(validate_deck_name("decks\\movement").is_err());
+        assert!(validate_deck_name("../movement").is_err());
+        assert!(validate_deck_name(".hidden").is_err());
+    }
+
+    #[test]
+    fn taken_names_get_a_numbered_suffix() {
+        let directory = get_test_dir("free-path");
+        assert_eq!(
+            get_free_path(&directory, "movement"),
+            directory.join("movement.ron")
+        );
+        fs::write(directory.join("movement.ron"), "").unwrap();
+        assert_eq!(
+            get_free_path(&directory, "movement"),
+            directory.join("movement (2).ron")
+        );
+        fs::write(directory.join("movement (2).ron"), "").unwrap();
+        assert_eq!(
+            get_free_path(&directory, "movement"),
+            directory.join("movement (3).ron")
+        );
+    }
+
+    #[test]
+    fn duplicates_and_renames_do_not_overwrite_decks() {
+        let directory = get_test_dir("deck-collisions");
+        let deck = directory.join("movement.ron");
+        fs::write(&deck, "").unwrap();
+        assert_eq!(
+            get_duplicate_path(&deck),
+            directory.join("movement copy.ron")
+        );
+        fs::write(directory.join("movement copy.ron"), "").unwrap();
+        assert_eq!(
+            get_duplicate_path(&deck),
+            directory.join("movement copy (2).ron")
+        );
+
+        assert_eq!(get_rename_path(&deck, "movement"), Ok(None));
+        assert_eq!(
+            get_rename_path(&deck, "dash"),
+            Ok(Some(directory.join("dash.ron")))
+        );
+        assert!(get_rename_path(&deck, "movement copy").is_err());
+        assert!(get_rename_path(&deck, "").is_err());
+        assert!(get_rename_path(&deck, "sub/dash").is_err());
+    }
+}
@@ -0,0 +1,497 @@
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+
+use crate::{
+    card_system::{Deck, DragableCard},
+    deck_edit::DeckEdit,
+    gui::{GuiElement, GuiState, line_diff_view},
+    settings_manager::Settings,
+    utils::{LineDiff, diff_lines},
+};
+
+/// File in `card_dir` holding the editor dock, skipped when listing decks
+pub const DOCK_FILE_NAME: &str = "dock.ron";
+/// Seconds without further changes before the deck and dock are written
+const AUTOSAVE_DELAY: f64 = 2.0;
+/// Seconds between checks of the deck file for changes made by other programs
+const WATCH_INTERVAL: f64 = 1.0;
+
+pub fn load_deck(path: &Path) -> Result<Deck, String> {
+    let deck_text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    ron::from_str(&deck_text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+pub fn write_deck(path: &Path, deck: &Deck) -> Result<(), String> {
+    write_text(path, &to_saved_text(deck)?)
+}
+
+pub fn get_dock_path(card_dir: &str) -> PathBuf {
+    Path::new(card_dir).join(DOCK_FILE_NAME)
+}
+
+/// Loads the dock saved by a previous session, a missing dock file is just an empty dock
+pub fn load_dock(card_dir: &str) -> Result<Vec<DragableCard>, String> {
+    let path = get_dock_path(card_dir);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let dock_text = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    ron::from_str(&dock_text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn to_saved_text<T: Serialize + ?Sized>(value: &T) -> Result<String, String> {
+    ron::ser::to_string_pretty(value, PrettyConfig::default())
+        .map_err(|e| format!("Failed to serialize: {}", e))
+}
+
+fn write_text(path: &Path, text: &str) -> Result<(), String> {
+    if cfg!(target_arch = "wasm32") {
+        return Ok(());
+    }
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(directory)
+            .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    }
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+struct PendingSave<V> {
+    version: V,
+    changed_at: f64,
+    /// A failed autosave is not retried until the value changes again
+    attempted: bool,
+}
+
+/// Tracks the last saved version of a value and debounces writes of newer versions
+struct SaveTracker<V> {
+    saved: V,
+    pending: Option<PendingSave<V>>,
+}
+
+impl<V: PartialEq> SaveTracker<V> {
+    fn new(saved: V) -> Self {
+        SaveTracker {
+            saved,
+            pending: None,
+        }
+    }
+
+    fn mark_saved(&mut self, version: V) {
+        self.saved = version;
+        self.pending = None;
+    }
+
+    /// Returns true once a new version has gone unchanged for the autosave delay
+    fn poll(&mut self, version: V, time: f64) -> bool {
+        if version == self.saved {
+            self.pending = None;
+            return false;
+        }
+        match &mut self.pending {
+            Some(pending) if pending.version == version => {
+                if pending.attempted || time - pending.changed_at < AUTOSAVE_DELAY {
+                    return false;
+                }
+                pending.attempted = true;
+                true
+            }
+            _ => {
+                self.pending = Some(PendingSave {
+                    version,
+                    changed_at: time,
+                    attempted: false,
+                });
+                false
+            }
+        }
+    }
+}
+
+/// Autosave state for the deck being edited and the editor dock.
+/// Both are tracked by generations that every edit bumps, so they only have to be serialized when they are written.
+pub struct EditorAutosave {
+    deck_generation: u64,
+    deck: SaveTracker<u64>,
+    dock_generation: u64,
+    dock: SaveTracker<u64>,
+}
+
+impl Default for EditorAutosave {
+    fn default() -> Self {
+        EditorAutosave::new()
+    }
+}
+
+impl EditorAutosave {
+    pub fn new() -> Self {
+        EditorAutosave {
+            deck_generation: 0,
+            deck: SaveTracker::new(0),
+            dock_generation: 0,
+            dock: SaveTracker::new(0),
+        }
+    }
+
+    /// Has to be called after every change to the deck being edited
+    pub fn mark_deck_changed(&mut self) {
+        self.deck_generation += 1;
+    }
+
+    /// Used when a different deck is loaded so it does not count as unsaved
+    pub fn mark_deck_saved(&mut self) {
+        self.deck.mark_saved(self.deck_generation);
+    }
+
+    /// Changes whenever the deck is edited or replaced
+    pub fn get_deck_generation(&self) -> u64 {
+        self.deck_generation
+    }
+
+    pub fn is_deck_dirty(&self) -> bool {
+        self.deck_generation != self.deck.saved
+    }
+
+    /// Has to be called after every change to the dock cards
+    pub fn mark_dock_changed(&mut self) {
+        self.dock_generation += 1;
+    }
+}
+
+/// Writes the deck and marks the current generation saved, returning the written text
+fn save_deck(autosave: &mut EditorAutosave, path: &Path, deck: &Deck) -> Result<String, String> {
+    let text = to_saved_text(deck)?;
+    write_text(path, &text)?;
+    autosave.deck.mark_saved(autosave.deck_generation);
+    Ok(text)
+}
+
+fn save_dock(
+    autosave: &mut EditorAutosave,
+    path: &Path,
+    dock_cards: &[DragableCard],
+) -> Result<(), String> {
+    write_text(path, &to_saved_text(dock_cards)?)?;
+    autosave.dock.mark_saved(autosave.dock_generation);
+    Ok(())
+}
+
+/// Writes the deck being edited to `card_file` and the dock to `card_dir` right away.
+/// The deck is not written over changes made to the file by another program until the user picks a version.
+pub fn save_deck_and_dock(gui_state: &mut GuiState, settings: &Settings) {
+    let deck_path = Path::new(&settings.card_file);
+    let deck_result = if gui_state.deck_file_watcher.check(deck_path) {
+        Err(format!(
+            "{} was changed by another program, choose which version to keep",
+            settings.card_file
+        ))
+    } else {
+        save_deck(
+            &mut gui_state.deck_autosave,
+            deck_path,
+            &gui_state.render_deck,
+        )
+        .map(|text| gui_state.deck_file_watcher.mark_written(deck_path, &text))
+    };
+    let dock_result = save_dock(
+        &mut gui_state.deck_autosave,
+        &get_dock_path(&settings.card_dir),
+        &gui_state.dock_cards,
+    );
+    gui_state.errors.extend(
+        [deck_result, dock_result]
+            .into_iter()
+            .filter_map(Result::err),
+    );
+    gui_state.deck_picker.refresh();
+}
+
+/// Called every frame while editing, writes the deck and dock once they stop changing
+pub fn autosave(gui_state: &mut GuiState, settings: &Settings, time: f64) {
+    let autosave = &mut gui_state.deck_autosave;
+    let deck_path = Path::new(&settings.card_file);
+    if autosave.deck.poll(autosave.deck_generation, time) {
+        if gui_state.deck_file_watcher.check(deck_path) {
+            // The external change prompt decides what happens to the file, retry once it is resolved
+            autosave.deck.pending = None;
+        } else {
+            match save_deck(autosave, deck_path, &gui_state.render_deck) {
+                Ok(deck_text) => {
+                    gui_state
+                        .deck_file_watcher
+                        .mark_written(deck_path, &deck_text);
+                    gui_state.deck_picker.refresh();
+                }
+                Err(e) => gui_state.errors.push(format!("Autosave failed: {}", e)),
+            }
+        }
+    }
+    if autosave.dock.poll(autosave.dock_generation, time) {
+        let dock_path = get_dock_path(&settings.card_dir);
+        if let Err(e) = save_dock(autosave, &dock_path, &gui_state.dock_cards) {
+            gui_state.errors.push(format!("Autosave failed: {}", e));
+        }
+    }
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Notices when the active deck file is changed by another program, such as a text editor.
+/// Files can not be watched on the web, so there it never reports a change.
+pub struct DeckFileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    content_hash: Option<u64>,
+    last_poll: f64,
+    /// The file after an external change, kept until the user picks a version
+    external_change: Option<ExternalChange>,
+    show_diff: bool,
+}
+
+struct ExternalChange {
+    deck: Result<Deck, String>,
+    text: String,
+    /// Computed the first time the difference is shown
+    diff: Option<Vec<LineDiff>>,
+}
+
+impl DeckFileWatcher {
+    pub fn new(path: &Path) -> Self {
+        let mut watcher = DeckFileWatcher {
+            path: path.to_path_buf(),
+            modified: None,
+            content_hash: None,
+            last_poll: 0.0,
+            external_change: None,
+            show_diff: false,
+        };
+        watcher.reset(path);
+        watcher
+    }
+
+    /// Accepts whatever is currently on disk as the known version
+    fn reset(&mut self, path: &Path) {
+        self.path = path.to_path_buf();
+        self.modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        self.content_hash = fs::read_to_string(path).ok().map(|text| hash_text(&text));
+        self.external_change = None;
+        self.show_diff = false;
+    }
+
+    fn mark_written(&mut self, path: &Path, text: &str) {
+        self.reset(path);
+        self.content_hash = Some(hash_text(text));
+    }
+
+    /// Returns true if the file has an external change that has not been resolved yet
+    fn check(&mut self, path: &Path) -> bool {
+        if cfg!(target_arch = "wasm32") {
+            return false;
+        }
+        if self.path != path {
+            self.reset(path);
+            return false;
+        }
+        if self.external_change.is_some() {
+            return true;
+        }
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        let Ok(text) = fs::read_to_string(path) else {
+            return false;
+        };
+        // Saving without changes only touches the modification time
+        if Some(hash_text(&text)) == self.content_hash {
+            return false;
+        }
+        let deck = ron::from_str::<Deck>(&text)
+            .map_err(|e| e.to_string())
+            .and_then(|deck| deck.check_complete().map(|()| deck));
+        self.external_change = Some(ExternalChange {
+            deck,
+            text,
+            diff: None,
+        });
+        true
+    }
+
+    pub fn poll(&mut self, path: &Path, time: f64) {
+        if time - self.last_poll < WATCH_INTERVAL {
+            return;
+        }
+        self.last_poll = time;
+        self.check(path);
+    }
+}
+
+/// Asks what to do after the active deck file was changed by another program
+pub fn external_change_prompt(ctx: &egui::Context, gui_state: &mut GuiState, settings: &Settings) {
+    let Some(external_change) = &gui_state.deck_file_watcher.external_change else {
+        return;
+    };
+    // The deck can only be changed from the editor screens, so the prompt waits until one is open
+    if !matches!(
+        gui_state.menu_stack.last(),
+        Some(GuiElement::CardEditor | GuiElement::DeckPicker | GuiElement::DeckCompare)
+    ) {
+        return;
+    }
+    let external_deck = external_change.deck.clone();
+    let deck_path = Path::new(&settings.card_file);
+    egui::Modal::new(egui::Id::new("deck_external_change")).show(ctx, |ui| {
+        ui.heading("Deck changed on disk");
+        ui.label(format!(
+            "{} was changed by another program.",
+            settings.card_file
+        ));
+        if let Err(e) = &external_deck {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("The changed file is not a valid deck: {}", e),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(external_deck.is_ok(), egui::Button::new("Reload"))
+                .on_hover_text("Replace the deck in the game with the file, this can be undone")
+                .clicked()
+            {
+                if let Ok(deck) = &external_deck {
+                    let edit = DeckEdit::SetDeck {
+                        deck: Box::new(deck.clone()),
+                    };
+                    if let Err(e) = gui_state.apply_deck_edit(edit, None) {
+                        gui_state.errors.push(e);
+                    }
+                    gui_state.gui_deck = deck.clone();
+                    gui_state.deck_autosave.mark_deck_saved();
+                    gui_state.deck_file_watcher.reset(deck_path);
+                    gui_state.deck_picker.refresh();
+                }
+            }
+            if ui
+                .button("Keep Game Version")
+                .on_hover_text("Overwrite the file with the deck in the game")
+                .clicked()
+            {
+                gui_state.deck_file_watcher.reset(deck_path);
+                save_deck_and_dock(gui_state, settings);
+            }
+            let show_diff = gui_state.deck_file_watcher.show_diff;
+            if ui.selectable_label(show_diff, "View Difference").clicked() {
+                gui_state.deck_file_watcher.show_diff = !show_diff;
+            }
+        });
+        if !gui_state.deck_file_watcher.show_diff {
+            return;
+        }
+        let Some(external_change) = &mut gui_state.deck_file_watcher.external_change else {
+            return;
+        };
+        let diff = external_change.diff.get_or_insert_with(|| {
+            let game_text = to_saved_text(&gui_state.render_deck).unwrap_or_default();
+            diff_lines(&game_text, &external_change.text)
+        });
+        ui.label("Lines only in the game are marked -, lines only in the file are marked +");
+        egui::ScrollArea::vertical()
+            .max_height(400.0)
+            .show(ui, |ui| {
+                line_diff_view(ui, diff);
+            });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::utils::get_test_dir;
+
+    /// Writes the file with a later modification time so changes are seen regardless of timestamp resolution
+    fn write_later(path: &Path, text: &str, seconds: u64) {
+        fs::write(path, text).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn pending_saves_wait_for_the_autosave_delay() {
+        let mut tracker = SaveTracker::new(0);
+        assert!(!tracker.poll(0, 0.0));
+        assert!(!tracker.poll(1, 1.0));
+        assert!(!tracker.poll(1, 1.0 + AUTOSAVE_DELAY / 2.0));
+        // Another edit restarts the delay
+        assert!(!tracker.poll(2, 2.0));
+        assert!(!tracker.poll(2, 1.0 + AUTOSAVE_DELAY));
+        assert!(tracker.poll(2, 2.0 + AUTOSAVE_DELAY));
+        // Failed saves are not retried until the value changes again
+        assert!(!tracker.poll(2, 3.0 + AUTOSAVE_DELAY));
+    }
+
+    #[test]
+    fn marking_saved_clears_the_pending_save() {
+        let mut autosave = EditorAutosave::new();
+        autosave.mark_deck_changed();
+        assert!(autosave.is_deck_dirty());
+        assert!(!autosave.deck.poll(autosave.deck_generation, 0.0));
+        autosave.mark_deck_saved();
+        assert!(!autosave.is_deck_dirty());
+        assert!(!autosave.deck.poll(autosave.deck_generation, AUTOSAVE_DELAY));
+        assert!(autosave.deck.pending.is_none());
+
+        autosave.mark_dock_changed();
+        assert!(!autosave.dock.poll(autosave.dock_generation, 0.0));
+        autosave.dock.mark_saved(autosave.dock_generation);
+        assert!(!autosave.dock.poll(autosave.dock_generation, AUTOSAVE_DELAY));
+    }
+
+    #[test]
+    fn watcher_only_reports_external_content_changes() {
+        let path = get_test_dir("deck-watcher").join("deck.ron");
+        fs::write(&path, "first").unwrap();
+        let mut watcher = DeckFileWatcher::new(&path);
+        assert!(!watcher.check(&path));
+
+        // Saving without changes only touches the modification time
+        write_later(&path, "first", 10);
+        assert!(!watcher.check(&path));
+
+        // The editor's own writes are not external changes
+        write_later(&path, "second", 20);
+        watcher.mark_written(&path, "second");
+        assert!(!watcher.check(&path));
+
+        write_later(&path, "third", 30);
+        assert!(watcher.check(&path));
+        let change = watcher.external_change.as_ref().unwrap();
+        assert_eq!(change.text, "third");
+        assert!(change.deck.is_err());
+        // The change stays reported until it is resolved
+        assert!(watcher.check(&path));
+        watcher.reset(&path);
+        assert!(!watcher.check(&path));
+    }
+}
@@ -0,0 +1,170 @@
+use egui::{
+    Color32, ScrollArea, Stroke, TextBuffer, TextEdit, TextFormat, TextStyle, Ui, text::LayoutJob,
+};
+use ron::{error::Position, ser::PrettyConfig};
+
+use crate::card_system::Deck;
+
+struct DeckTextError {
+    position: Option<Position>,
+    message: String,
+}
+
+/// Pretty RON view of a deck that is kept in sync with the visual card editor in both directions.
+/// Text that fails to parse is kept as is and the deck is left untouched until it parses again.
+#[derive(Default)]
+pub struct DeckTextEditor {
+    pub open: bool,
+    text: String,
+    /// Deck generation the text was last synced with, used to notice changes made outside the text pane
+    synced_generation: Option<u64>,
+    error: Option<DeckTextError>,
+}
+
+impl DeckTextEditor {
+    /// Returns the new deck if the text was edited into one.
+    /// The deck is only serialized again when `generation` changes.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        deck: &Deck,
+        generation: u64,
+        editable: bool,
+    ) -> Option<Deck> {
+        if self.synced_generation != Some(generation) {
+            match ron::ser::to_string_pretty(deck, PrettyConfig::default()) {
+                Ok(deck_text) => self.text = deck_text,
+                Err(e) => {
+                    ui.colored_label(Color32::RED, format!("Failed to serialize deck: {}", e));
+                    return None;
+                }
+            }
+            self.synced_generation = Some(generation);
+            self.error = None;
+        }
+
+        match &self.error {
+            Some(DeckTextError {
+                position: Some(position),
+                message,
+            }) => {
+                ui.colored_label(
+                    Color32::RED,
+                    format!(
+                        "Line {}, column {}: {}",
+                        position.line, position.col, message
+                    ),
+                );
+            }
+            Some(DeckTextError {
+                position: None,
+                message,
+            }) => {
+                ui.colored_label(Color32::RED, message);
+            }
+            None => {
+                ui.colored_label(Color32::GREEN, "Deck is valid");
+            }
+        }
+
+        let error_offset = self
+            .error
+            .as_ref()
+            .and_then(|error| error.position)
+            .map(|position| get_byte_offset(&self.text, position));
+        let mut layouter = |ui: &Ui, text: &dyn TextBuffer, wrap_width: f32| {
+            let mut job = highlight_error(ui, text.as_str(), error_offset);
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+        let response = ScrollArea::both()
+            .id_salt("deck_text_editor")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.add(
+                    TextEdit::multiline(&mut self.text)
+                        .code_editor()
+                        .interactive(editable)
+                        .desired_width(f32::INFINITY)
+                        .layouter(&mut layouter),
+                )
+            })
+            .inner;
+
+        if !response.changed() {
+            return None;
+        }
+        let parsed = match ron::from_str::<Deck>(&self.text) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.error = Some(DeckTextError {
+                    position: Some(e.position),
+                    message: e.code.to_string(),
+                });
+                return None;
+            }
+        };
+        if let Err(message) = parsed.check_complete() {
+            self.error = Some(DeckTextError {
+                position: None,
+                message,
+            });
+            return None;
+        }
+        self.error = None;
+        Some(parsed)
+    }
+
+    /// Called once the returned deck is applied, so the user's formatting is kept instead of resyncing
+    pub fn mark_synced(&mut self, generation: u64) {
+        self.synced_generation = Some(generation);
+    }
+}
+
+/// Converts a one based ron line and column into a byte offset into the text
+fn get_byte_offset(text: &str, position: Position) -> usize {
+    let mut line = 1;
+    let mut col = 1;
+    for (offset, c) in text.char_indices() {
+        if line == position.line && col == position.col {
+            return offset;
+        }
+        if c == '\n' {
+            if line == position.line {
+                return offset;
+            }
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+    text.len()
+}
+
+/// Lays the text out in the monospace font, marking from the error position to the end of its line
+fn highlight_error(ui: &Ui, text: &str, error_offset: Option<usize>) -> LayoutJob {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let text_format = TextFormat::simple(font_id, ui.visuals().text_color());
+    let mut job = LayoutJob::default();
+    let Some(error_start) = error_offset.filter(|offset| *offset < text.len()) else {
+        job.append(text, 0.0, text_format);
+        return job;
+    };
+    let error_end = text[error_start..]
+        .find('\n')
+        .map_or(text.len(), |end| error_start + end)
+        .max(error_start + text[error_start..].chars().next().map_or(0, char::len_utf8));
+    job.append(&text[..error_start], 0.0, text_format.clone());
+    job.append(
+        &text[error_start..error_end],
+        0.0,
+        TextFormat {
+            background: Color32::DARK_RED,
+            underline: Stroke::new(1.0, Color32::RED),
+            ..text_format.clone()
+        },
+    );
+    job.append(&text[error_end..], 0.0, text_format);
+    job
+}
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+const DEFAULT_MAX_HISTORY: usize = 100;
+
+struct HistoryEntry<E> {
+    edits: Vec<E>,
+    /// Undoes `edits`, in the order it has to be applied
+    inverse: Vec<E>,
+    coalesce_key: Option<Vec<usize>>,
+}
+
+/// Bounded undo/redo history where each step stores the edits made and the edits that undo them.
+/// Consecutive edits sharing a coalesce key, such as repeated +/- clicks on the same modifier, are merged into one step.
+pub struct EditHistory<E: Clone> {
+    undo_stack: VecDeque<HistoryEntry<E>>,
+    redo_stack: Vec<HistoryEntry<E>>,
+    max_size: usize,
+}
+
+impl<E: Clone> Default for EditHistory<E> {
+    fn default() -> Self {
+        EditHistory::new(DEFAULT_MAX_HISTORY)
+    }
+}
+
+impl<E: Clone> EditHistory<E> {
+    pub fn new(max_size: usize) -> Self {
+        EditHistory {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            max_size: max_size.max(1),
+        }
+    }
+
+    /// Records an edit that was just applied along with the edits that undo it
+    pub fn record(&mut self, edit: E, inverse: Vec<E>, coalesce_key: Option<Vec<usize>>) {
+        self.redo_stack.clear();
+        if let Some(last) = self
+            .undo_stack
+            .back_mut()
+            .filter(|last| coalesce_key.is_some() && last.coalesce_key == coalesce_key)
+        {
+            last.edits.push(edit);
+            // The newest edit has to be undone first
+            last.inverse.splice(0..0, inverse);
+            return;
+        }
+        self.undo_stack.push_back(HistoryEntry {
+            edits: vec![edit],
+            inverse,
+            coalesce_key,
+        });
+        while self.undo_stack.len() > self.max_size {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Returns the edits that undo the last step, in the order they have to be applied
+    pub fn undo(&mut self) -> Option<Vec<E>> {
+        let mut entry = self.undo_stack.pop_back()?;
+        let inverse = entry.inverse.clone();
+        entry.coalesce_key = None;
+        self.redo_stack.push(entry);
+        Some(inverse)
+    }
+
+    /// Returns the edits of the last undone step, in the order they have to be applied
+    pub fn redo(&mut self) -> Option<Vec<E>> {
+        let entry = self.redo_stack.pop()?;
+        let edits = entry.edits.clone();
+        self.undo_stack.push_back(entry);
+        Some(edits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesced_edits_are_undone_newest_first() {
+        let mut history = EditHistory::default();
+        history.record("add 1", vec!["remove 1"], Some(vec![2, 0]));
+        history.record("add 2", vec!["remove 2"], Some(vec![2, 0]));
+        history.record("add 3", vec!["remove 3"], None);
+
+        assert_eq!(history.undo(), Some(vec!["remove 3"]));
+        assert_eq!(history.undo(), Some(vec!["remove 2", "remove 1"]));
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(), Some(vec!["add 1", "add 2"]));
+        // A redone step is not merged with the next edit
+        history.record("add 4", vec!["remove 4"], Some(vec![2, 0]));
+        assert_eq!(history.undo(), Some(vec!["remove 4"]));
+    }
+}
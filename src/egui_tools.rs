@@ -82,6 +82,21 @@ impl EguiRenderer {
         self.state
             .handle_platform_output(window, full_output.platform_output);
 
+        // Viewport commands are not processed here, so paste requests are answered with a paste event next frame
+        let paste_requested = full_output.viewport_output.values().any(|output| {
+            output
+                .commands
+                .contains(&egui::ViewportCommand::RequestPaste)
+        });
+        if paste_requested {
+            if let Some(text) = self.state.clipboard_text() {
+                self.state
+                    .egui_input_mut()
+                    .events
+                    .push(egui::Event::Paste(text));
+            }
+        }
+
         let tris = self
             .state
             .egui_ctx()
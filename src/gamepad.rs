@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+/// Buttons named by their position so layouts of different brands line up
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Stick axes go from -1 to 1 with up and right positive, triggers go from 0 to 1
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+impl AxisDirection {
+    pub fn get_sign(&self) -> f32 {
+        match self {
+            AxisDirection::Positive => 1.0,
+            AxisDirection::Negative => -1.0,
+        }
+    }
+
+    pub fn get_symbol(&self) -> char {
+        match self {
+            AxisDirection::Positive => '+',
+            AxisDirection::Negative => '-',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadEvent {
+    Button(GamepadButton, bool),
+    Axis(GamepadAxis, f32),
+}
+
+/// Where gamepad input comes from, so it can be replaced by made up events
+pub trait GamepadBackend {
+    /// Everything that happened on the gamepads since the last poll
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// Used when no gamepad library is available for the platform
+pub struct NoGamepad;
+
+impl GamepadBackend for NoGamepad {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        vec![]
+    }
+}
+
+/// Reads every connected gamepad through gilrs
+#[cfg(not(target_arch = "wasm32"))]
+pub struct GilrsGamepad {
+    gilrs: gilrs::Gilrs,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GilrsGamepad {
+    pub fn new() -> Result<Self, String> {
+        gilrs::Gilrs::new()
+            .map(|gilrs| GilrsGamepad { gilrs })
+            .map_err(|e| e.to_string())
+    }
+
+    fn translate_button(button: gilrs::Button) -> Option<GamepadButton> {
+        use gilrs::Button;
+        Some(match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::West => GamepadButton::West,
+            Button::North => GamepadButton::North,
+            Button::LeftTrigger => GamepadButton::LeftBumper,
+            Button::RightTrigger => GamepadButton::RightBumper,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn translate_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+        use gilrs::Axis;
+        Some(match axis {
+            Axis::LeftStickX => GamepadAxis::LeftStickX,
+            Axis::LeftStickY => GamepadAxis::LeftStickY,
+            Axis::RightStickX => GamepadAxis::RightStickX,
+            Axis::RightStickY => GamepadAxis::RightStickY,
+            Axis::LeftZ => GamepadAxis::LeftTrigger,
+            Axis::RightZ => GamepadAxis::RightTrigger,
+            _ => return None,
+        })
+    }
+
+    fn translate_event(event: gilrs::EventType) -> Vec<GamepadEvent> {
+        use gilrs::EventType;
+        match event {
+            EventType::ButtonPressed(button, _) => Self::translate_button(button)
+                .map(|button| GamepadEvent::Button(button, true))
+                .into_iter()
+                .collect(),
+            EventType::ButtonReleased(button, _) => Self::translate_button(button)
+                .map(|button| GamepadEvent::Button(button, false))
+                .into_iter()
+                .collect(),
+            // Most gamepads report analog triggers as buttons with a value
+            EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                vec![GamepadEvent::Axis(GamepadAxis::LeftTrigger, value)]
+            }
+            EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                vec![GamepadEvent::Axis(GamepadAxis::RightTrigger, value)]
+            }
+            EventType::AxisChanged(axis, value, _) => Self::translate_axis(axis)
+                .map(|axis| GamepadEvent::Axis(axis, value))
+                .into_iter()
+                .collect(),
+            // Release everything so nothing stays held on a gamepad that is gone
+            EventType::Disconnected => {
+                let buttons = [
+                    GamepadButton::South,
+                    GamepadButton::East,
+                    GamepadButton::West,
+                    GamepadButton::North,
+                    GamepadButton::LeftBumper,
+                    GamepadButton::RightBumper,
+                    GamepadButton::LeftTrigger,
+                    GamepadButton::RightTrigger,
+                    GamepadButton::Select,
+                    GamepadButton::Start,
+                    GamepadButton::LeftStick,
+                    GamepadButton::RightStick,
+                    GamepadButton::DPadUp,
+                    GamepadButton::DPadDown,
+                    GamepadButton::DPadLeft,
+                    GamepadButton::DPadRight,
+                ];
+                let axes = [
+                    GamepadAxis::LeftStickX,
+                    GamepadAxis::LeftStickY,
+                    GamepadAxis::RightStickX,
+                    GamepadAxis::RightStickY,
+                    GamepadAxis::LeftTrigger,
+                    GamepadAxis::RightTrigger,
+                ];
+                buttons
+                    .into_iter()
+                    .map(|button| GamepadEvent::Button(button, false))
+                    .chain(axes.into_iter().map(|axis| GamepadEvent::Axis(axis, 0.0)))
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GamepadBackend for GilrsGamepad {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = vec![];
+        while let Some(gilrs::Event { event, .. }) = self.gilrs.next_event() {
+            events.extend(GilrsGamepad::translate_event(event));
+        }
+        events
+    }
+}
+
+/// How far an axis is pushed in a direction, from 0 at the edge of the deadzone to 1 at the end
+pub fn get_axis_amount(value: f32, direction: AxisDirection, deadzone: f32) -> f32 {
+    let value = value * direction.get_sign();
+    if value <= deadzone || deadzone >= 1.0 {
+        return 0.0;
+    }
+    ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_amount_is_zero_inside_the_deadzone() {
+        assert_eq!(get_axis_amount(0.1, AxisDirection::Positive, 0.15), 0.0);
+        assert_eq!(get_axis_amount(-0.15, AxisDirection::Negative, 0.15), 0.0);
+        assert_eq!(get_axis_amount(0.9, AxisDirection::Negative, 0.15), 0.0);
+    }
+
+    #[test]
+    fn axis_amount_scales_from_the_deadzone_edge() {
+        assert_eq!(get_axis_amount(0.625, AxisDirection::Positive, 0.25), 0.5);
+        assert_eq!(get_axis_amount(-0.625, AxisDirection::Negative, 0.25), 0.5);
+        assert_eq!(get_axis_amount(1.0, AxisDirection::Positive, 0.25), 1.0);
+        // Some gamepads report slightly past the end
+        assert_eq!(get_axis_amount(1.05, AxisDirection::Positive, 0.25), 1.0);
+        assert_eq!(get_axis_amount(0.5, AxisDirection::Positive, 0.0), 0.5);
+        assert_eq!(get_axis_amount(1.0, AxisDirection::Positive, 1.0), 0.0);
+    }
+}
@@ -1,12 +1,27 @@
-use egui::{self, Color32, Id, Rect, Rgba, Ui};
+use egui::{
+    self, Align2, Color32, CornerRadius, Id, Rect, Rgba, RichText, Stroke, StrokeKind, Ui, Vec2,
+    emath, pos2,
+};
 
 use crate::{
-    card_editor::PaletteState,
+    card_editor::{DeckComparison, PaletteState},
     card_system::{Deck, DragableCard},
-    lobby_browser::LobbyBrowser, // utils::{translate_egui_key_code, translate_egui_pointer_button},
+    deck_edit::{DeckEdit, DropableType},
+    deck_picker::DeckPicker,
+    deck_storage::{DeckFileWatcher, EditorAutosave},
+    deck_text_editor::DeckTextEditor,
+    edit_history::EditHistory,
+    health::HealthSection,
+    lobby_browser::LobbyBrowser,
+    local_player::LocalPlayer,
+    settings_layers::SettingsLayers, // utils::{translate_egui_key_code, translate_egui_pointer_button},
+    status_effects::AppliedStatusEffect,
+    utils::LineDiff,
 };
 
 pub const PADDING: f32 = 10.0;
+/// Seconds an info message stays on screen
+pub const MESSAGE_DISPLAY_TIME: f32 = 4.0;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub enum GuiElement {
@@ -19,21 +34,51 @@ pub enum GuiElement {
     SingleplayerMenu,
     ModeGui,
     DeckPicker,
+    DeckCompare,
     Settings,
 }
 
 pub struct GuiState {
     pub menu_stack: Vec<GuiElement>,
     pub errors: Vec<String>,
+    /// Info messages with the time they stay shown, unlike errors these go away on their own
+    pub messages: Vec<(String, f32)>,
     pub gui_deck: Deck,
     pub render_deck: Deck,
     pub render_deck_idx: usize,
     pub dock_cards: Vec<DragableCard>,
     pub cooldown_cache_refresh_delay: f32,
     pub palette_state: PaletteState,
+    pub palette_search: String,
+    pub palette_slot_filter: Option<DropableType>,
     pub lobby_browser: LobbyBrowser,
     pub should_exit: bool,
     pub game_just_started: bool,
+    pub deck_history: EditHistory<DeckEdit>,
+    pub deck_text_editor: DeckTextEditor,
+    pub deck_picker: DeckPicker,
+    pub deck_autosave: EditorAutosave,
+    pub deck_file_watcher: DeckFileWatcher,
+    pub deck_comparison: Option<DeckComparison>,
+    pub settings_layers: SettingsLayers,
+}
+
+impl GuiState {
+    pub fn push_message(&mut self, message: String) {
+        self.messages.push((message, MESSAGE_DISPLAY_TIME));
+    }
+
+    /// Applies an edit to the render deck and records it in the undo history
+    pub fn apply_deck_edit(
+        &mut self,
+        edit: DeckEdit,
+        coalesce_key: Option<Vec<usize>>,
+    ) -> Result<Option<DragableCard>, String> {
+        let (removed, inverse) = self.render_deck.apply_edit_with_inverse(edit.clone())?;
+        self.deck_autosave.mark_deck_changed();
+        self.deck_history.record(edit, inverse, coalesce_key);
+        Ok(removed)
+    }
 }
 
 // Helper function to center arbitrary widgets. It works by measuring the width of the widgets after rendering, and
@@ -211,90 +256,95 @@ pub fn darken(color: Color32, factor: f32) -> Color32 {
     color.into()
 }
 
-// pub fn healthbar(corner_offset: f32, ctx: &egui::Context, spectate_player: &Entity) {
-//     egui::Area::new("healthbar")
-//         .anchor(
-//             Align2::LEFT_BOTTOM,
-//             Vec2::new(corner_offset, -corner_offset),
-//         )
-//         .show(ctx, |ui| {
-//             let thickness = 1.0;
-//             let color = Color32::from_additive_luminance(255);
-//             let (player_health, player_max_health) = spectate_player.get_health_stats();
+pub fn healthbar(corner_offset: f32, ctx: &egui::Context, local_player: &LocalPlayer) {
+    let health = &local_player.health;
+    egui::Area::new("healthbar".into())
+        .anchor(
+            Align2::LEFT_BOTTOM,
+            Vec2::new(corner_offset, -corner_offset),
+        )
+        .show(ctx, |ui| {
+            let thickness = 1.0;
+            let color = Color32::from_additive_luminance(255);
+            let (player_health, player_max_health) = health.get_health_stats();
 
-//             for AppliedStatusEffect { effect, time_left } in spectate_player.status_effects.iter() {
-//                 let effect_name = match effect {
-//                     ReferencedStatusEffect::DamageOverTime(stacks) => {
-//                         format!("Damage Over Time {}", stacks)
-//                     }
-//                     ReferencedStatusEffect::Speed(stacks) => format!("Speed {}", stacks),
-//                     ReferencedStatusEffect::IncreaseDamageTaken(stacks) => {
-//                         format!("Increase Damage Taken {}", stacks)
-//                     }
-//                     ReferencedStatusEffect::IncreaseGravity(direction, stacks) => {
-//                         format!("Increase Gravity {} {}", direction, stacks)
-//                     }
-//                     ReferencedStatusEffect::Overheal(stacks) => format!("Overheal {}", stacks),
-//                     ReferencedStatusEffect::Grow(stacks) => format!("Grow {}", stacks),
-//                     ReferencedStatusEffect::IncreaseMaxHealth(stacks) => {
-//                         format!("Increase Max Health {}", stacks)
-//                     }
-//                     ReferencedStatusEffect::Invincibility => "Invincibility".to_string(),
-//                     ReferencedStatusEffect::Trapped => "Trapped".to_string(),
-//                     ReferencedStatusEffect::Lockout => "Lockout".to_string(),
-//                     ReferencedStatusEffect::OnHit(_) => "On Player Hit".to_string(),
-//                 };
-//                 ui.label(
-//                     RichText::new(format!("{}: {:.1}s", effect_name, time_left))
-//                         .color(Color32::WHITE),
-//                 );
-//             }
+            for AppliedStatusEffect { effect, time_left } in local_player.status_effects.iter() {
+                ui.label(
+                    RichText::new(format!("{}: {:.1}s", effect.get_name(), time_left))
+                        .color(Color32::WHITE),
+                );
+            }
+            for (amount, time_left) in local_player.health_changes.iter() {
+                let (text, color) = if *amount > 0.0 {
+                    (format!("-{:.0}", amount), Color32::RED)
+                } else {
+                    (format!("+{:.0}", -amount), Color32::GREEN)
+                };
+                ui.label(RichText::new(text).color(color.gamma_multiply(time_left.min(1.0))));
+            }
+            if let Some(respawn_time) = local_player.get_respawn_time() {
+                ui.label(
+                    RichText::new(format!("Respawning in {:.1}s", respawn_time))
+                        .color(Color32::WHITE),
+                );
+            }
+            if let Some(charge) = local_player.get_charge() {
+                ui.add(
+                    egui::ProgressBar::new(charge)
+                        .desired_width(200.0)
+                        .text(format!("Charge {:.0}%", 100.0 * charge)),
+                );
+            }
 
-//             ui.label(
-//                 RichText::new(format!("{} / {}", player_health, player_max_health))
-//                     .color(Color32::WHITE),
-//             );
-//             let desired_size = egui::vec2(200.0, 30.0);
-//             let (_id, rect) = ui.allocate_space(desired_size);
+            ui.label(
+                RichText::new(format!("{:.0} / {:.0}", player_health, player_max_health))
+                    .color(Color32::WHITE),
+            );
+            let desired_size = egui::vec2(200.0, 30.0);
+            let (_id, rect) = ui.allocate_space(desired_size);
 
-//             let to_screen =
-//                 emath::RectTransform::from_to(Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0), rect);
+            let to_screen =
+                emath::RectTransform::from_to(Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0), rect);
 
-//             let healthbar_size =
-//                 Rect::from_min_max(to_screen * pos2(0.0, 0.0), to_screen * pos2(1.0, 1.0));
-//             let mut health_rendered = 0.0;
-//             for health_section in spectate_player.health.iter() {
-//                 let (health_size, health_color) = match health_section {
-//                     HealthSection::Health(current, _max) => {
-//                         let prev_health_rendered = health_rendered;
-//                         health_rendered += current;
-//                         (
-//                             Rect::from_min_max(
-//                                 to_screen * pos2(prev_health_rendered / player_max_health, 0.0),
-//                                 to_screen * pos2(health_rendered / player_max_health, 1.0),
-//                             ),
-//                             Color32::WHITE,
-//                         )
-//                     }
-//                     HealthSection::Overhealth(current, _time) => {
-//                         let prev_health_rendered = health_rendered;
-//                         health_rendered += current;
-//                         (
-//                             Rect::from_min_max(
-//                                 to_screen * pos2(prev_health_rendered / player_max_health, 0.0),
-//                                 to_screen * pos2(health_rendered / player_max_health, 1.0),
-//                             ),
-//                             Color32::GREEN,
-//                         )
-//                     }
-//                 };
-//                 ui.painter().add(epaint::Shape::rect_filled(
-//                     health_size,
-//                     Rounding::ZERO,
-//                     health_color,
-//                 ));
-//             }
+            let healthbar_size =
+                Rect::from_min_max(to_screen * pos2(0.0, 0.0), to_screen * pos2(1.0, 1.0));
+            let mut health_rendered = 0.0;
+            for health_section in health.iter() {
+                let (current, health_color) = match health_section {
+                    HealthSection::Health(current, _max) => (current, Color32::WHITE),
+                    HealthSection::Overhealth(current, _time) => (current, Color32::GREEN),
+                };
+                let prev_health_rendered = health_rendered;
+                health_rendered += current;
+                let health_size = Rect::from_min_max(
+                    to_screen * pos2(prev_health_rendered / player_max_health, 0.0),
+                    to_screen * pos2(health_rendered / player_max_health, 1.0),
+                );
+                ui.painter()
+                    .rect_filled(health_size, CornerRadius::ZERO, health_color);
+            }
 
-//             ui.painter().rect_stroke(healthbar_size, CornerRadius::ZERO, Stroke::new(thickness, color));
-//         });
-// }
+            ui.painter().rect_stroke(
+                healthbar_size,
+                CornerRadius::ZERO,
+                Stroke::new(thickness, color),
+                StrokeKind::Inside,
+            );
+        });
+}
+
+/// Draws a unified line diff, removed lines in red and added lines in green
+pub fn line_diff_view(ui: &mut Ui, diff: &[LineDiff]) {
+    for line in diff {
+        let (prefix, text, color) = match line {
+            LineDiff::Same(text) => (' ', text, Color32::GRAY),
+            LineDiff::Removed(text) => ('-', text, Color32::LIGHT_RED),
+            LineDiff::Added(text) => ('+', text, Color32::LIGHT_GREEN),
+        };
+        ui.label(
+            RichText::new(format!("{} {}", prefix, text))
+                .monospace()
+                .color(color),
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    PLAYER_BASE_MAX_HEALTH,
+    card_system::{
+        BaseCard, ReferencedStatusEffect, ReferencedStatusEffects, StatusEffect,
+        UnsignedSimpleStatusEffectType,
+    },
+    playground_module,
+    status_effects::StatusEffectContainer,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum HealthSection {
+    /// Current and maximum health
+    Health(f32, f32),
+    /// Remaining overhealth and the time until it decays
+    Overhealth(f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HealthEvent {
+    Damaged(f32),
+    Healed(f32),
+    Died,
+    Respawned,
+}
+
+/// A player's health made up of a base health section followed by overhealth sections.
+/// Damage is taken from the most recently applied overhealth first and from the base health last.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Health {
+    sections: Vec<HealthSection>,
+    is_dead: bool,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health::new(PLAYER_BASE_MAX_HEALTH)
+    }
+}
+
+impl Health {
+    pub fn new(max_health: f32) -> Self {
+        Health {
+            sections: vec![HealthSection::Health(max_health, max_health)],
+            is_dead: false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HealthSection> {
+        self.sections.iter()
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.is_dead
+    }
+
+    pub fn get_health(&self) -> f32 {
+        self.sections
+            .iter()
+            .map(|section| match section {
+                HealthSection::Health(current, _) => *current,
+                HealthSection::Overhealth(current, _) => *current,
+            })
+            .sum()
+    }
+
+    pub fn get_max_health(&self) -> f32 {
+        self.sections
+            .iter()
+            .map(|section| match section {
+                HealthSection::Health(_, max) => *max,
+                HealthSection::Overhealth(..) => 0.0,
+            })
+            .sum()
+    }
+
+    pub fn get_overhealth(&self) -> f32 {
+        self.sections
+            .iter()
+            .map(|section| match section {
+                HealthSection::Health(..) => 0.0,
+                HealthSection::Overhealth(current, _) => *current,
+            })
+            .sum()
+    }
+
+    /// Returns the current health and the value the healthbar should be scaled against
+    pub fn get_health_stats(&self) -> (f32, f32) {
+        let health = self.get_health();
+        (health, self.get_max_health().max(health))
+    }
+
+    /// Sets the maximum of the base health section, keeping the current health within it
+    pub fn set_max_health(&mut self, max_health: f32) {
+        for section in self.sections.iter_mut() {
+            if let HealthSection::Health(current, max) = section {
+                *max = max_health.max(1.0);
+                *current = current.min(*max);
+            }
+        }
+    }
+
+    pub fn add_overhealth(&mut self, amount: f32, duration: f32) {
+        if self.is_dead || amount <= 0.0 || duration <= 0.0 {
+            return;
+        }
+        self.sections
+            .push(HealthSection::Overhealth(amount, duration));
+    }
+
+    pub fn apply_status_effects(&mut self, status_effects: &ReferencedStatusEffects) {
+        let duration = status_effects.duration as f32 * BaseCard::EFFECT_LENGTH_SCALE;
+        for effect in status_effects.effects.iter() {
+            if let ReferencedStatusEffect::Overheal(stacks) = effect {
+                let amount = StatusEffect::UnsignedSimpleStatusEffect(
+                    UnsignedSimpleStatusEffectType::Overheal,
+                    *stacks,
+                )
+                .get_effect_value();
+                self.add_overhealth(amount, duration);
+            }
+        }
+    }
+
+    /// Applies damage after the status effect multipliers, negative damage heals the base health section
+    pub fn apply_damage(
+        &mut self,
+        damage: f32,
+        status_effects: &StatusEffectContainer,
+    ) -> Vec<HealthEvent> {
+        if self.is_dead || damage == 0.0 {
+            return vec![];
+        }
+        if damage < 0.0 {
+            return self.heal(-damage);
+        }
+        if status_effects.is_invincible() {
+            return vec![];
+        }
+        let mut remaining = damage * status_effects.get_damage_taken_multiplier();
+        let total_damage = remaining;
+        for section in self.sections.iter_mut().rev() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let current = match section {
+                HealthSection::Health(current, _) => current,
+                HealthSection::Overhealth(current, _) => current,
+            };
+            let absorbed = remaining.min(*current);
+            *current -= absorbed;
+            remaining -= absorbed;
+        }
+        self.sections.retain(|section| match section {
+            HealthSection::Health(..) => true,
+            HealthSection::Overhealth(current, _) => *current > 0.0,
+        });
+        let mut events = vec![HealthEvent::Damaged(total_damage - remaining)];
+        if self.get_health() <= 0.0 {
+            self.is_dead = true;
+            events.push(HealthEvent::Died);
+        }
+        events
+    }
+
+    pub fn heal(&mut self, amount: f32) -> Vec<HealthEvent> {
+        if self.is_dead || amount <= 0.0 {
+            return vec![];
+        }
+        let mut healed = 0.0;
+        for section in self.sections.iter_mut() {
+            if let HealthSection::Health(current, max) = section {
+                let new_health = (*current + amount).min(*max);
+                healed += new_health - *current;
+                *current = new_health;
+            }
+        }
+        if healed <= 0.0 {
+            return vec![];
+        }
+        vec![HealthEvent::Healed(healed)]
+    }
+
+    /// Advances overhealth decay, applies damage over time and keeps the maximum health in sync with the status effects
+    pub fn tick(
+        &mut self,
+        delta_time: f32,
+        status_effects: &StatusEffectContainer,
+    ) -> Vec<HealthEvent> {
+        if self.is_dead {
+            return vec![];
+        }
+        self.set_max_health(PLAYER_BASE_MAX_HEALTH + status_effects.get_max_health_bonus());
+        for section in self.sections.iter_mut() {
+            if let HealthSection::Overhealth(_, time_left) = section {
+                *time_left -= delta_time;
+            }
+        }
+        self.sections.retain(|section| match section {
+            HealthSection::Health(..) => true,
+            HealthSection::Overhealth(_, time_left) => *time_left > 0.0,
+        });
+        self.apply_damage(
+            status_effects.get_damage_over_time() * delta_time,
+            status_effects,
+        )
+    }
+
+    pub fn respawn(&mut self, max_health: f32) -> HealthEvent {
+        *self = Health::new(max_health);
+        HealthEvent::Respawned
+    }
+
+    /// The layout is generated from `PlayerHealth` in `common.slang`
+    pub fn to_gpu(&self) -> playground_module::PlayerHealth {
+        playground_module::PlayerHealth {
+            current: self.get_health() - self.get_overhealth(),
+            max: self.get_max_health(),
+            overhealth: self.get_overhealth(),
+            is_dead: self.is_dead as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_effects() -> StatusEffectContainer {
+        StatusEffectContainer::default()
+    }
+
+    #[test]
+    fn damage_takes_the_newest_overhealth_first() {
+        let mut health = Health::new(100.0);
+        health.add_overhealth(10.0, 5.0);
+        health.add_overhealth(20.0, 5.0);
+        let events = health.apply_damage(25.0, &no_effects());
+        assert_eq!(events, vec![HealthEvent::Damaged(25.0)]);
+        let sections: Vec<_> = health.iter().copied().collect();
+        assert_eq!(
+            sections,
+            vec![
+                HealthSection::Health(100.0, 100.0),
+                HealthSection::Overhealth(5.0, 5.0),
+            ]
+        );
+        health.apply_damage(10.0, &no_effects());
+        assert_eq!(health.get_overhealth(), 0.0);
+        assert_eq!(health.get_health(), 95.0);
+    }
+
+    #[test]
+    fn status_effects_scale_or_block_damage() {
+        let mut health = Health::new(100.0);
+        let mut status_effects = StatusEffectContainer::default();
+        status_effects.apply_effect(ReferencedStatusEffect::IncreaseDamageTaken(1), 1.0);
+        assert_eq!(
+            health.apply_damage(20.0, &status_effects),
+            vec![HealthEvent::Damaged(25.0)]
+        );
+        status_effects.apply_effect(ReferencedStatusEffect::Invincibility, 1.0);
+        assert_eq!(health.apply_damage(20.0, &status_effects), vec![]);
+        assert_eq!(health.get_health(), 75.0);
+    }
+
+    #[test]
+    fn lethal_damage_kills_once_until_respawn() {
+        let mut health = Health::new(100.0);
+        assert_eq!(
+            health.apply_damage(150.0, &no_effects()),
+            vec![HealthEvent::Damaged(100.0), HealthEvent::Died]
+        );
+        assert!(health.is_dead());
+        assert_eq!(health.apply_damage(10.0, &no_effects()), vec![]);
+        assert_eq!(health.heal(10.0), vec![]);
+        assert_eq!(health.respawn(100.0), HealthEvent::Respawned);
+        assert!(!health.is_dead());
+        assert_eq!(health.get_health_stats(), (100.0, 100.0));
+    }
+
+    #[test]
+    fn healing_stops_at_max_health() {
+        let mut health = Health::new(100.0);
+        assert_eq!(health.heal(10.0), vec![]);
+        health.apply_damage(30.0, &no_effects());
+        assert_eq!(health.heal(50.0), vec![HealthEvent::Healed(30.0)]);
+        assert_eq!(
+            health.apply_damage(-5.0, &no_effects()),
+            vec![],
+            "negative damage at full health heals nothing"
+        );
+    }
+
+    #[test]
+    fn overhealth_decays_after_its_duration() {
+        let mut health = Health::new(PLAYER_BASE_MAX_HEALTH);
+        health.apply_status_effects(&ReferencedStatusEffects {
+            duration: 2,
+            effects: vec![ReferencedStatusEffect::Overheal(2)],
+        });
+        assert_eq!(health.get_overhealth(), 20.0);
+        let duration = 2.0 * BaseCard::EFFECT_LENGTH_SCALE;
+        health.tick(duration - 0.1, &no_effects());
+        assert_eq!(health.get_overhealth(), 20.0);
+        health.tick(0.2, &no_effects());
+        assert_eq!(health.get_overhealth(), 0.0);
+    }
+
+    #[test]
+    fn tick_applies_damage_over_time_and_max_health() {
+        let mut health = Health::new(PLAYER_BASE_MAX_HEALTH);
+        let mut status_effects = StatusEffectContainer::default();
+        status_effects.apply_effect(ReferencedStatusEffect::DamageOverTime(1), 10.0);
+        status_effects.apply_effect(ReferencedStatusEffect::IncreaseMaxHealth(1), 10.0);
+        let events = health.tick(0.5, &status_effects);
+        assert_eq!(events, vec![HealthEvent::Damaged(5.0)]);
+        assert_eq!(health.get_max_health(), 1.1 * PLAYER_BASE_MAX_HEALTH);
+        assert_eq!(health.get_health(), PLAYER_BASE_MAX_HEALTH - 5.0);
+    }
+
+    #[test]
+    fn gpu_health_splits_overhealth_out() {
+        let mut health = Health::new(100.0);
+        health.add_overhealth(15.0, 1.0);
+        health.apply_damage(5.0, &no_effects());
+        let gpu = health.to_gpu();
+        assert_eq!(
+            (gpu.current, gpu.max, gpu.overhealth, gpu.is_dead),
+            (100.0, 100.0, 10.0, 0)
+        );
+    }
+}
@@ -0,0 +1,667 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+use crate::{
+    card_system::{Deck, StateKeybind},
+    gamepad::{AxisDirection, GamepadAxis, GamepadButton, GamepadEvent, get_axis_amount},
+    playground_module,
+    settings_manager::{Control, ControlSettings, GamepadSettings, Settings},
+};
+
+/// Something the player can do, bound to any number of inputs
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Jump,
+    Crouch,
+    Interact,
+    /// Opens the escape menu, or closes the menu on top
+    Menu,
+    Fullscreen,
+    /// Horizontal view movement, in pixels of mouse movement
+    LookX,
+    /// Vertical view movement, in pixels of mouse movement with down positive
+    LookY,
+    /// An ability of the deck, counted through the cooldowns in order
+    Ability(usize),
+}
+
+/// The controls and the analog axes. Controls are repeated here instead of nested
+/// since the settings file can't hold an enum inside an enum.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum BindingInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButton),
+    /// Gives how far the axis is pushed in the direction past the deadzone, instead of just on or off
+    GamepadAxis(GamepadAxis, AxisDirection),
+    MouseX,
+    MouseY,
+}
+
+impl BindingInput {
+    pub fn get_control(&self) -> Option<Control> {
+        match self {
+            BindingInput::Key(key_code) => Some(Control::Key(*key_code)),
+            BindingInput::Mouse(button) => Some(Control::Mouse(*button)),
+            BindingInput::GamepadButton(button) => Some(Control::GamepadButton(*button)),
+            BindingInput::GamepadAxis(axis, direction) => {
+                Some(Control::GamepadAxis(*axis, *direction))
+            }
+            BindingInput::MouseX | BindingInput::MouseY => None,
+        }
+    }
+}
+
+impl From<Control> for BindingInput {
+    fn from(control: Control) -> Self {
+        match control {
+            Control::Key(key_code) => BindingInput::Key(key_code),
+            Control::Mouse(button) => BindingInput::Mouse(button),
+            Control::GamepadButton(button) => BindingInput::GamepadButton(button),
+            Control::GamepadAxis(axis, direction) => BindingInput::GamepadAxis(axis, direction),
+        }
+    }
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// An input driving an action. Controls only count while their modifiers are also held.
+/// A held control adds `scale` to the action, on look actions that is pixels per second.
+/// Mouse movement is multiplied by `scale`, so a negative scale inverts it.
+/// Gamepad axes add `scale` times how far they are pushed, on look actions also times the look speed after the look curve.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Binding {
+    pub action: Action,
+    pub input: BindingInput,
+    #[serde(default)]
+    pub modifiers: ModifiersState,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+impl Binding {
+    pub fn new(action: Action, control: Control) -> Self {
+        Binding {
+            action,
+            input: control.into(),
+            modifiers: ModifiersState::empty(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// Input that no longer depends on the window, so it can be made up as well
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Control(Control, bool),
+    Modifiers(ModifiersState),
+    MouseMoved(f32, f32),
+    Gamepad(GamepadEvent),
+}
+
+/// Turns controls into actions, the single place player input and ability keybinds are read from
+#[derive(Default)]
+pub struct InputMap {
+    bindings: Vec<Binding>,
+    held: HashSet<Control>,
+    modifiers: ModifiersState,
+    gamepad_axes: HashMap<GamepadAxis, f32>,
+    gamepad_settings: GamepadSettings,
+    active: HashSet<Action>,
+    /// Total mouse and held control movement of the look actions
+    look: HashMap<Action, f32>,
+    cursor_position: Option<(f64, f64)>,
+    /// Control changes since the last frame, for the ability keybinds
+    control_changes: Vec<(Control, bool)>,
+}
+
+impl InputMap {
+    pub fn new(settings: &Settings, deck: &Deck) -> Self {
+        let mut input_map = InputMap::default();
+        input_map.sync_bindings(settings, deck);
+        input_map
+    }
+
+    /// Bindings from the controls in the settings and the keybinds of the deck
+    pub fn get_bindings(settings: &Settings, deck: &Deck) -> Vec<Binding> {
+        let controls = &settings.movement_controls;
+        let mut bindings = vec![
+            Binding::new(Action::Forward, controls.forward.clone()),
+            Binding::new(Action::Backward, controls.backward.clone()),
+            Binding::new(Action::Left, controls.left.clone()),
+            Binding::new(Action::Right, controls.right.clone()),
+            Binding::new(Action::Jump, controls.jump.clone()),
+            Binding::new(Action::Crouch, controls.crouch.clone()),
+            Binding::new(Action::Interact, controls.interact.clone()),
+            Binding::new(Action::Menu, Control::Key(KeyCode::Escape)),
+            Binding::new(Action::Fullscreen, settings.fullscreen_toggle.clone()),
+            Binding {
+                action: Action::LookX,
+                input: BindingInput::MouseX,
+                modifiers: ModifiersState::empty(),
+                scale: 1.0,
+            },
+            Binding {
+                action: Action::LookY,
+                input: BindingInput::MouseY,
+                modifiers: ModifiersState::empty(),
+                scale: 1.0,
+            },
+        ];
+        let abilities = deck
+            .cooldowns
+            .iter()
+            .flat_map(|cooldown| cooldown.abilities.iter());
+        for (slot, ability) in abilities.enumerate() {
+            for control in ability.keybind.get_controls() {
+                bindings.push(Binding::new(Action::Ability(slot), control));
+            }
+        }
+        bindings.extend(controls.extra_bindings.iter().cloned());
+        bindings.extend(settings.gamepad_settings.bindings.iter().cloned());
+        bindings
+    }
+
+    /// Rebinds when the settings or deck changed, controls that are held stay held
+    pub fn sync_bindings(&mut self, settings: &Settings, deck: &Deck) {
+        let bindings = InputMap::get_bindings(settings, deck);
+        if bindings != self.bindings || settings.gamepad_settings != self.gamepad_settings {
+            self.bindings = bindings;
+            self.gamepad_settings = settings.gamepad_settings.clone();
+            self.active = self.get_active();
+        }
+    }
+
+    /// Reads the input out of a window event, returning the actions that started or stopped
+    pub fn handle_window_event(&mut self, event: &WindowEvent) -> Vec<(Action, bool)> {
+        let input_event = match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state,
+                        physical_key: PhysicalKey::Code(key_code),
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => InputEvent::Control(Control::Key(*key_code), *state == ElementState::Pressed),
+            WindowEvent::MouseInput { state, button, .. } => {
+                InputEvent::Control(Control::Mouse(*button), *state == ElementState::Pressed)
+            }
+            WindowEvent::ModifiersChanged(modifiers) => InputEvent::Modifiers(modifiers.state()),
+            WindowEvent::CursorMoved { position, .. } => {
+                let last_position = self.cursor_position.replace((position.x, position.y));
+                let Some((last_x, last_y)) = last_position else {
+                    return vec![];
+                };
+                InputEvent::MouseMoved((position.x - last_x) as f32, (position.y - last_y) as f32)
+            }
+            WindowEvent::CursorLeft { .. } => {
+                // Don't jump the view by the distance the cursor moved outside the window
+                self.cursor_position = None;
+                return vec![];
+            }
+            _ => return vec![],
+        };
+        self.handle_event(input_event)
+    }
+
+    /// Applies an input, returning the actions that started or stopped
+    pub fn handle_event(&mut self, event: InputEvent) -> Vec<(Action, bool)> {
+        match event {
+            InputEvent::Control(control, pressed) => self.set_held(control, pressed),
+            InputEvent::Modifiers(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            InputEvent::MouseMoved(x, y) => {
+                for binding in self.bindings.iter() {
+                    let movement = match binding.input {
+                        BindingInput::MouseX => x,
+                        BindingInput::MouseY => y,
+                        _ => continue,
+                    };
+                    *self.look.entry(binding.action).or_default() += movement * binding.scale;
+                }
+            }
+            InputEvent::Gamepad(GamepadEvent::Button(button, pressed)) => {
+                self.set_held(Control::GamepadButton(button), pressed)
+            }
+            InputEvent::Gamepad(GamepadEvent::Axis(axis, value)) => {
+                self.gamepad_axes.insert(axis, value);
+                for direction in [AxisDirection::Positive, AxisDirection::Negative] {
+                    let amount = get_axis_amount(value, direction, self.gamepad_settings.deadzone);
+                    self.set_held(Control::GamepadAxis(axis, direction), amount > 0.0);
+                }
+            }
+        }
+        let active = self.get_active();
+        let mut changes: Vec<(Action, bool)> = vec![];
+        for binding in self.bindings.iter() {
+            let action = binding.action;
+            let is_active = active.contains(&action);
+            if is_active != self.active.contains(&action)
+                && !changes.iter().any(|(changed, _)| *changed == action)
+            {
+                changes.push((action, is_active));
+            }
+        }
+        self.active = active;
+        changes
+    }
+
+    /// Moves the look actions by the controls held over the frame
+    pub fn tick(&mut self, delta_time: f32) {
+        for action in [Action::LookX, Action::LookY] {
+            let rate = self.get_value(action);
+            if rate != 0.0 {
+                *self.look.entry(action).or_default() += rate * delta_time;
+            }
+        }
+    }
+
+    fn set_held(&mut self, control: Control, pressed: bool) {
+        let changed = if pressed {
+            self.held.insert(control.clone())
+        } else {
+            self.held.remove(&control)
+        };
+        if changed {
+            self.control_changes.push((control, pressed));
+        }
+    }
+
+    fn is_held(&self, binding: &Binding) -> bool {
+        binding.input.get_control().is_some_and(|control| {
+            self.held.contains(&control) && self.modifiers.contains(binding.modifiers)
+        })
+    }
+
+    fn get_active(&self) -> HashSet<Action> {
+        self.bindings
+            .iter()
+            .filter(|binding| self.is_held(binding))
+            .map(|binding| binding.action)
+            .collect()
+    }
+
+    /// Sum of the held bindings of an action, each giving its scale or for gamepad axes its scale times how far the axis is pushed
+    pub fn get_value(&self, action: Action) -> f32 {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.action == action && self.is_held(binding))
+            .map(|binding| match binding.input {
+                BindingInput::GamepadAxis(axis, direction) => {
+                    binding.scale * self.get_axis_amount(action, axis, direction)
+                }
+                _ => binding.scale,
+            })
+            .sum()
+    }
+
+    fn get_axis_amount(&self, action: Action, axis: GamepadAxis, direction: AxisDirection) -> f32 {
+        let settings = &self.gamepad_settings;
+        let value = self.gamepad_axes.get(&axis).copied().unwrap_or(0.0);
+        let amount = get_axis_amount(value, direction, settings.deadzone);
+        match action {
+            Action::LookX | Action::LookY => amount.powf(settings.look_curve) * settings.look_speed,
+            _ => amount,
+        }
+    }
+
+    /// Total movement of a look action so far
+    pub fn get_look(&self, action: Action) -> f32 {
+        self.look.get(&action).copied().unwrap_or(0.0)
+    }
+
+    pub fn get_player_input(&self, controls: &ControlSettings) -> playground_module::PlayerInput {
+        let get_axis = |action: Action| self.get_value(action).clamp(0.0, 1.0);
+        playground_module::PlayerInput {
+            forward: get_axis(Action::Forward),
+            backward: get_axis(Action::Backward),
+            left: get_axis(Action::Left),
+            right: get_axis(Action::Right),
+            jump: get_axis(Action::Jump),
+            crouch: get_axis(Action::Crouch),
+            interact: get_axis(Action::Interact),
+            lookX: self.get_look(Action::LookX),
+            lookY: self.get_look(Action::LookY),
+            yawSensitivity: controls.yaw_sensitivity,
+            pitchSensitivity: if controls.invert_y {
+                -controls.pitch_sensitivity
+            } else {
+                controls.pitch_sensitivity
+            },
+            lookAcceleration: controls.acceleration,
+            maxLookAcceleration: controls.max_acceleration,
+        }
+    }
+
+    /// Feeds the control changes since the last frame to the keybind of an ability slot.
+    /// Controls bound to `Action::Ability(slot)` that the keybind doesn't use act as each of the keybind's controls.
+    pub fn update_keybind(&self, slot: usize, keybind: &mut StateKeybind) {
+        let keybind_controls = keybind.get_controls();
+        for (control, pressed) in self.control_changes.iter() {
+            if keybind_controls.contains(control) {
+                keybind.update(control, *pressed);
+                continue;
+            }
+            let is_bound = self.bindings.iter().any(|binding| {
+                binding.action == Action::Ability(slot)
+                    && binding.input.get_control().as_ref() == Some(control)
+                    && (!pressed || self.modifiers.contains(binding.modifiers))
+            });
+            if is_bound {
+                for keybind_control in keybind_controls.iter() {
+                    keybind.update(keybind_control, *pressed);
+                }
+            }
+        }
+    }
+
+    /// Call every frame once the keybinds have been updated, whether or not a game is running
+    pub fn end_frame(&mut self) {
+        self.control_changes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::GamepadBackend;
+    use cgmath::{InnerSpace, Vector2, Vector3};
+
+    fn input_map(bindings: Vec<Binding>) -> InputMap {
+        InputMap {
+            bindings,
+            ..InputMap::default()
+        }
+    }
+
+    fn get_player_input(input_map: &InputMap) -> playground_module::PlayerInput {
+        input_map.get_player_input(&ControlSettings::default())
+    }
+
+    fn press(input_map: &mut InputMap, key_code: KeyCode, pressed: bool) -> Vec<(Action, bool)> {
+        input_map.handle_event(InputEvent::Control(Control::Key(key_code), pressed))
+    }
+
+    #[test]
+    fn actions_start_and_stop_with_their_controls() {
+        let mut input_map = input_map(vec![
+            Binding::new(Action::Jump, Control::Key(KeyCode::Space)),
+            Binding::new(Action::Jump, Control::Key(KeyCode::KeyJ)),
+        ]);
+        assert_eq!(
+            press(&mut input_map, KeyCode::Space, true),
+            vec![(Action::Jump, true)]
+        );
+        // Already active through the other binding
+        assert_eq!(press(&mut input_map, KeyCode::KeyJ, true), vec![]);
+        assert_eq!(press(&mut input_map, KeyCode::Space, false), vec![]);
+        assert_eq!(
+            press(&mut input_map, KeyCode::KeyJ, false),
+            vec![(Action::Jump, false)]
+        );
+        assert_eq!(press(&mut input_map, KeyCode::KeyW, true), vec![]);
+    }
+
+    #[test]
+    fn bindings_only_count_while_their_modifiers_are_held() {
+        let mut input_map = input_map(vec![Binding {
+            modifiers: ModifiersState::SHIFT,
+            ..Binding::new(Action::Crouch, Control::Key(KeyCode::KeyC))
+        }]);
+        assert_eq!(press(&mut input_map, KeyCode::KeyC, true), vec![]);
+        assert_eq!(
+            input_map.handle_event(InputEvent::Modifiers(ModifiersState::SHIFT)),
+            vec![(Action::Crouch, true)]
+        );
+        assert_eq!(
+            input_map.handle_event(InputEvent::Modifiers(ModifiersState::empty())),
+            vec![(Action::Crouch, false)]
+        );
+    }
+
+    #[test]
+    fn player_input_sums_and_clamps_held_bindings() {
+        let mut input_map = input_map(vec![
+            Binding {
+                scale: 0.5,
+                ..Binding::new(Action::Forward, Control::Key(KeyCode::KeyW))
+            },
+            Binding {
+                scale: 0.75,
+                ..Binding::new(Action::Forward, Control::Key(KeyCode::ArrowUp))
+            },
+            Binding::new(Action::Left, Control::Key(KeyCode::KeyA)),
+        ]);
+        press(&mut input_map, KeyCode::KeyW, true);
+        assert_eq!(get_player_input(&input_map).forward, 0.5);
+        press(&mut input_map, KeyCode::ArrowUp, true);
+        assert_eq!(get_player_input(&input_map).forward, 1.0);
+        let player_input = get_player_input(&input_map);
+        assert_eq!(player_input.left, 0.0);
+        assert_eq!(player_input.backward, 0.0);
+        press(&mut input_map, KeyCode::KeyA, true);
+        assert_eq!(get_player_input(&input_map).left, 1.0);
+    }
+
+    #[test]
+    fn ability_bindings_act_as_the_keybind_controls() {
+        let mut input_map = input_map(vec![
+            Binding::new(Action::Ability(0), Control::Key(KeyCode::KeyQ)),
+            Binding::new(Action::Ability(0), Control::Key(KeyCode::KeyE)),
+        ]);
+        let mut keybind = StateKeybind::Pressed(Control::Key(KeyCode::KeyQ), false);
+        let mut other_slot = keybind.clone();
+        press(&mut input_map, KeyCode::KeyE, true);
+        input_map.update_keybind(0, &mut keybind);
+        input_map.update_keybind(1, &mut other_slot);
+        assert!(keybind.get_state());
+        assert!(!other_slot.get_state());
+
+        input_map.end_frame();
+        press(&mut input_map, KeyCode::KeyE, false);
+        input_map.update_keybind(0, &mut keybind);
+        assert!(!keybind.get_state());
+    }
+
+    #[test]
+    fn control_changes_are_only_seen_until_the_frame_ends() {
+        let mut input_map = input_map(vec![]);
+        let mut keybind = StateKeybind::OnPressed(Control::Key(KeyCode::KeyQ), false);
+        press(&mut input_map, KeyCode::KeyQ, true);
+        input_map.end_frame();
+        input_map.update_keybind(0, &mut keybind);
+        assert!(!keybind.get_state());
+    }
+
+    /// Plays back made up gamepad events
+    struct FakeGamepad(Vec<GamepadEvent>);
+
+    impl GamepadBackend for FakeGamepad {
+        fn poll(&mut self) -> Vec<GamepadEvent> {
+            std::mem::take(&mut self.0)
+        }
+    }
+
+    fn poll(input_map: &mut InputMap, gamepad: &mut impl GamepadBackend) -> Vec<(Action, bool)> {
+        gamepad
+            .poll()
+            .into_iter()
+            .flat_map(|event| input_map.handle_event(InputEvent::Gamepad(event)))
+            .collect()
+    }
+
+    #[test]
+    fn gamepad_buttons_drive_their_bindings() {
+        let mut input_map = input_map(vec![Binding::new(
+            Action::Jump,
+            Control::GamepadButton(GamepadButton::South),
+        )]);
+        let mut gamepad = FakeGamepad(vec![
+            GamepadEvent::Button(GamepadButton::East, true),
+            GamepadEvent::Button(GamepadButton::South, true),
+        ]);
+        assert_eq!(
+            poll(&mut input_map, &mut gamepad),
+            vec![(Action::Jump, true)]
+        );
+        assert_eq!(get_player_input(&input_map).jump, 1.0);
+        gamepad.0 = vec![GamepadEvent::Button(GamepadButton::South, false)];
+        assert_eq!(
+            poll(&mut input_map, &mut gamepad),
+            vec![(Action::Jump, false)]
+        );
+    }
+
+    #[test]
+    fn sticks_only_move_past_the_deadzone() {
+        let mut input_map = input_map(vec![Binding::new(
+            Action::Forward,
+            Control::GamepadAxis(GamepadAxis::LeftStickY, AxisDirection::Positive),
+        )]);
+        let deadzone = input_map.gamepad_settings.deadzone;
+        let mut gamepad = FakeGamepad(vec![GamepadEvent::Axis(GamepadAxis::LeftStickY, deadzone)]);
+        assert_eq!(poll(&mut input_map, &mut gamepad), vec![]);
+        assert_eq!(get_player_input(&input_map).forward, 0.0);
+
+        let half_pushed = deadzone + (1.0 - deadzone) * 0.5;
+        gamepad.0 = vec![GamepadEvent::Axis(GamepadAxis::LeftStickY, half_pushed)];
+        assert_eq!(
+            poll(&mut input_map, &mut gamepad),
+            vec![(Action::Forward, true)]
+        );
+        assert!((get_player_input(&input_map).forward - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn look_stick_follows_the_look_curve() {
+        let mut input_map = input_map(vec![
+            Binding::new(
+                Action::LookX,
+                Control::GamepadAxis(GamepadAxis::RightStickX, AxisDirection::Positive),
+            ),
+            Binding {
+                scale: -1.0,
+                ..Binding::new(
+                    Action::LookX,
+                    Control::GamepadAxis(GamepadAxis::RightStickX, AxisDirection::Negative),
+                )
+            },
+        ]);
+        input_map.gamepad_settings.deadzone = 0.2;
+        input_map.gamepad_settings.look_curve = 2.0;
+        input_map.gamepad_settings.look_speed = 400.0;
+        let mut gamepad = FakeGamepad(vec![GamepadEvent::Axis(GamepadAxis::RightStickX, 0.6)]);
+        poll(&mut input_map, &mut gamepad);
+        // Half pushed past the deadzone, squared by the curve
+        assert!((input_map.get_value(Action::LookX) - 100.0).abs() < 1e-3);
+        gamepad.0 = vec![GamepadEvent::Axis(GamepadAxis::RightStickX, -1.0)];
+        poll(&mut input_map, &mut gamepad);
+        assert!((input_map.get_value(Action::LookX) + 400.0).abs() < 1e-3);
+    }
+
+    /// CPU reference of the view rotation in `update_player`, which has to stay the same as it so these tests cover the shader.
+    /// Turns the view direction by the look movement since `prev_look`, skipping pitch that would look straight up or down.
+    fn get_look_direction(
+        player_input: &playground_module::PlayerInput,
+        prev_look: Vector2<f32>,
+        dir: Vector3<f32>,
+        delta_time: f32,
+    ) -> Vector3<f32> {
+        let mouse_delta = Vector2::new(player_input.lookX, player_input.lookY) - prev_look;
+        let mut sensitivity_scale = 1.0;
+        if player_input.lookAcceleration > 0.0 && delta_time > 0.0 {
+            let speed = mouse_delta.magnitude() / delta_time;
+            sensitivity_scale = (1.0 + player_input.lookAcceleration * speed / 1000.0)
+                .min(player_input.maxLookAcceleration);
+        }
+
+        let yaw_angle = -mouse_delta.x * player_input.yawSensitivity * sensitivity_scale;
+        let (sin_yaw, cos_yaw) = yaw_angle.sin_cos();
+        let dir = Vector3::new(
+            cos_yaw * dir.x + sin_yaw * dir.z,
+            dir.y,
+            -sin_yaw * dir.x + cos_yaw * dir.z,
+        );
+
+        // Rotation around the right vector, as the pitch matrix in the shader
+        let pitch_angle = -mouse_delta.y * player_input.pitchSensitivity * sensitivity_scale;
+        let right_vec = dir.cross(Vector3::unit_y()).normalize();
+        let (sin_pitch, cos_pitch) = pitch_angle.sin_cos();
+        let new_dir = dir * cos_pitch
+            + right_vec.cross(dir) * sin_pitch
+            + right_vec * right_vec.dot(dir) * (1.0 - cos_pitch);
+        if new_dir.y.abs() < 0.99 { new_dir } else { dir }
+    }
+
+    fn look_input(controls: &ControlSettings, x: f32, y: f32) -> playground_module::PlayerInput {
+        InputMap {
+            look: HashMap::from([(Action::LookX, x), (Action::LookY, y)]),
+            ..InputMap::default()
+        }
+        .get_player_input(controls)
+    }
+
+    const FORWARD: Vector3<f32> = Vector3::new(0.0, 0.0, 1.0);
+
+    #[test]
+    fn looking_down_pitches_down_unless_inverted() {
+        let mut controls = ControlSettings::default();
+        let prev_look = Vector2::new(5.0, 20.0);
+        let input = look_input(&controls, 5.0, 30.0);
+        let dir = get_look_direction(&input, prev_look, FORWARD, 0.01);
+        assert!(dir.y < 0.0);
+        assert!(dir.x.abs() < 1e-6);
+
+        controls.invert_y = true;
+        let input = look_input(&controls, 5.0, 30.0);
+        let inverted = get_look_direction(&input, prev_look, FORWARD, 0.01);
+        assert!((inverted.y + dir.y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn acceleration_is_clamped_to_the_max() {
+        let controls = ControlSettings {
+            yaw_sensitivity: 0.001,
+            acceleration: 1.0,
+            max_acceleration: 4.0,
+            ..ControlSettings::default()
+        };
+        // 10000 pixels per second would be 11 times the sensitivity without the clamp
+        let input = look_input(&controls, 100.0, 0.0);
+        let dir = get_look_direction(&input, Vector2::new(0.0, 0.0), FORWARD, 0.01);
+        assert!((dir.x - (-0.4f32).sin()).abs() < 1e-5);
+
+        // 10 pixels per second only adds 1%
+        let input = look_input(&controls, 10.0, 0.0);
+        let dir = get_look_direction(&input, Vector2::new(0.0, 0.0), FORWARD, 1.0);
+        assert!((dir.x - (-0.01f32 * 1.01).sin()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pitch_past_straight_up_is_skipped() {
+        let controls = ControlSettings::default();
+        let input = look_input(&controls, 10.0, -150.0);
+        let dir = get_look_direction(&input, Vector2::new(0.0, 0.0), FORWARD, 0.01);
+        assert_eq!(dir.y, 0.0);
+        // The yaw still applies
+        assert!((dir.x - (-0.1f32).sin()).abs() < 1e-5);
+
+        let input = look_input(&controls, 0.0, -100.0);
+        let dir = get_look_direction(&input, Vector2::new(0.0, 0.0), FORWARD, 0.01);
+        assert!((dir.y - 1.0f32.sin()).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,298 @@
+use egui::{CollapsingHeader, ComboBox, DragValue, Id, Ui};
+use winit::keyboard::KeyCode;
+
+use crate::{
+    card_system::{Keybind, ProjectileModifier, ReferencedStatusEffectType},
+    controls_editor::control_button,
+    settings_manager::Control,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum KeybindKind {
+    Pressed,
+    OnPressed,
+    OnReleased,
+    HeldFor,
+    IsOnGround,
+    HasCharges,
+    HealthBelow,
+    HasStatus,
+    And,
+    Or,
+    Not,
+    True,
+}
+
+impl KeybindKind {
+    const ALL: [KeybindKind; 12] = [
+        KeybindKind::Pressed,
+        KeybindKind::OnPressed,
+        KeybindKind::OnReleased,
+        KeybindKind::HeldFor,
+        KeybindKind::IsOnGround,
+        KeybindKind::HasCharges,
+        KeybindKind::HealthBelow,
+        KeybindKind::HasStatus,
+        KeybindKind::And,
+        KeybindKind::Or,
+        KeybindKind::Not,
+        KeybindKind::True,
+    ];
+
+    fn of(keybind: &Keybind) -> Self {
+        match keybind {
+            Keybind::Pressed(_) => KeybindKind::Pressed,
+            Keybind::OnPressed(_) => KeybindKind::OnPressed,
+            Keybind::OnReleased(_) => KeybindKind::OnReleased,
+            Keybind::HeldFor(_, _) => KeybindKind::HeldFor,
+            Keybind::IsOnGround => KeybindKind::IsOnGround,
+            Keybind::HasCharges(_) => KeybindKind::HasCharges,
+            Keybind::HealthBelow(_) => KeybindKind::HealthBelow,
+            Keybind::HasStatus(_) => KeybindKind::HasStatus,
+            Keybind::And(_, _) => KeybindKind::And,
+            Keybind::Or(_, _) => KeybindKind::Or,
+            Keybind::Not(_) => KeybindKind::Not,
+            Keybind::True => KeybindKind::True,
+        }
+    }
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            KeybindKind::Pressed => "While pressed",
+            KeybindKind::OnPressed => "On press",
+            KeybindKind::OnReleased => "On release",
+            KeybindKind::HeldFor => "Release after holding",
+            KeybindKind::IsOnGround => "On ground",
+            KeybindKind::HasCharges => "Has charges",
+            KeybindKind::HealthBelow => "Health below",
+            KeybindKind::HasStatus => "Has status",
+            KeybindKind::And => "All of",
+            KeybindKind::Or => "Any of",
+            KeybindKind::Not => "Not",
+            KeybindKind::True => "Always",
+        }
+    }
+}
+
+impl Keybind {
+    /// Readable one line description, e.g. `Space and not on ground`
+    pub fn get_summary(&self) -> String {
+        match self {
+            Keybind::Pressed(control) => format!("{}", control),
+            Keybind::OnPressed(control) => format!("press {}", control),
+            Keybind::OnReleased(control) => format!("release {}", control),
+            Keybind::HeldFor(control, seconds) => {
+                format!("release {} after {}s", control, seconds)
+            }
+            Keybind::IsOnGround => "on ground".to_string(),
+            Keybind::HasCharges(charges) => format!("{} charges", charges),
+            Keybind::HealthBelow(percent) => format!("health below {}%", percent),
+            Keybind::HasStatus(effect_type) => format!("has {:?}", effect_type),
+            Keybind::And(a, b) => {
+                format!("{} and {}", a.get_nested_summary(), b.get_nested_summary())
+            }
+            Keybind::Or(a, b) => {
+                format!("{} or {}", a.get_nested_summary(), b.get_nested_summary())
+            }
+            Keybind::Not(a) => format!("not {}", a.get_nested_summary()),
+            Keybind::True => "always".to_string(),
+        }
+    }
+
+    fn get_nested_summary(&self) -> String {
+        match self {
+            Keybind::And(_, _) | Keybind::Or(_, _) => format!("({})", self.get_summary()),
+            _ => self.get_summary(),
+        }
+    }
+
+    fn get_control(&self) -> Option<Control> {
+        match self {
+            Keybind::Pressed(control)
+            | Keybind::OnPressed(control)
+            | Keybind::OnReleased(control)
+            | Keybind::HeldFor(control, _) => Some(control.clone()),
+            Keybind::And(a, b) | Keybind::Or(a, b) => a.get_control().or_else(|| b.get_control()),
+            Keybind::Not(a) => a.get_control(),
+            _ => None,
+        }
+    }
+
+    /// Converts to another kind of node, keeping the control and children where the new kind has them
+    fn with_kind(&self, kind: KeybindKind) -> Keybind {
+        let control = self.get_control().unwrap_or(Control::Key(KeyCode::Space));
+        let (first, second) = match self {
+            Keybind::And(a, b) | Keybind::Or(a, b) => (a.clone(), b.clone()),
+            Keybind::Not(a) => (a.clone(), Box::new(Keybind::True)),
+            other => (Box::new(other.clone()), Box::new(Keybind::True)),
+        };
+        match kind {
+            KeybindKind::Pressed => Keybind::Pressed(control),
+            KeybindKind::OnPressed => Keybind::OnPressed(control),
+            KeybindKind::OnReleased => Keybind::OnReleased(control),
+            KeybindKind::HeldFor => Keybind::HeldFor(control, 0.5),
+            KeybindKind::IsOnGround => Keybind::IsOnGround,
+            KeybindKind::HasCharges => Keybind::HasCharges(1),
+            KeybindKind::HealthBelow => Keybind::HealthBelow(50),
+            KeybindKind::HasStatus => Keybind::HasStatus(ReferencedStatusEffectType::Speed),
+            KeybindKind::And => Keybind::And(first, second),
+            KeybindKind::Or => Keybind::Or(first, second),
+            KeybindKind::Not => Keybind::Not(first),
+            KeybindKind::True => Keybind::True,
+        }
+    }
+}
+
+fn draw_parameters(ui: &mut Ui, keybind: &mut Keybind, id: Id) -> bool {
+    match keybind {
+        Keybind::Pressed(control) | Keybind::OnPressed(control) | Keybind::OnReleased(control) => {
+            control_button(ui, control, id).changed()
+        }
+        Keybind::HeldFor(control, seconds) => {
+            let control_changed = control_button(ui, control, id).changed();
+            ui.add(
+                DragValue::new(seconds)
+                    .range(0.0..=ProjectileModifier::FULL_CHARGE_TIME)
+                    .speed(0.05)
+                    .suffix("s"),
+            )
+            .changed()
+                || control_changed
+        }
+        Keybind::HasCharges(charges) => ui.add(DragValue::new(charges).range(0..=100)).changed(),
+        Keybind::HealthBelow(percent) => ui
+            .add(DragValue::new(percent).range(0..=100).suffix("%"))
+            .changed(),
+        Keybind::HasStatus(effect_type) => {
+            let mut changed = false;
+            ComboBox::from_id_salt(id.with("status"))
+                .selected_text(format!("{:?}", effect_type))
+                .show_ui(ui, |ui| {
+                    for option in ReferencedStatusEffectType::ALL {
+                        changed |= ui
+                            .selectable_value(effect_type, option, format!("{:?}", option))
+                            .changed();
+                    }
+                });
+            changed
+        }
+        _ => false,
+    }
+}
+
+/// Draws one node and its children. Returns true if the node asked to be deleted,
+/// which the parent handles by replacing itself with the remaining child.
+fn draw_node(
+    ui: &mut Ui,
+    keybind: &mut Keybind,
+    id: Id,
+    can_delete: bool,
+    changed: &mut bool,
+) -> bool {
+    let mut delete = false;
+    ui.horizontal(|ui| {
+        let kind = KeybindKind::of(keybind);
+        ComboBox::from_id_salt(id.with("kind"))
+            .selected_text(kind.get_name())
+            .show_ui(ui, |ui| {
+                for option in KeybindKind::ALL {
+                    if ui
+                        .selectable_label(option == kind, option.get_name())
+                        .clicked()
+                        && option != kind
+                    {
+                        *keybind = keybind.with_kind(option);
+                        *changed = true;
+                    }
+                }
+            });
+        *changed |= draw_parameters(ui, keybind, id);
+        ui.menu_button("Wrap", |ui| {
+            let wrapped = if ui.button("All of").clicked() {
+                Some(KeybindKind::And)
+            } else if ui.button("Any of").clicked() {
+                Some(KeybindKind::Or)
+            } else if ui.button("Not").clicked() {
+                Some(KeybindKind::Not)
+            } else {
+                None
+            };
+            if let Some(wrapped) = wrapped {
+                let inner = Box::new(keybind.clone());
+                *keybind = match wrapped {
+                    KeybindKind::And => Keybind::And(inner, Box::new(Keybind::True)),
+                    KeybindKind::Or => Keybind::Or(inner, Box::new(Keybind::True)),
+                    _ => Keybind::Not(inner),
+                };
+                *changed = true;
+                ui.close();
+            }
+        });
+        if let Keybind::And(first, _) | Keybind::Or(first, _) | Keybind::Not(first) = keybind {
+            if ui
+                .button("Unwrap")
+                .on_hover_text("Replace with the first condition")
+                .clicked()
+            {
+                *keybind = *first.clone();
+                *changed = true;
+            }
+        }
+        if can_delete && ui.button("X").on_hover_text("Delete").clicked() {
+            delete = true;
+        }
+    });
+
+    let replacement = match keybind {
+        Keybind::And(a, b) | Keybind::Or(a, b) => {
+            ui.indent(id.with("children"), |ui| {
+                if draw_node(ui, a, id.with(0), true, changed) {
+                    Some(*b.clone())
+                } else if draw_node(ui, b, id.with(1), true, changed) {
+                    Some(*a.clone())
+                } else {
+                    None
+                }
+            })
+            .inner
+        }
+        Keybind::Not(a) => {
+            // Without its condition a not node means nothing, so it goes as well
+            if ui
+                .indent(id.with("children"), |ui| {
+                    draw_node(ui, a, id.with(0), true, changed)
+                })
+                .inner
+            {
+                if can_delete {
+                    delete = true;
+                    None
+                } else {
+                    Some(Keybind::True)
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    if let Some(replacement) = replacement {
+        *keybind = replacement;
+        *changed = true;
+    }
+    delete
+}
+
+/// Collapsible tree for building nested keybinds, headed by a readable summary.
+/// Returns true if the keybind was changed.
+pub fn draw_keybind_tree(ui: &mut Ui, keybind: &mut Keybind, id: Id) -> bool {
+    let mut changed = false;
+    CollapsingHeader::new(keybind.get_summary())
+        .id_salt(id)
+        .show(ui, |ui| {
+            draw_node(ui, keybind, id, false, &mut changed);
+        })
+        .header_response
+        .on_hover_text(keybind.to_expression_string());
+    changed
+}
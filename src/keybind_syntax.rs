@@ -0,0 +1,438 @@
+// Text syntax for keybind expressions, e.g. `Space & !Ground | Mouse(Left)`.
+//
+// expression := and ("|" and)*
+// and        := unary ("&" unary)*
+// unary      := "!" unary | "(" expression ")" | atom
+// atom       := control | "OnPressed(" control ")" | "OnReleased(" control ")"
+//             | "Held(" control "," seconds ")" | "Ground" | "True"
+//             | "Charges(" count ")" | "HealthBelow(" percent ")" | "Status(" ReferencedStatusEffectType ")"
+// control    := KeyCode | "Mouse(" MouseButton ")" | "Pad(" GamepadButton ")" | "Pad(" GamepadAxis ("+" | "-") ")"
+
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use crate::{
+    card_system::{Keybind, ReferencedStatusEffectType},
+    gamepad::{AxisDirection, GamepadAxis, GamepadButton},
+    settings_manager::Control,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeybindParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for KeybindParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl Keybind {
+    pub fn parse(text: &str) -> Result<Keybind, KeybindParseError> {
+        let mut parser = Parser { text, position: 0 };
+        let keybind = parser.parse_expression()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(parser.error(format!(
+                "Unexpected '{}'",
+                parser.peek().unwrap_or_default()
+            )));
+        }
+        Ok(keybind)
+    }
+
+    pub fn to_expression_string(&self) -> String {
+        self.format_with_precedence(0)
+    }
+
+    fn get_precedence(&self) -> u32 {
+        match self {
+            Keybind::Or(_, _) => 1,
+            Keybind::And(_, _) => 2,
+            Keybind::Not(_) => 3,
+            _ => 4,
+        }
+    }
+
+    fn format_with_precedence(&self, min_precedence: u32) -> String {
+        let formatted = match self {
+            Keybind::Pressed(control) => format_control(control),
+            Keybind::OnPressed(control) => format!("OnPressed({})", format_control(control)),
+            Keybind::OnReleased(control) => format!("OnReleased({})", format_control(control)),
+            Keybind::HeldFor(control, seconds) => {
+                format!("Held({}, {})", format_control(control), seconds)
+            }
+            Keybind::IsOnGround => "Ground".to_string(),
+            Keybind::HasCharges(charges) => format!("Charges({})", charges),
+            Keybind::HealthBelow(percent) => format!("HealthBelow({})", percent),
+            Keybind::HasStatus(effect_type) => format!("Status({:?})", effect_type),
+            // Binary operators are left associative, so only the right side needs parentheses at equal precedence
+            Keybind::And(a, b) => format!(
+                "{} & {}",
+                a.format_with_precedence(2),
+                b.format_with_precedence(3)
+            ),
+            Keybind::Or(a, b) => format!(
+                "{} | {}",
+                a.format_with_precedence(1),
+                b.format_with_precedence(2)
+            ),
+            Keybind::Not(a) => format!("!{}", a.format_with_precedence(3)),
+            Keybind::True => "True".to_string(),
+        };
+        if self.get_precedence() < min_precedence {
+            format!("({})", formatted)
+        } else {
+            formatted
+        }
+    }
+}
+
+fn format_control(control: &Control) -> String {
+    match control {
+        Control::Key(key) => format!("{:?}", key),
+        Control::Mouse(button) => format!("Mouse({:?})", button),
+        Control::GamepadButton(button) => format!("Pad({:?})", button),
+        Control::GamepadAxis(axis, direction) => {
+            format!("Pad({:?}{})", axis, direction.get_symbol())
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: String) -> KeybindParseError {
+        KeybindParseError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), KeybindParseError> {
+        if self.consume(expected) {
+            Ok(())
+        } else {
+            Err(self.error(match self.peek() {
+                Some(found) => format!("Expected '{}' but found '{}'", expected, found),
+                None => format!("Expected '{}' but found end of input", expected),
+            }))
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Keybind, KeybindParseError> {
+        let mut keybind = self.parse_and()?;
+        while self.consume('|') {
+            keybind = Keybind::Or(Box::new(keybind), Box::new(self.parse_and()?));
+        }
+        Ok(keybind)
+    }
+
+    fn parse_and(&mut self) -> Result<Keybind, KeybindParseError> {
+        let mut keybind = self.parse_unary()?;
+        while self.consume('&') {
+            keybind = Keybind::And(Box::new(keybind), Box::new(self.parse_unary()?));
+        }
+        Ok(keybind)
+    }
+
+    fn parse_unary(&mut self) -> Result<Keybind, KeybindParseError> {
+        if self.consume('!') {
+            return Ok(Keybind::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.consume('(') {
+            let keybind = self.parse_expression()?;
+            self.expect(')')?;
+            return Ok(keybind);
+        }
+        self.parse_atom()
+    }
+
+    fn parse_token(
+        &mut self,
+        is_token_char: impl Fn(char) -> bool,
+        expected: &str,
+    ) -> Result<(usize, &'a str), KeybindParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !is_token_char(c) {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+        if start == self.position {
+            return Err(self.error(match self.peek() {
+                Some(found) => format!("Expected {} but found '{}'", expected, found),
+                None => format!("Expected {} but found end of input", expected),
+            }));
+        }
+        Ok((start, &self.text[start..self.position]))
+    }
+
+    fn parse_identifier(&mut self) -> Result<(usize, &'a str), KeybindParseError> {
+        self.parse_token(|c| c.is_ascii_alphanumeric() || c == '_', "a keybind")
+    }
+
+    fn parse_number<T: std::str::FromStr>(&mut self) -> Result<T, KeybindParseError> {
+        let (start, number) =
+            self.parse_token(|c| c.is_ascii_alphanumeric() || c == '.', "a number")?;
+        number.parse().map_err(|_| KeybindParseError {
+            position: start,
+            message: format!("Invalid number '{}'", number),
+        })
+    }
+
+    fn parse_ron_identifier<T: DeserializeOwned>(
+        &mut self,
+        kind: &str,
+    ) -> Result<T, KeybindParseError> {
+        let (start, identifier) = self.parse_identifier()?;
+        // Allow variants with a payload such as `Mouse(Other(3))`
+        let end = if self.peek() == Some('(') {
+            let mut depth = 0;
+            let mut end = self.position;
+            for (offset, c) in self.text[self.position..].char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    end = self.position + offset + 1;
+                    break;
+                }
+            }
+            end
+        } else {
+            self.position
+        };
+        let ron_text = &self.text[start..end];
+        match ron::from_str(ron_text) {
+            Ok(value) => {
+                self.position = end;
+                Ok(value)
+            }
+            Err(_) => Err(KeybindParseError {
+                position: start,
+                message: format!("Unknown {} '{}'", kind, identifier),
+            }),
+        }
+    }
+
+    fn parse_control(&mut self) -> Result<Control, KeybindParseError> {
+        self.skip_whitespace();
+        if self.text[self.position..].starts_with("Mouse") {
+            let start = self.position;
+            self.position += "Mouse".len();
+            if self.consume('(') {
+                let button = self.parse_ron_identifier::<MouseButton>("mouse button")?;
+                self.expect(')')?;
+                return Ok(Control::Mouse(button));
+            }
+            self.position = start;
+        }
+        if self.text[self.position..].starts_with("Pad") {
+            let start = self.position;
+            self.position += "Pad".len();
+            if self.consume('(') {
+                return self.parse_gamepad_control();
+            }
+            self.position = start;
+        }
+        Ok(Control::Key(self.parse_ron_identifier::<KeyCode>("key")?))
+    }
+
+    /// Parses the inside of `Pad(...)`, a button or an axis followed by the direction
+    fn parse_gamepad_control(&mut self) -> Result<Control, KeybindParseError> {
+        let (identifier_start, identifier) = self.parse_identifier()?;
+        let direction = if self.consume('+') {
+            Some(AxisDirection::Positive)
+        } else if self.consume('-') {
+            Some(AxisDirection::Negative)
+        } else {
+            None
+        };
+        let control = match direction {
+            Some(direction) => ron::from_str::<GamepadAxis>(identifier)
+                .map(|axis| Control::GamepadAxis(axis, direction)),
+            None => ron::from_str::<GamepadButton>(identifier).map(Control::GamepadButton),
+        };
+        let Ok(control) = control else {
+            return Err(KeybindParseError {
+                position: identifier_start,
+                message: format!(
+                    "Unknown gamepad {} '{}'",
+                    if direction.is_some() {
+                        "axis"
+                    } else {
+                        "button"
+                    },
+                    identifier
+                ),
+            });
+        };
+        self.expect(')')?;
+        Ok(control)
+    }
+
+    fn parse_atom(&mut self) -> Result<Keybind, KeybindParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let (_, identifier) = self.parse_identifier()?;
+        let keybind = match identifier {
+            "Ground" => Keybind::IsOnGround,
+            "True" => Keybind::True,
+            "OnPressed" | "OnReleased" | "Held" | "Charges" | "HealthBelow" | "Status" => {
+                self.expect('(')?;
+                let keybind = match identifier {
+                    "OnPressed" => Keybind::OnPressed(self.parse_control()?),
+                    "OnReleased" => Keybind::OnReleased(self.parse_control()?),
+                    "Held" => {
+                        let control = self.parse_control()?;
+                        self.expect(',')?;
+                        Keybind::HeldFor(control, self.parse_number()?)
+                    }
+                    "Charges" => Keybind::HasCharges(self.parse_number()?),
+                    "HealthBelow" => Keybind::HealthBelow(self.parse_number()?),
+                    "Status" => Keybind::HasStatus(
+                        self.parse_ron_identifier::<ReferencedStatusEffectType>("status effect")?,
+                    ),
+                    _ => unreachable!(),
+                };
+                self.expect(')')?;
+                keybind
+            }
+            _ => {
+                self.position = start;
+                Keybind::Pressed(self.parse_control()?)
+            }
+        };
+        Ok(keybind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key_code: KeyCode) -> Box<Keybind> {
+        Box::new(Keybind::Pressed(Control::Key(key_code)))
+    }
+
+    fn round_trip(keybind: Keybind) {
+        let text = keybind.to_expression_string();
+        assert_eq!(Keybind::parse(&text), Ok(keybind), "parsing {}", text);
+    }
+
+    #[test]
+    fn atoms_round_trip() {
+        round_trip(Keybind::Pressed(Control::Key(KeyCode::Space)));
+        round_trip(Keybind::OnPressed(Control::Mouse(MouseButton::Left)));
+        round_trip(Keybind::OnReleased(Control::Mouse(MouseButton::Other(3))));
+        round_trip(Keybind::HeldFor(Control::Key(KeyCode::KeyQ), 0.75));
+        round_trip(Keybind::Pressed(Control::GamepadButton(
+            GamepadButton::South,
+        )));
+        round_trip(Keybind::Pressed(Control::GamepadAxis(
+            GamepadAxis::LeftTrigger,
+            AxisDirection::Positive,
+        )));
+        round_trip(Keybind::IsOnGround);
+        round_trip(Keybind::HasCharges(2));
+        round_trip(Keybind::HealthBelow(30));
+        for effect_type in ReferencedStatusEffectType::ALL {
+            round_trip(Keybind::HasStatus(effect_type));
+        }
+        round_trip(Keybind::True);
+    }
+
+    #[test]
+    fn operators_round_trip_with_the_needed_parentheses() {
+        let a_or_b = Keybind::Or(key(KeyCode::KeyA), key(KeyCode::KeyB));
+        let and = Keybind::And(Box::new(a_or_b.clone()), key(KeyCode::KeyC));
+        assert_eq!(and.to_expression_string(), "(KeyA | KeyB) & KeyC");
+        round_trip(and);
+
+        let right_nested = Keybind::Or(key(KeyCode::KeyC), Box::new(a_or_b.clone()));
+        assert_eq!(right_nested.to_expression_string(), "KeyC | (KeyA | KeyB)");
+        round_trip(right_nested);
+
+        let not = Keybind::Not(Box::new(Keybind::And(
+            key(KeyCode::KeyA),
+            Box::new(Keybind::IsOnGround),
+        )));
+        assert_eq!(not.to_expression_string(), "!(KeyA & Ground)");
+        round_trip(not);
+        round_trip(Keybind::Not(Box::new(Keybind::Not(Box::new(a_or_b)))));
+    }
+
+    #[test]
+    fn parse_precedence_and_whitespace() {
+        assert_eq!(
+            Keybind::parse(" KeyA|KeyB &  !Ground "),
+            Ok(Keybind::Or(
+                key(KeyCode::KeyA),
+                Box::new(Keybind::And(
+                    key(KeyCode::KeyB),
+                    Box::new(Keybind::Not(Box::new(Keybind::IsOnGround)))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn identifiers_do_not_take_dots() {
+        let err = Keybind::parse("Space.Foo").unwrap_err();
+        assert_eq!(err.position, 5);
+        assert!(Keybind::parse("Held(Space, 0.5.)").is_err());
+        assert_eq!(
+            Keybind::parse("Held(Space, 0.5)"),
+            Ok(Keybind::HeldFor(Control::Key(KeyCode::Space), 0.5))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let err = Keybind::parse("Space & Bogus").unwrap_err();
+        assert_eq!(err.position, 8);
+        assert_eq!(err.message, "Unknown key 'Bogus'");
+        let err = Keybind::parse("Pad(LeftStickX)").unwrap_err();
+        assert_eq!(err.message, "Unknown gamepad button 'LeftStickX'");
+        let err = Keybind::parse("Status(Bogus)").unwrap_err();
+        assert_eq!(err.position, 7);
+        assert_eq!(err.message, "Unknown status effect 'Bogus'");
+        assert!(Keybind::parse("(Space").is_err());
+        assert!(Keybind::parse("").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{
+    PLAYER_BASE_MAX_HEALTH,
+    card_system::{
+        CardManager, Deck, DirectionCard, KeybindPlayerState, ProjectileModifier,
+        ReferencedBaseCard, ReferencedEffect, StateKeybind,
+    },
+    health::{Health, HealthEvent},
+    input_map::InputMap,
+    playground_module,
+    status_effects::StatusEffectContainer,
+};
+
+const RESPAWN_TIME: f32 = 3.0;
+/// How long a health change stays listed above the healthbar
+const HEALTH_CHANGE_DISPLAY_TIME: f32 = 1.0;
+
+/// Charges shared by the abilities of one cooldown
+struct CooldownState {
+    max_charges: u32,
+    charges: u32,
+    is_reloading: bool,
+    /// Time to recover a charge, or all charges when reloading
+    cooldown: f32,
+    time_to_charge: f32,
+}
+
+struct AbilityState {
+    card: ReferencedBaseCard,
+    keybind: StateKeybind,
+    cooldown_idx: usize,
+    /// Time after a cast of this ability before the cooldown's abilities can be cast again
+    recovery_time: f32,
+}
+
+/// The parts of the local player simulated on the CPU: abilities, health and status effects.
+/// Movement stays in `update_player`, which gets the results through the player effects and health uniforms.
+pub struct LocalPlayer {
+    pub health: Health,
+    pub status_effects: StatusEffectContainer,
+    /// Time until a dead player respawns
+    respawn_timer: Option<f32>,
+    /// Recent damage as positive and healing as negative amounts, with the time they stay shown
+    pub health_changes: Vec<(f32, f32)>,
+    /// Generation of the deck the abilities were built from
+    deck_generation: u64,
+    card_manager: CardManager,
+    cooldowns: Vec<CooldownState>,
+    /// Time until each cooldown's abilities can be cast again
+    recovery: Vec<f32>,
+    abilities: Vec<AbilityState>,
+    /// Impulses cast since the last update, applied once by the GPU physics
+    knockback: Vec<(f32, DirectionCard)>,
+}
+
+impl LocalPlayer {
+    pub fn new(deck: &Deck, deck_generation: u64) -> Self {
+        let mut local_player = LocalPlayer {
+            health: Health::default(),
+            status_effects: StatusEffectContainer::default(),
+            respawn_timer: None,
+            health_changes: vec![],
+            deck_generation,
+            card_manager: CardManager::default(),
+            cooldowns: vec![],
+            recovery: vec![],
+            abilities: vec![],
+            knockback: vec![],
+        };
+        local_player.set_deck(deck, deck_generation);
+        local_player
+    }
+
+    pub fn get_deck_generation(&self) -> u64 {
+        self.deck_generation
+    }
+
+    /// Rebuilds the abilities and passive from an edited deck, cooldowns start out full.
+    /// Health is kept, timed status effects are dropped since they may refer to cards of the old deck.
+    pub fn set_deck(&mut self, deck: &Deck, deck_generation: u64) {
+        let mut card_manager = CardManager::default();
+        self.status_effects.clear();
+        self.status_effects.set_passive(
+            deck.passive
+                .passive_effects
+                .iter()
+                .flat_map(|effect| card_manager.register_status_effect(effect.clone()))
+                .collect(),
+        );
+        let total_impact = deck.get_total_impact();
+        let mut cooldowns = vec![];
+        let mut abilities = vec![];
+        for cooldown in deck.cooldowns.iter() {
+            if cooldown.abilities.is_empty() {
+                continue;
+            }
+            let (cooldown_time, recovery) = cooldown.get_cooldown_recovery(total_impact);
+            let referenced_cooldown = card_manager.register_cooldown(cooldown.clone());
+            for ((card, keybind), recovery) in
+                referenced_cooldown.abilities.into_iter().zip(recovery)
+            {
+                abilities.push(AbilityState {
+                    card,
+                    keybind: keybind.into(),
+                    cooldown_idx: cooldowns.len(),
+                    recovery_time: recovery,
+                });
+            }
+            cooldowns.push(CooldownState {
+                max_charges: referenced_cooldown.max_charges,
+                charges: referenced_cooldown.max_charges,
+                is_reloading: referenced_cooldown.is_reloading,
+                cooldown: cooldown_time,
+                time_to_charge: 0.0,
+            });
+        }
+        self.deck_generation = deck_generation;
+        self.card_manager = card_manager;
+        self.recovery = vec![0.0; cooldowns.len()];
+        self.cooldowns = cooldowns;
+        self.abilities = abilities;
+    }
+
+    /// Reads the ability keybinds, casts the abilities that activated and advances the timers.
+    /// Abilities are counted in the same order as `Action::Ability`.
+    pub fn update(&mut self, input_map: &InputMap, delta_time: f32, can_cast: bool) {
+        self.knockback.clear();
+        for cooldown in self.cooldowns.iter_mut() {
+            if cooldown.charges < cooldown.max_charges
+                && !(cooldown.is_reloading && cooldown.charges > 0)
+            {
+                cooldown.time_to_charge -= delta_time;
+                if cooldown.time_to_charge <= 0.0 {
+                    cooldown.charges = if cooldown.is_reloading {
+                        cooldown.max_charges
+                    } else {
+                        cooldown.charges + 1
+                    };
+                    cooldown.time_to_charge += cooldown.cooldown;
+                }
+            }
+        }
+        for recovery in self.recovery.iter_mut() {
+            *recovery = (*recovery - delta_time).max(0.0);
+        }
+
+        let (current_health, max_health) = self.health.get_health_stats();
+        let mut casts = vec![];
+        for (slot, ability) in self.abilities.iter_mut().enumerate() {
+            input_map.update_keybind(slot, &mut ability.keybind);
+            ability.keybind.tick(delta_time);
+            ability.keybind.update_player_state(&KeybindPlayerState {
+                charges: self.cooldowns[ability.cooldown_idx].charges,
+                health_fraction: current_health / max_health,
+                status_effects: &self.status_effects,
+            });
+            let cooldown = &mut self.cooldowns[ability.cooldown_idx];
+            let recovery = &mut self.recovery[ability.cooldown_idx];
+            if can_cast
+                && !self.health.is_dead()
+                && ability.keybind.get_state()
+                && cooldown.charges > 0
+                && *recovery <= 0.0
+                && !self.status_effects.is_locked_out()
+            {
+                if cooldown.charges == cooldown.max_charges {
+                    cooldown.time_to_charge = cooldown.cooldown;
+                }
+                cooldown.charges -= 1;
+                *recovery = ability.recovery_time;
+                casts.push((ability.card, ability.keybind.get_charge_time()));
+            }
+            ability.keybind.clear();
+        }
+        let mut events = vec![];
+        for (card, charge_time) in casts {
+            events.extend(self.cast(card, charge_time));
+        }
+
+        self.status_effects.tick(delta_time);
+        events.extend(self.health.tick(delta_time, &self.status_effects));
+        if let Some(respawn_timer) = self.respawn_timer.as_mut() {
+            *respawn_timer -= delta_time;
+            if *respawn_timer <= 0.0 {
+                self.respawn_timer = None;
+                events.push(self.health.respawn(PLAYER_BASE_MAX_HEALTH));
+            }
+        }
+
+        for (_, time_left) in self.health_changes.iter_mut() {
+            *time_left -= delta_time;
+        }
+        self.health_changes
+            .retain(|(_, time_left)| *time_left > 0.0);
+        for event in events {
+            self.handle_health_event(event);
+        }
+    }
+
+    fn handle_health_event(&mut self, event: HealthEvent) {
+        match event {
+            HealthEvent::Damaged(amount) => {
+                self.health_changes
+                    .push((amount, HEALTH_CHANGE_DISPLAY_TIME));
+            }
+            HealthEvent::Healed(amount) => {
+                self.health_changes
+                    .push((-amount, HEALTH_CHANGE_DISPLAY_TIME));
+            }
+            HealthEvent::Died => {
+                self.status_effects.clear();
+                self.respawn_timer = Some(RESPAWN_TIME);
+            }
+            HealthEvent::Respawned => {
+                self.health_changes.clear();
+            }
+        }
+    }
+
+    /// How far the most charged held ability is towards a full charge
+    pub fn get_charge(&self) -> Option<f32> {
+        self.abilities
+            .iter()
+            .filter_map(|ability| ability.keybind.get_held_time())
+            .reduce(f32::max)
+            .map(|held_time| (held_time / ProjectileModifier::FULL_CHARGE_TIME).min(1.0))
+    }
+
+    /// Time until a dead player respawns
+    pub fn get_respawn_time(&self) -> Option<f32> {
+        self.respawn_timer
+    }
+
+    /// Applies the parts of a card that affect the caster.
+    /// Projectiles are left out since this port has no projectile simulation yet.
+    fn cast(&mut self, card: ReferencedBaseCard, charge_time: f32) -> Vec<HealthEvent> {
+        let cast_effects = self
+            .card_manager
+            .get_effects_from_base_card(card, charge_time);
+        let mut events = vec![];
+        for effect in cast_effects.effects.iter() {
+            events.extend(self.apply_effect(effect));
+        }
+        for status_effects in cast_effects.status_effects.iter() {
+            self.status_effects.apply(status_effects);
+            self.health.apply_status_effects(status_effects);
+        }
+        events
+    }
+
+    fn apply_effect(&mut self, effect: &ReferencedEffect) -> Vec<HealthEvent> {
+        match effect {
+            ReferencedEffect::Damage(damage) => self
+                .health
+                .apply_damage(*damage as f32, &self.status_effects),
+            ReferencedEffect::Cleanse => {
+                self.status_effects.cleanse();
+                vec![]
+            }
+            ReferencedEffect::Knockback(knockback, direction) => {
+                self.knockback.push((*knockback as f32, direction.clone()));
+                vec![]
+            }
+            // Cast directly, the player is already where the teleport was activated
+            ReferencedEffect::Teleport => vec![],
+        }
+    }
+
+    fn get_knockback(&self, direction: DirectionCard) -> f32 {
+        self.knockback
+            .iter()
+            .filter(|(_, knockback_direction)| *knockback_direction == direction)
+            .map(|(knockback, _)| knockback)
+            .sum()
+    }
+
+    pub fn get_player_effects(&self) -> playground_module::PlayerEffects {
+        playground_module::PlayerEffects {
+            speed_multiplier: self.status_effects.get_speed_multiplier(),
+            size_multiplier: self.status_effects.get_size_multiplier(),
+            forward_gravity: self
+                .status_effects
+                .get_gravity_change(DirectionCard::Forward),
+            up_gravity: self.status_effects.get_gravity_change(DirectionCard::Up),
+            movement_gravity: self
+                .status_effects
+                .get_gravity_change(DirectionCard::Movement),
+            forward_knockback: self.get_knockback(DirectionCard::Forward),
+            up_knockback: self.get_knockback(DirectionCard::Up),
+            movement_knockback: self.get_knockback(DirectionCard::Movement),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        input_map::InputEvent,
+        settings_manager::{Control, Settings},
+    };
+    use winit::keyboard::KeyCode;
+
+    const DECK: &str = "(
+        cooldowns: [
+            (
+                modifiers: [],
+                abilities: [
+                    (
+                        card: MultiCast([
+                            Effect(Knockback(10, Up)),
+                            StatusEffects(2, [SimpleStatusEffect(Speed, 1)]),
+                            Projectile([]),
+                        ], [Duplication(1)]),
+                        keybind: OnPressed(Key(KeyQ)),
+                    ),
+                ],
+            ),
+        ],
+        passive: (passive_effects: []),
+    )";
+
+    fn press_and_update(local_player: &mut LocalPlayer, input_map: &mut InputMap, press: bool) {
+        if press {
+            input_map.handle_event(InputEvent::Control(Control::Key(KeyCode::KeyQ), true));
+        }
+        local_player.update(input_map, 0.01, true);
+        input_map.end_frame();
+    }
+
+    #[test]
+    fn casting_applies_effects_once_and_uses_a_charge() {
+        let deck: Deck = ron::from_str(DECK).unwrap();
+        let mut input_map = InputMap::new(&Settings::default(), &deck);
+        let mut local_player = LocalPlayer::new(&deck, 0);
+        let max_charges = local_player.cooldowns[0].max_charges;
+
+        press_and_update(&mut local_player, &mut input_map, true);
+        assert_eq!(local_player.cooldowns[0].charges, max_charges - 1);
+        assert_eq!(local_player.status_effects.iter().count(), 1);
+        assert_eq!(local_player.get_player_effects().up_knockback, 10.0);
+
+        // The knockback is only sent for the frame it was cast in
+        press_and_update(&mut local_player, &mut input_map, false);
+        assert_eq!(local_player.get_player_effects().up_knockback, 0.0);
+        assert_eq!(local_player.status_effects.iter().count(), 1);
+    }
+
+    #[test]
+    fn cooldowns_recover_charges() {
+        let deck: Deck = ron::from_str(DECK).unwrap();
+        let mut input_map = InputMap::new(&Settings::default(), &deck);
+        let mut local_player = LocalPlayer::new(&deck, 0);
+        let max_charges = local_player.cooldowns[0].max_charges;
+
+        press_and_update(&mut local_player, &mut input_map, true);
+        let cooldown = local_player.cooldowns[0].cooldown;
+        local_player.update(&input_map, cooldown, true);
+        assert_eq!(local_player.cooldowns[0].charges, max_charges);
+    }
+
+    #[test]
+    fn changed_decks_rebuild_the_abilities_and_keep_health() {
+        let deck: Deck = ron::from_str(DECK).unwrap();
+        let mut local_player = LocalPlayer::new(&deck, 0);
+        local_player
+            .health
+            .apply_damage(10.0, &local_player.status_effects);
+        let (health, _) = local_player.health.get_health_stats();
+
+        let mut edited_deck = deck.clone();
+        edited_deck.cooldowns.push(deck.cooldowns[0].clone());
+        local_player.set_deck(&edited_deck, 1);
+        assert_eq!(local_player.get_deck_generation(), 1);
+        assert_eq!(local_player.abilities.len(), 2);
+        assert_eq!(local_player.cooldowns.len(), 2);
+        assert_eq!(local_player.health.get_health_stats().0, health);
+    }
+}
@@ -1,38 +1,66 @@
 mod card_editor;
 mod card_system;
+mod controls_editor;
+mod deck_edit;
+mod deck_picker;
+mod deck_storage;
+mod deck_text_editor;
+mod edit_history;
 mod egui_tools;
+mod gamepad;
 mod gui;
+mod health;
+mod input_map;
+mod keybind_editor;
+mod keybind_syntax;
 use egui_probe::Probe;
 mod lobby_browser;
+mod local_player;
+mod settings_layers;
 mod settings_manager;
 mod shared;
+mod status_effects;
+mod trajectory_preview;
 mod utils;
 
 use slang_playground_compiler::CompilationResult;
 use slang_renderer::Renderer;
-use std::{fs, sync::Arc};
+use std::{fs, path::Path, sync::Arc};
 use wgpu::Features;
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::{ElementState, KeyEvent, WindowEvent},
+    event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowId},
 };
 
 #[cfg(target_family = "wasm")]
 use std::panic;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::gamepad::GilrsGamepad;
 #[cfg(debug_assertions)]
 #[cfg(target_family = "wasm")]
 extern crate console_error_panic_hook;
 
 use crate::card_system::Deck;
+use crate::deck_picker::{DeckPicker, deck_picker};
+use crate::deck_storage::{
+    DeckFileWatcher, EditorAutosave, external_change_prompt, load_dock, save_deck_and_dock,
+};
+use crate::deck_text_editor::DeckTextEditor;
+use crate::edit_history::EditHistory;
 use crate::{
-    card_editor::{PaletteState, card_editor},
-    gui::{GuiElement, GuiState, PADDING, horizontal_centerer, vertical_centerer},
+    card_editor::{PaletteState, card_editor, deck_compare},
+    controls_editor::{capture_gamepad_event, capture_window_event, controls_editor},
+    gamepad::{GamepadBackend, NoGamepad},
+    gui::{GuiElement, GuiState, PADDING, healthbar, horizontal_centerer, vertical_centerer},
+    input_map::{Action, InputEvent, InputMap},
     lobby_browser::LobbyBrowser,
-    settings_manager::{Control, Settings},
+    local_player::LocalPlayer,
+    settings_layers::{CommandLine, USAGE, load_settings},
+    settings_manager::Settings,
 };
 
 pub const PLAYER_BASE_MAX_HEALTH: f32 = 100.0;
@@ -117,74 +145,165 @@ fn configure_surface(
     surface.configure(device, &surface_config);
 }
 
-const SETTINGS_FILE: &str = "settings.yaml";
-
 struct App {
     render_data: Option<RenderData>,
     game: Option<Renderer>,
-    player_input: playground_module::PlayerInput,
+    /// Abilities and status effects of the player in `game`
+    local_player: Option<LocalPlayer>,
+    input_map: InputMap,
+    gamepad: Box<dyn GamepadBackend>,
     #[cfg(target_arch = "wasm32")]
     state_receiver: Option<futures::channel::oneshot::Receiver<RenderData>>,
     compilation: CompilationResult,
     surface_format: wgpu::TextureFormat,
     settings: Settings,
     gui_state: GuiState,
+    /// Preset given with `--preset`, started once the window is ready
+    startup_preset: Option<String>,
 }
 
 impl App {
-    fn new(compilation: CompilationResult) -> Self {
+    fn new(compilation: CompilationResult, command_line: CommandLine) -> Self {
+        let (settings, settings_layers, mut errors) = load_settings(&command_line);
+        let startup_preset = command_line.preset.filter(|preset| {
+            let exists = settings.preset_settings.iter().any(|p| &p.name == preset);
+            if !exists {
+                errors.push(format!("There is no preset named {}", preset));
+            }
+            exists
+        });
+
         #[cfg(target_arch = "wasm32")]
-        let settings_string = include_str!("../settings.yaml");
+        let player_deck: Deck = Deck::empty();
         #[cfg(not(target_arch = "wasm32"))]
-        let settings_string = fs::read_to_string(SETTINGS_FILE).unwrap();
-        let settings = Settings::from_string(&settings_string);
+        let player_deck: Deck = match fs::read_to_string(&settings.card_file)
+            .map_err(|e| e.to_string())
+            .and_then(|deck_text| ron::from_str(&deck_text).map_err(|e| e.to_string()))
+        {
+            Ok(deck) => deck,
+            Err(e) => {
+                errors.push(format!("Failed to load deck {}: {}", settings.card_file, e));
+                Deck::empty()
+            }
+        };
+        let dock_cards = load_dock(&settings.card_dir).unwrap_or_else(|e| {
+            errors.push(e);
+            vec![]
+        });
 
         #[cfg(target_arch = "wasm32")]
-        let player_deck: Deck = Deck::empty();
+        let gamepad: Box<dyn GamepadBackend> = Box::new(NoGamepad);
         #[cfg(not(target_arch = "wasm32"))]
-        let player_deck: Deck =
-            ron::from_str(fs::read_to_string(&settings.card_file).unwrap().as_str()).unwrap();
+        let gamepad: Box<dyn GamepadBackend> = match GilrsGamepad::new() {
+            Ok(gamepad) => Box::new(gamepad),
+            Err(e) => {
+                errors.push(format!("Gamepads are unavailable: {}", e));
+                Box::new(NoGamepad)
+            }
+        };
 
+        let mut menu_stack = vec![GuiElement::MainMenu];
+        if startup_preset.is_some() {
+            menu_stack.push(GuiElement::SingleplayerMenu);
+        }
         let gui_state = GuiState {
-            menu_stack: vec![GuiElement::MainMenu],
-            errors: Vec::new(),
+            menu_stack,
+            errors,
+            messages: vec![],
             gui_deck: player_deck.clone(),
             render_deck: player_deck.clone(),
-            dock_cards: vec![],
+            deck_autosave: EditorAutosave::default(),
+            deck_file_watcher: DeckFileWatcher::new(Path::new(&settings.card_file)),
+            deck_comparison: None,
+            dock_cards,
             render_deck_idx: 0,
             cooldown_cache_refresh_delay: 0.0,
             palette_state: PaletteState::BaseCards,
+            palette_search: String::new(),
+            palette_slot_filter: None,
             should_exit: false,
             game_just_started: false,
             lobby_browser: LobbyBrowser::new(),
+            deck_history: EditHistory::default(),
+            deck_text_editor: DeckTextEditor::default(),
+            deck_picker: DeckPicker::default(),
+            settings_layers,
         };
 
         Self {
             render_data: None,
             game: None,
-            player_input: playground_module::PlayerInput {
-                forward: 0.0,
-                backward: 0.0,
-                left: 0.0,
-                right: 0.0,
-                jump: 0.0,
-                crouch: 0.0,
-                interact: 0.0,
-                mouseSensitivity: settings.movement_controls.sensitivity,
-            },
+            local_player: None,
+            input_map: InputMap::new(&settings, &gui_state.render_deck),
+            gamepad,
             #[cfg(target_arch = "wasm32")]
             state_receiver: None,
             compilation,
             surface_format: wgpu::TextureFormat::Rgba8Unorm,
             settings,
             gui_state,
+            startup_preset,
+        }
+    }
+
+    /// Acts on actions that started or stopped outside of the game, like opening the menu
+    fn handle_actions(&mut self, changes: Vec<(Action, bool)>) {
+        for (action, is_active) in changes {
+            match (action, is_active) {
+                (Action::Fullscreen, false) => {
+                    if let Some(render_data) = self.render_data.as_ref() {
+                        toggle_fullscreen(&render_data.window);
+                    }
+                }
+                (Action::Menu, false) => {
+                    if self.gui_state.menu_stack.len() > 0
+                        && !self
+                            .gui_state
+                            .menu_stack
+                            .last()
+                            .is_some_and(|gui| *gui == GuiElement::MainMenu)
+                    {
+                        let exited_ui = self.gui_state.menu_stack.pop().unwrap();
+                        match exited_ui {
+                            GuiElement::CardEditor => {
+                                self.gui_state.render_deck_idx = 0;
+                                self.gui_state.render_deck = self.gui_state.gui_deck.clone();
+                                save_deck_and_dock(&mut self.gui_state, &self.settings);
+                            }
+                            _ => (),
+                        }
+                    } else {
+                        self.gui_state.menu_stack.push(GuiElement::EscMenu);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn poll_gamepad(&mut self) {
+        let Some(render_data) = self.render_data.as_ref() else {
+            return;
+        };
+        let ctx = render_data.egui_renderer.context().clone();
+        for event in self.gamepad.poll() {
+            if capture_gamepad_event(&ctx, &event) {
+                continue;
+            }
+            let changes = self.input_map.handle_event(InputEvent::Gamepad(event));
+            self.handle_actions(changes);
         }
     }
 
     fn render_frame(&mut self) {
+        self.input_map
+            .sync_bindings(&self.settings, &self.gui_state.render_deck);
+        self.poll_gamepad();
         let Some(render_data) = self.render_data.as_mut() else {
             return;
         };
+        let delta_time = render_data.egui_renderer.context().input(|i| i.stable_dt);
+        self.input_map.tick(delta_time);
 
         let surface_texture = render_data
             .surface
@@ -201,13 +320,34 @@ impl App {
             .create_command_encoder(&Default::default());
 
         if let Some(game) = self.game.as_mut() {
-            self.player_input.mouseSensitivity = self.settings.movement_controls.sensitivity;
-            playground_module::set_player_input(game, self.player_input);
+            let deck_generation = self.gui_state.deck_autosave.get_deck_generation();
+            let local_player = self.local_player.get_or_insert_with(|| {
+                LocalPlayer::new(&self.gui_state.render_deck, deck_generation)
+            });
+            if local_player.get_deck_generation() != deck_generation {
+                local_player.set_deck(&self.gui_state.render_deck, deck_generation);
+            }
+            // Clicks in menus shouldn't cast abilities
+            local_player.update(
+                &self.input_map,
+                delta_time,
+                self.gui_state.menu_stack.is_empty(),
+            );
+            playground_module::set_player_input(
+                game,
+                self.input_map
+                    .get_player_input(&self.settings.movement_controls),
+            );
+            playground_module::set_player_effects(game, local_player.get_player_effects());
+            playground_module::set_player_health(game, local_player.health.to_gpu());
             playground_module::set_graphics_settings(game, self.settings.graphics_settings);
             game.begin_frame();
             game.run_compute_passes(&mut encoder);
             game.run_draw_passes(&mut encoder, &texture_view);
+        } else {
+            self.local_player = None;
         }
+        self.input_map.end_frame();
 
         {
             use egui::*;
@@ -256,6 +396,7 @@ impl App {
                                                 .push(GuiElement::MultiplayerMenu);
                                         }
                                         if ui.button("Deck Picker").clicked() {
+                                            self.gui_state.deck_picker.refresh();
                                             self.gui_state.menu_stack.push(GuiElement::DeckPicker);
                                         }
                                         // if ui.button("Play Replay").clicked() {
@@ -310,7 +451,10 @@ impl App {
                                 vertical_centerer(ui, |ui| {
                                     ui.vertical_centered(|ui| {
                                         for preset in self.settings.preset_settings.iter() {
-                                            if ui.button(&preset.name).clicked() {
+                                            let is_startup_preset =
+                                                self.startup_preset.as_ref() == Some(&preset.name);
+                                            if ui.button(&preset.name).clicked() || is_startup_preset {
+                                                self.startup_preset = None;
                                                 self.gui_state.menu_stack.clear();
                                                 self.game =
                                                     Some(pollster::block_on(Renderer::new(
@@ -386,9 +530,13 @@ impl App {
                     card_editor(
                         &ctx,
                         &mut self.gui_state,
+                        &self.settings,
                         // game,
                     );
                 }
+                Some(&GuiElement::DeckCompare) => {
+                    deck_compare(&ctx, &mut self.gui_state, &self.settings);
+                }
 
                 Some(&GuiElement::MultiplayerMenu) => {
                     egui::Area::new("multiplayer menu".into())
@@ -470,17 +618,26 @@ impl App {
                 Some(&GuiElement::Settings) => {
                     egui::TopBottomPanel::top("top_panel").show(&ctx, |ui| {
                         ui.heading("Settings");
+                        let pending_restart =
+                            self.gui_state.settings_layers.get_pending_restart();
+                        if !pending_restart.is_empty() {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                format!("Restart to apply: {}", pending_restart.join(", ")),
+                            );
+                        }
                     });
                     egui::TopBottomPanel::bottom("bottom_panel").show(&ctx, |ui| {
                         ui.horizontal(|ui| {
                             if ui.button("Save").clicked() {
-                                if let Err(e) = fs::write(SETTINGS_FILE, self.settings.to_string())
+                                if let Err(e) =
+                                    self.gui_state.settings_layers.save(&self.settings)
                                 {
                                     self.gui_state
                                         .errors
                                         .push(format!("Failed to save settings: {}", e));
                                 } else {
-                                    self.gui_state.errors.push("Settings saved".to_string());
+                                    self.gui_state.push_message("Settings saved".to_string());
                                 }
                             }
                             if ui.button("Back").clicked() {
@@ -492,7 +649,24 @@ impl App {
                         .frame(Frame::new().inner_margin(PADDING))
                         .show(&ctx, |ui| {
                             egui::ScrollArea::vertical().show(ui, |ui| {
+                                egui::CollapsingHeader::new("Controls")
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        controls_editor(
+                                            ui,
+                                            &mut self.settings,
+                                            &self.gui_state.render_deck,
+                                        );
+                                    });
                                 Probe::new(&mut self.settings).show(ui);
+                                egui::CollapsingHeader::new("Where values come from").show(
+                                    ui,
+                                    |ui| {
+                                        self.gui_state
+                                            .settings_layers
+                                            .draw_sources(ui, &self.settings);
+                                    },
+                                );
                             });
                         });
                 }
@@ -703,16 +877,7 @@ impl App {
                                 );
                                 vertical_centerer(ui, |ui| {
                                     ui.vertical_centered(|ui| {
-                                        // let Ok(decks) = recurse_files(self.settings.card_dir.clone()) else {
-                                        //     panic!("Cannot read directory {}", self.settings.card_dir);
-                                        // };
-                                        // for deck in decks {
-                                        //     if ui.button(deck.file_name().unwrap().to_str().unwrap()).clicked() {
-                                        //         self.gui_state.gui_deck = ron::from_str(fs::read_to_string(deck.as_path()).unwrap().as_str()).unwrap();
-                                        //         self.gui_state.menu_stack.pop();
-                                        //         self.gui_state.menu_stack.push(GuiElement::CardEditor);
-                                        //     }
-                                        // }
+                                        deck_picker(ui, &mut self.gui_state, &mut self.settings);
                                         if ui.button("Back").clicked() {
                                             self.gui_state.menu_stack.pop();
                                         }
@@ -722,6 +887,9 @@ impl App {
                         });
                 }
                 None => {
+                    if let Some(local_player) = self.local_player.as_ref() {
+                        healthbar(10.0, &ctx, local_player);
+                    }
                     egui::Area::new("crosshair".into())
                         .anchor(Align2::CENTER_CENTER, (0.0, 0.0))
                         .show(&ctx, |ui| {
@@ -811,7 +979,20 @@ impl App {
                 }
             }
 
-            
+            self.gui_state.deck_file_watcher.poll(
+                Path::new(&self.settings.card_file),
+                ctx.input(|i| i.time),
+            );
+            external_change_prompt(&ctx, &mut self.gui_state, &self.settings);
+            if self.gui_state.settings_layers.poll(ctx.input(|i| i.time)) {
+                let (warnings, messages) =
+                    self.gui_state.settings_layers.reload(&mut self.settings);
+                self.gui_state.errors.extend(warnings);
+                for message in messages {
+                    self.gui_state.push_message(message);
+                }
+            }
+
             let corner_offset = 10.0;
             egui::Area::new("errors".into())
                 .order(Order::Foreground)
@@ -820,6 +1001,34 @@ impl App {
                     Vec2::new(-corner_offset, -corner_offset),
                 )
                 .show(&ctx, |ui| {
+                    let delta_time = ui.input(|i| i.stable_dt);
+                    for (_, time_left) in self.gui_state.messages.iter_mut() {
+                        *time_left -= delta_time;
+                    }
+                    self.gui_state
+                        .messages
+                        .retain(|(_, time_left)| *time_left > 0.0);
+                    for (message, _) in self.gui_state.messages.iter() {
+                        egui::Frame {
+                            inner_margin: Margin {
+                                left: 4,
+                                right: 4,
+                                top: 4,
+                                bottom: 4,
+                            },
+                            ..Default::default()
+                        }
+                        .stroke(ui.visuals().widgets.noninteractive.bg_stroke)
+                        .fill(Color32::from_gray(40))
+                        .corner_radius(ui.visuals().widgets.noninteractive.corner_radius)
+                        .show(ui, |ui| {
+                            ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+                            ui.label(egui::RichText::new(message).color(egui::Color32::WHITE));
+                        });
+                    }
+                    if !self.gui_state.messages.is_empty() {
+                        ctx.request_repaint();
+                    }
                     let mut errors_to_remove = vec![];
                     for (err_idx, error) in self.gui_state.errors.iter().enumerate() {
                         egui::Frame {
@@ -957,6 +1166,9 @@ impl ApplicationHandler for App {
         let Some(render_data) = self.render_data.as_mut() else {
             return;
         };
+        if capture_window_event(render_data.egui_renderer.context(), &event) {
+            return;
+        }
         match event {
             WindowEvent::Resized(size) => {
                 configure_surface(
@@ -966,94 +1178,14 @@ impl ApplicationHandler for App {
                     self.surface_format,
                 );
             }
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        state,
-                        physical_key,
-                        ..
-                    },
-                ..
-            } => {
-                let state_value = if state == ElementState::Pressed {
-                    1.0
-                } else {
-                    0.0
-                };
-                macro_rules! key_match {
-                    ($property:ident) => {
-                        if let Control::Key(key_code) = self.settings.movement_controls.$property
-                            && key_code == physical_key
-                        {
-                            self.player_input.$property = state_value;
-                        }
-                    };
-                }
-                key_match!(jump);
-                key_match!(crouch);
-                key_match!(right);
-                key_match!(left);
-                key_match!(forward);
-                key_match!(backward);
-
-                // Check for fullscreen toggle key binding
-                if state == ElementState::Released {
-                    if let Control::Key(key_code) = self.settings.fullscreen_toggle
-                        && key_code == physical_key
-                    {
-                        toggle_fullscreen(&render_data.window);
-                    }
-                }
-
-                match physical_key {
-                    PhysicalKey::Code(KeyCode::Escape) => {
-                        if state == ElementState::Released {
-                            if self.gui_state.menu_stack.len() > 0
-                                && !self
-                                    .gui_state
-                                    .menu_stack
-                                    .last()
-                                    .is_some_and(|gui| *gui == GuiElement::MainMenu)
-                            {
-                                let exited_ui = self.gui_state.menu_stack.pop().unwrap();
-                                match exited_ui {
-                                    GuiElement::CardEditor => {
-                                        self.gui_state.render_deck_idx = 0;
-                                        self.gui_state.render_deck =
-                                            self.gui_state.gui_deck.clone();
-                                        let config = ron::ser::PrettyConfig::default();
-                                        let export = ron::ser::to_string_pretty(
-                                            &self.gui_state.gui_deck,
-                                            config,
-                                        )
-                                        .unwrap();
-                                        #[cfg(not(target_arch = "wasm32"))]
-                                        fs::write(&self.settings.card_file, export)
-                                            .expect("failed to write card file");
-                                    }
-                                    _ => (),
-                                }
-                            } else {
-                                self.gui_state.menu_stack.push(GuiElement::EscMenu);
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            WindowEvent::MouseInput { state, button, .. } => {
-                // If the fullscreen toggle is bound to a mouse button, toggle on released
-                if state == ElementState::Released {
-                    if let Control::Mouse(bind_button) = self.settings.fullscreen_toggle
-                        && bind_button == button
-                    {
-                        toggle_fullscreen(&render_data.window);
-                    }
-                }
-            }
             _ => (),
         }
+        let changes = self.input_map.handle_window_event(&event);
+        self.handle_actions(changes);
 
+        let Some(render_data) = self.render_data.as_mut() else {
+            return;
+        };
         render_data
             .egui_renderer
             .handle_input(&render_data.window, &event);
@@ -1113,6 +1245,21 @@ fn main() {
     // documentation for more information.
     env_logger::init();
 
+    #[cfg(target_arch = "wasm32")]
+    let command_line = CommandLine::default();
+    #[cfg(not(target_arch = "wasm32"))]
+    let command_line = match CommandLine::parse(std::env::args()) {
+        Ok(command_line) if command_line.show_help => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(command_line) => command_line,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return;
+        }
+    };
+
     let event_loop = EventLoop::new().unwrap();
 
     // When the current loop iteration finishes, immediately begin a new
@@ -1127,7 +1274,7 @@ fn main() {
     // the background.
     // event_loop.set_control_flow(ControlFlow::Wait);
 
-    let mut app = App::new(playground_module::COMPILATION_RESULT.clone());
+    let mut app = App::new(playground_module::COMPILATION_RESULT.clone(), command_line);
     event_loop.run_app(&mut app).unwrap();
 }
 
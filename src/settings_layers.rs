@@ -0,0 +1,885 @@
+use std::{collections::HashMap, fs, path::PathBuf, time::SystemTime};
+
+use egui::Ui;
+use serde_yml::{Mapping, Value};
+
+use crate::settings_manager::{Settings, parse_settings_file};
+
+const APP_DIR_NAME: &str = "slang-voxels";
+const SETTINGS_FILE_NAME: &str = "settings.yaml";
+/// Environment variables starting with this set a setting, `__` separates nested names.
+/// For example `SLANG_VOXELS_MOVEMENT_CONTROLS__YAW_SENSITIVITY=0.02`.
+const ENV_PREFIX: &str = "SLANG_VOXELS_";
+/// Seconds between checks of the settings files for edits made while running
+const WATCH_INTERVAL: f64 = 1.0;
+pub const USAGE: &str = "Usage: slang-voxels [OPTIONS]
+
+Options:
+  --settings <FILE>    Read and save user settings in FILE instead of the user config directory
+  --deck <FILE>        Use the deck in FILE
+  --preset <NAME>      Start the singleplayer preset NAME right away
+  --remote-url <URL>   Connect to the lobby server at URL
+  --help               Print this message
+
+Settings can also be set with SLANG_VOXELS_<NAME> environment variables,
+use __ between nested names, such as SLANG_VOXELS_MOVEMENT_CONTROLS__YAW_SENSITIVITY.";
+
+/// Layers that settings are read from, later layers override earlier ones
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingsSource {
+    Default,
+    System,
+    Local,
+    User,
+    Environment,
+    CommandLine,
+}
+
+impl SettingsSource {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SettingsSource::Default => "Default",
+            SettingsSource::System => "System file",
+            SettingsSource::Local => "Working directory file",
+            SettingsSource::User => "User file",
+            SettingsSource::Environment => "Environment",
+            SettingsSource::CommandLine => "Command line",
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CommandLine {
+    pub settings_file: Option<PathBuf>,
+    pub deck: Option<String>,
+    pub preset: Option<String>,
+    pub remote_url: Option<String>,
+    pub show_help: bool,
+}
+
+impl CommandLine {
+    /// Parses flags given as `--flag value` or `--flag=value`, the first argument is the program name
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command_line = CommandLine::default();
+        let mut args = args.skip(1);
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if flag == "--help" || flag == "-h" {
+                command_line.show_help = true;
+                continue;
+            }
+            let slot = match flag.as_str() {
+                "--settings" => None,
+                "--deck" => Some(&mut command_line.deck),
+                "--preset" => Some(&mut command_line.preset),
+                "--remote-url" => Some(&mut command_line.remote_url),
+                _ => return Err(format!("Unknown argument {}", flag)),
+            };
+            let value = inline_value
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            match slot {
+                Some(slot) => *slot = Some(value),
+                None => command_line.settings_file = Some(PathBuf::from(value)),
+            }
+        }
+        Ok(command_line)
+    }
+}
+
+fn get_system_settings_path() -> Option<PathBuf> {
+    if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(windows) {
+        std::env::var_os("PROGRAMDATA").map(|dir| {
+            PathBuf::from(dir)
+                .join(APP_DIR_NAME)
+                .join(SETTINGS_FILE_NAME)
+        })
+    } else {
+        Some(
+            PathBuf::from("/etc")
+                .join(APP_DIR_NAME)
+                .join(SETTINGS_FILE_NAME),
+        )
+    }
+}
+
+/// `settings.yaml` next to where the game is started, which is where settings used to be read and saved
+fn get_local_settings_path() -> Option<PathBuf> {
+    (!cfg!(target_arch = "wasm32")).then(|| PathBuf::from(SETTINGS_FILE_NAME))
+}
+
+fn get_user_settings_path() -> Option<PathBuf> {
+    let config_dir = if cfg!(target_arch = "wasm32") {
+        None
+    } else if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    config_dir.map(|dir| dir.join(APP_DIR_NAME).join(SETTINGS_FILE_NAME))
+}
+
+/// When a changed setting is used, most are read every frame and apply right away
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingEffect {
+    Live,
+    NextGame,
+    Restart,
+}
+
+impl SettingEffect {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SettingEffect::Live => "Right away",
+            SettingEffect::NextGame => "Next game",
+            SettingEffect::Restart => "After a restart",
+        }
+    }
+}
+
+pub fn get_setting_effect(path: &[String]) -> SettingEffect {
+    match path.first().map(String::as_str) {
+        // The deck and dock are loaded on startup, the deck picker switches decks on its own
+        Some("card_file" | "card_dir") => SettingEffect::Restart,
+        Some("preset_settings" | "create_lobby_settings" | "replay_settings") => {
+            SettingEffect::NextGame
+        }
+        _ => SettingEffect::Live,
+    }
+}
+
+fn get_path<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| match value {
+        Value::Mapping(mapping) => mapping.get(key.as_str()),
+        Value::Sequence(sequence) => key.parse::<usize>().ok().and_then(|idx| sequence.get(idx)),
+        _ => None,
+    })
+}
+
+/// Sets the value at `path`, creating mappings along the way when needed
+fn set_path(value: &mut Value, path: &[String], new_value: Value) {
+    let Some((key, rest)) = path.split_first() else {
+        *value = new_value;
+        return;
+    };
+    if let Value::Sequence(sequence) = value {
+        if let Some(child) = key
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| sequence.get_mut(idx))
+        {
+            set_path(child, rest, new_value);
+        }
+        return;
+    }
+    if !matches!(value, Value::Mapping(_)) {
+        *value = Value::Mapping(Mapping::new());
+    }
+    if let Value::Mapping(mapping) = value {
+        let child = mapping
+            .entry(Value::String(key.clone()))
+            .or_insert(Value::Null);
+        set_path(child, rest, new_value);
+    }
+}
+
+/// Calls `f` with every value that is not a mapping, sequences count as a single value
+fn for_each_leaf(value: &Value, path: &mut Vec<String>, f: &mut impl FnMut(&[String], &Value)) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter() {
+                path.push(
+                    key.as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("{:?}", key)),
+                );
+                for_each_leaf(child, path, f);
+                path.pop();
+            }
+        }
+        _ => f(path, value),
+    }
+}
+
+/// Returns only the parts of `value` that differ from `base`
+fn get_changes(base: &Value, value: &Value) -> Option<Value> {
+    match (base, value) {
+        (Value::Mapping(base), Value::Mapping(mapping)) => {
+            let changes: Mapping = mapping
+                .iter()
+                .filter_map(|(key, child)| {
+                    let change = match base.get(key) {
+                        Some(base_child) => get_changes(base_child, child)?,
+                        None => child.clone(),
+                    };
+                    Some((key.clone(), change))
+                })
+                .collect();
+            (!changes.is_empty()).then_some(Value::Mapping(changes))
+        }
+        _ => (base != value).then(|| value.clone()),
+    }
+}
+
+/// Goes through text so `f32` values compare equal to the same numbers read from a file
+fn to_settings_value(settings: &Settings) -> Result<Value, String> {
+    serde_yml::to_string(settings)
+        .and_then(|text| serde_yml::from_str(&text))
+        .map_err(|e| e.to_string())
+}
+
+/// Settings after loading and validation, in the same form they are saved in
+fn normalize(value: &Value) -> Value {
+    to_settings_value(&Settings::from_value(value.clone()).0).unwrap_or(Value::Null)
+}
+
+/// The setting an environment variable sets, such as `["movement_controls", "yaw_sensitivity"]` for
+/// `SLANG_VOXELS_MOVEMENT_CONTROLS__YAW_SENSITIVITY`
+fn get_env_path(name: &str) -> Option<Vec<String>> {
+    let name = name.strip_prefix(ENV_PREFIX)?;
+    Some(
+        name.to_lowercase()
+            .split("__")
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Parses the value of an environment variable as YAML so numbers and `!Key` controls work,
+/// anything else is taken as a plain string
+fn parse_override(text: &str) -> Value {
+    serde_yml::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Everything settings are read from besides the built-in defaults
+#[derive(Clone, Default)]
+struct SettingsInputs {
+    system_file: Option<PathBuf>,
+    local_file: Option<PathBuf>,
+    user_file: Option<PathBuf>,
+    /// `SLANG_VOXELS_*` environment variables, sorted by name
+    env_vars: Vec<(String, String)>,
+    command_line: CommandLine,
+}
+
+impl SettingsInputs {
+    fn new(command_line: &CommandLine) -> Self {
+        let mut env_vars: Vec<(String, String)> = if cfg!(target_arch = "wasm32") {
+            vec![]
+        } else {
+            std::env::vars()
+                .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+                .collect()
+        };
+        env_vars.sort();
+        SettingsInputs {
+            system_file: get_system_settings_path(),
+            local_file: get_local_settings_path(),
+            user_file: command_line
+                .settings_file
+                .clone()
+                .or_else(get_user_settings_path),
+            env_vars,
+            command_line: command_line.clone(),
+        }
+    }
+}
+
+/// Tracks where each setting was loaded from, and writes only the user's own changes back to the user file
+pub struct SettingsLayers {
+    pub user_file: Option<PathBuf>,
+    loaded_files: Vec<(SettingsSource, PathBuf)>,
+    sources: HashMap<Vec<String>, SettingsSource>,
+    /// Defaults and the system file, the user file only stores values that differ from these
+    base: Value,
+    /// Settings before the environment and command line were applied
+    file_settings: Value,
+    /// Values set by the environment or command line as they were loaded, these are never saved
+    overrides: Vec<(Vec<String>, Value)>,
+    /// Settings as last loaded or saved, used to mark edited values
+    saved: Value,
+    inputs: SettingsInputs,
+    /// Modification times of the settings files, to notice edits made while running
+    file_times: Vec<(PathBuf, Option<SystemTime>)>,
+    last_poll: f64,
+    /// Settings changed on disk that only apply after a restart
+    pending_restart: Vec<String>,
+    /// Warnings from the last load, so a reload only shows the ones that are new
+    warnings: Vec<String>,
+}
+
+impl SettingsLayers {
+    fn set_source(&mut self, path: &[String], source: SettingsSource) {
+        self.sources.retain(|key, _| !key.starts_with(path));
+        self.sources.insert(path.to_vec(), source);
+    }
+
+    fn merge(&mut self, merged: &mut Value, layer: &Value, source: SettingsSource) {
+        let mut path = vec![];
+        for_each_leaf(layer, &mut path, &mut |path, value| {
+            set_path(merged, path, value.clone());
+            self.set_source(path, source);
+        });
+    }
+
+    fn merge_file(
+        &mut self,
+        merged: &mut Value,
+        path: PathBuf,
+        source: SettingsSource,
+        warnings: &mut Vec<String>,
+    ) {
+        let Ok(text) = fs::read_to_string(&path) else {
+            return;
+        };
+        match parse_settings_file(&text) {
+            Ok(mut layer) => {
+                // The local file is often a copy of the defaults, only what it changes should show as coming from it
+                if source == SettingsSource::Local {
+                    layer = get_changes(merged, &layer).unwrap_or(Value::Mapping(Mapping::new()));
+                }
+                self.merge(merged, &layer, source);
+                self.loaded_files.push((source, path));
+            }
+            Err(e) => warnings.push(format!(
+                "Failed to parse {}, skipping it: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    fn merge_override(
+        &mut self,
+        merged: &mut Value,
+        path: Vec<String>,
+        value: Value,
+        source: SettingsSource,
+    ) {
+        set_path(merged, &path, value.clone());
+        self.set_source(&path, source);
+        self.overrides.push((path, value));
+    }
+
+    fn get_file_times(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        self.inputs
+            .system_file
+            .clone()
+            .into_iter()
+            .chain(self.inputs.local_file.clone())
+            .chain(self.user_file.clone())
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    /// Returns true when a settings file was changed by another program since it was last read or saved
+    pub fn poll(&mut self, time: f64) -> bool {
+        if cfg!(target_arch = "wasm32") || time - self.last_poll < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_poll = time;
+        let file_times = self.get_file_times();
+        if file_times == self.file_times {
+            return false;
+        }
+        self.file_times = file_times;
+        true
+    }
+
+    /// Loads the settings files again and applies the values that changed on disk, except the ones that need a restart.
+    /// Other values keep what they are in `settings`, so unsaved edits in the settings screen stay.
+    /// Returns the new warnings and the info messages to show.
+    pub fn reload(&mut self, settings: &mut Settings) -> (Vec<String>, Vec<String>) {
+        // Otherwise a half written file or a typo would reset everything in it to the defaults
+        for (path, _) in self.file_times.iter() {
+            if let Ok(text) = fs::read_to_string(path)
+                && let Err(e) = parse_settings_file(&text)
+            {
+                return (
+                    vec![format!(
+                        "Not reloading settings until {} is fixed: {}",
+                        path.display(),
+                        e
+                    )],
+                    vec![],
+                );
+            }
+        }
+        let (_, mut layers, load_warnings) = load_layers(self.inputs.clone());
+        layers.pending_restart = std::mem::take(&mut self.pending_restart);
+        let mut warnings: Vec<String> = load_warnings
+            .into_iter()
+            .filter(|warning| !self.warnings.contains(warning))
+            .collect();
+        let mut value = match to_settings_value(settings) {
+            Ok(value) => value,
+            Err(e) => {
+                warnings.push(format!("Failed to reload settings: {}", e));
+                return (warnings, vec![]);
+            }
+        };
+        let mut changed = vec![];
+        for_each_leaf(&layers.saved, &mut vec![], &mut |path, leaf| {
+            if get_path(&self.saved, path) != Some(leaf) {
+                if get_setting_effect(path) != SettingEffect::Restart {
+                    set_path(&mut value, path, leaf.clone());
+                }
+                changed.push(path.to_vec());
+            }
+        });
+        *self = layers;
+        // Like an override, saving keeps the file's value while the running one is still in use
+        for path in changed.iter() {
+            if get_setting_effect(path) == SettingEffect::Restart
+                && let Some(running) = get_path(&value, path)
+            {
+                self.overrides.push((path.clone(), running.clone()));
+            }
+        }
+        if changed.is_empty() {
+            return (warnings, vec![]);
+        }
+        let (reloaded, reload_warnings) = Settings::from_value(value);
+        *settings = reloaded;
+        for warning in reload_warnings {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning.clone());
+                warnings.push(warning);
+            }
+        }
+
+        let mut messages = vec![];
+        let get_names = |effect: SettingEffect| -> Vec<String> {
+            changed
+                .iter()
+                .filter(|path| get_setting_effect(path) == effect)
+                .map(|path| path.join("."))
+                .collect()
+        };
+        let live = get_names(SettingEffect::Live);
+        if !live.is_empty() {
+            messages.push(format!("Reloaded settings: {}", live.join(", ")));
+        }
+        let next_game = get_names(SettingEffect::NextGame);
+        if !next_game.is_empty() {
+            messages.push(format!(
+                "Reloaded for the next game: {}",
+                next_game.join(", ")
+            ));
+        }
+        for name in get_names(SettingEffect::Restart) {
+            messages.push(format!("Restart to apply {}", name));
+            if !self.pending_restart.contains(&name) {
+                self.pending_restart.push(name);
+            }
+        }
+        (warnings, messages)
+    }
+
+    /// Settings changed on disk while running that need a restart to take effect
+    pub fn get_pending_restart(&self) -> &[String] {
+        &self.pending_restart
+    }
+
+    pub fn get_source(&self, path: &[String]) -> SettingsSource {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.sources.get(&path[..len]).copied())
+            .unwrap_or(SettingsSource::Default)
+    }
+
+    /// Writes the values that differ from the defaults and system file to the user file.
+    /// Values still matching what the environment or command line set keep their value from the user file.
+    pub fn save(&mut self, settings: &Settings) -> Result<(), String> {
+        let Some(user_file) = self.user_file.clone() else {
+            return Ok(());
+        };
+        let current = to_settings_value(settings)?;
+        let mut value = current.clone();
+        for (path, override_value) in self.overrides.iter() {
+            if get_path(&value, path) == Some(override_value) {
+                if let Some(file_value) = get_path(&self.file_settings, path) {
+                    set_path(&mut value, path, file_value.clone());
+                }
+            }
+        }
+        let changes = get_changes(&self.base, &value).unwrap_or(Value::Mapping(Mapping::new()));
+        let text = serde_yml::to_string(&changes).map_err(|e| e.to_string())?;
+        if let Some(directory) = user_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)
+                .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        }
+        fs::write(&user_file, text)
+            .map_err(|e| format!("Failed to write {}: {}", user_file.display(), e))?;
+
+        let mut edited = vec![];
+        for_each_leaf(&current, &mut vec![], &mut |path, leaf| {
+            if get_path(&self.saved, path) != Some(leaf) {
+                edited.push(path.to_vec());
+            }
+        });
+        for path in edited {
+            self.set_source(&path, SettingsSource::User);
+        }
+        if !self
+            .loaded_files
+            .iter()
+            .any(|(source, _)| *source == SettingsSource::User)
+        {
+            self.loaded_files.push((SettingsSource::User, user_file));
+        }
+        self.file_settings = value;
+        self.saved = current;
+        // Don't reload what was just written
+        self.file_times = self.get_file_times();
+        Ok(())
+    }
+
+    /// Lists the files that were read and the source of every setting
+    pub fn draw_sources(&self, ui: &mut Ui, settings: &Settings) {
+        for (source, path) in self.loaded_files.iter() {
+            ui.label(format!("{}: {}", source.get_name(), path.display()));
+        }
+        match &self.user_file {
+            Some(user_file) => ui.label(format!("Saving to {}", user_file.display())),
+            None => ui.label("Settings can not be saved here"),
+        };
+        let Ok(current) = to_settings_value(settings) else {
+            return;
+        };
+        egui::Grid::new("settings_sources")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for_each_leaf(&current, &mut vec![], &mut |path, value| {
+                    ui.label(path.join("."));
+                    if get_path(&self.saved, path) == Some(value) {
+                        ui.label(self.get_source(path).get_name());
+                    } else {
+                        ui.label("Edited");
+                    }
+                    let effect = get_setting_effect(path);
+                    if effect == SettingEffect::Live {
+                        ui.label("");
+                    } else {
+                        ui.label(format!("Applies: {}", effect.get_name()))
+                            .on_hover_text(
+                                "Changes to this setting don't apply to what is already running",
+                            );
+                    }
+                    ui.end_row();
+                });
+            });
+    }
+}
+
+/// Loads the built-in defaults, then the system file, `./settings.yaml`, the user file, `SLANG_VOXELS_*` environment variables
+/// and finally command line flags, each overriding the values set before it
+pub fn load_settings(command_line: &CommandLine) -> (Settings, SettingsLayers, Vec<String>) {
+    load_layers(SettingsInputs::new(command_line))
+}
+
+fn load_layers(inputs: SettingsInputs) -> (Settings, SettingsLayers, Vec<String>) {
+    let mut warnings = vec![];
+    let user_file = inputs.user_file.clone();
+    let command_line = inputs.command_line.clone();
+    let mut layers = SettingsLayers {
+        user_file: user_file.clone(),
+        loaded_files: vec![],
+        sources: HashMap::new(),
+        base: Value::Null,
+        file_settings: Value::Null,
+        overrides: vec![],
+        saved: Value::Null,
+        inputs: inputs.clone(),
+        file_times: vec![],
+        last_poll: 0.0,
+        pending_restart: vec![],
+        warnings: vec![],
+    };
+    let mut merged = parse_settings_file(include_str!("../settings.yaml"))
+        .unwrap_or(Value::Mapping(Mapping::new()));
+    if let Some(system_file) = inputs.system_file {
+        layers.merge_file(
+            &mut merged,
+            system_file,
+            SettingsSource::System,
+            &mut warnings,
+        );
+    }
+    // Part of the base like the system file, so saving doesn't copy the local file's values into the user file
+    if let Some(local_file) = inputs.local_file {
+        layers.merge_file(
+            &mut merged,
+            local_file,
+            SettingsSource::Local,
+            &mut warnings,
+        );
+    }
+    layers.base = normalize(&merged);
+    if let Some(user_file) = user_file {
+        if command_line.settings_file.is_some() && !user_file.exists() {
+            warnings.push(format!(
+                "{} does not exist yet, it will be created when settings are saved",
+                user_file.display()
+            ));
+        }
+        layers.merge_file(&mut merged, user_file, SettingsSource::User, &mut warnings);
+    }
+    layers.file_settings = normalize(&merged);
+
+    for (name, value) in inputs.env_vars.iter() {
+        if let Some(path) = get_env_path(name) {
+            layers.merge_override(
+                &mut merged,
+                path,
+                parse_override(value),
+                SettingsSource::Environment,
+            );
+        }
+    }
+    let flags = [
+        ("card_file", &command_line.deck),
+        ("remote_url", &command_line.remote_url),
+    ];
+    for (name, value) in flags {
+        if let Some(value) = value {
+            layers.merge_override(
+                &mut merged,
+                vec![name.to_string()],
+                Value::String(value.clone()),
+                SettingsSource::CommandLine,
+            );
+        }
+    }
+
+    let (settings, settings_warnings) = Settings::from_value(merged);
+    warnings.extend(settings_warnings);
+    layers.saved = to_settings_value(&settings).unwrap_or(Value::Null);
+    // Overrides are compared with what they loaded as, which differs when validation replaced them
+    for (path, value) in layers.overrides.iter_mut() {
+        if let Some(loaded) = get_path(&layers.saved, path) {
+            *value = loaded.clone();
+        }
+    }
+    layers.file_times = layers.get_file_times();
+    layers.warnings = warnings.clone();
+    (settings, layers, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::get_test_dir;
+    use std::path::Path;
+
+    fn parse_args(args: &[&str]) -> Result<CommandLine, String> {
+        CommandLine::parse(
+            std::iter::once("slang-voxels")
+                .chain(args.iter().copied())
+                .map(str::to_string),
+        )
+    }
+
+    fn write_file(dir: &Path, name: &str, text: &str) -> Option<PathBuf> {
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        Some(path)
+    }
+
+    fn path(path: &str) -> Vec<String> {
+        path.split('.').map(str::to_string).collect()
+    }
+
+    /// Every layer sets some values, each one overriding a value of the layers before it
+    fn layered_inputs(dir: &Path) -> SettingsInputs {
+        SettingsInputs {
+            system_file: write_file(
+                dir,
+                "system.yaml",
+                "remote_url: system\ngamepad_settings:\n  deadzone: 0.1\n  look_speed: 100.0\n  look_curve: 1.5\n",
+            ),
+            local_file: write_file(
+                dir,
+                "local.yaml",
+                "remote_url: local\ngamepad_settings:\n  deadzone: 0.1\n  look_speed: 200.0\n  look_curve: 1.6\n",
+            ),
+            user_file: write_file(
+                dir,
+                "user.yaml",
+                "remote_url: user\ngamepad_settings:\n  look_curve: 1.7\nmovement_controls:\n  max_acceleration: 5.0\n",
+            ),
+            env_vars: vec![
+                (
+                    "SLANG_VOXELS_MOVEMENT_CONTROLS__MAX_ACCELERATION".to_string(),
+                    "6".to_string(),
+                ),
+                ("SLANG_VOXELS_REMOTE_URL".to_string(), "env".to_string()),
+            ],
+            command_line: parse_args(&["--remote-url", "cli"]).unwrap(),
+        }
+    }
+
+    #[test]
+    fn command_line_flags_take_inline_or_next_values() {
+        let command_line =
+            parse_args(&["--deck=decks/a.ron", "--settings", "my settings.yaml", "-h"]).unwrap();
+        assert_eq!(command_line.deck.as_deref(), Some("decks/a.ron"));
+        assert_eq!(
+            command_line.settings_file,
+            Some(PathBuf::from("my settings.yaml"))
+        );
+        assert!(command_line.show_help);
+        assert_eq!(command_line.preset, None);
+    }
+
+    #[test]
+    fn command_line_errors_name_the_flag() {
+        assert_eq!(
+            parse_args(&["--preset"]).err(),
+            Some("--preset needs a value".to_string())
+        );
+        assert_eq!(
+            parse_args(&["--bogus=1"]).err(),
+            Some("Unknown argument --bogus".to_string())
+        );
+    }
+
+    #[test]
+    fn env_names_split_on_double_underscores() {
+        assert_eq!(
+            get_env_path("SLANG_VOXELS_MOVEMENT_CONTROLS__YAW_SENSITIVITY"),
+            Some(path("movement_controls.yaw_sensitivity"))
+        );
+        assert_eq!(
+            get_env_path("SLANG_VOXELS_CARD_DIR"),
+            Some(path("card_dir"))
+        );
+        assert_eq!(get_env_path("HOME"), None);
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let dir = get_test_dir("settings-precedence");
+        let (settings, layers, warnings) = load_layers(layered_inputs(&dir));
+        assert_eq!(warnings, Vec::<String>::new());
+
+        let expected = [
+            ("movement_controls.yaw_sensitivity", SettingsSource::Default),
+            ("gamepad_settings.deadzone", SettingsSource::System),
+            ("gamepad_settings.look_speed", SettingsSource::Local),
+            ("gamepad_settings.look_curve", SettingsSource::User),
+            (
+                "movement_controls.max_acceleration",
+                SettingsSource::Environment,
+            ),
+            ("remote_url", SettingsSource::CommandLine),
+        ];
+        for (name, source) in expected {
+            assert_eq!(layers.get_source(&path(name)), source, "{}", name);
+        }
+        assert_eq!(settings.gamepad_settings.deadzone, 0.1);
+        assert_eq!(settings.gamepad_settings.look_speed, 200.0);
+        assert_eq!(settings.gamepad_settings.look_curve, 1.7);
+        assert_eq!(settings.movement_controls.max_acceleration, 6.0);
+        assert_eq!(settings.remote_url, "cli");
+    }
+
+    #[test]
+    fn saving_writes_only_the_changes_from_lower_layers() {
+        let dir = get_test_dir("settings-save");
+        let (mut settings, mut layers, _) = load_layers(layered_inputs(&dir));
+        settings.gamepad_settings.look_curve = 1.8;
+        settings.movement_controls.yaw_sensitivity = 0.03;
+        layers.save(&settings).unwrap();
+
+        // The environment and command line values aren't saved, the user file keeps its own
+        let saved = parse_settings_file(&fs::read_to_string(dir.join("user.yaml")).unwrap());
+        let expected = parse_settings_file(
+            "remote_url: user\ngamepad_settings:\n  look_curve: 1.8\nmovement_controls:\n  max_acceleration: 5.0\n  yaw_sensitivity: 0.03\n",
+        );
+        assert_eq!(saved, expected);
+        assert_eq!(
+            layers.get_source(&path("movement_controls.yaw_sensitivity")),
+            SettingsSource::User
+        );
+    }
+
+    fn user_only_inputs(dir: &Path, text: &str) -> SettingsInputs {
+        SettingsInputs {
+            user_file: write_file(dir, "user.yaml", text),
+            ..SettingsInputs::default()
+        }
+    }
+
+    /// Writes the user file as another program would, with a newer modification time
+    fn edit_user_file(dir: &Path, text: &str) {
+        let path = dir.join("user.yaml");
+        fs::write(&path, text).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn reload_applies_live_changes_and_keeps_unsaved_edits() {
+        let dir = get_test_dir("settings-reload-live");
+        let (mut settings, mut layers, _) = load_layers(user_only_inputs(
+            &dir,
+            "gamepad_settings:\n  look_curve: 1.5\n",
+        ));
+        settings.gamepad_settings.look_speed = 321.0;
+
+        edit_user_file(&dir, "gamepad_settings:\n  look_curve: 2.5\n");
+        assert!(layers.poll(WATCH_INTERVAL));
+        let (warnings, messages) = layers.reload(&mut settings);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(
+            messages,
+            vec!["Reloaded settings: gamepad_settings.look_curve".to_string()]
+        );
+        assert_eq!(settings.gamepad_settings.look_curve, 2.5);
+        assert_eq!(settings.gamepad_settings.look_speed, 321.0);
+        assert!(layers.get_pending_restart().is_empty());
+        // Nothing changed since the last check
+        assert!(!layers.poll(2.0 * WATCH_INTERVAL));
+    }
+
+    #[test]
+    fn reload_leaves_restart_changes_pending() {
+        let dir = get_test_dir("settings-reload-restart");
+        let (mut settings, mut layers, _) =
+            load_layers(user_only_inputs(&dir, "card_file: decks/a.ron\n"));
+
+        edit_user_file(&dir, "card_file: decks/b.ron\n");
+        let (_, messages) = layers.reload(&mut settings);
+        assert_eq!(messages, vec!["Restart to apply card_file".to_string()]);
+        assert_eq!(settings.card_file, "decks/a.ron");
+        assert_eq!(layers.get_pending_restart(), ["card_file".to_string()]);
+
+        // Saving other edits doesn't undo the change on disk
+        settings.gamepad_settings.look_curve = 3.0;
+        layers.save(&settings).unwrap();
+        let saved = fs::read_to_string(dir.join("user.yaml")).unwrap();
+        assert!(saved.contains("decks/b.ron"), "{}", saved);
+    }
+}
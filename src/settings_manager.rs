@@ -1,21 +1,33 @@
 use std::fmt::Display;
 
-use egui::{self, ComboBox};
 use egui_probe::EguiProbe;
 use serde::{Deserialize, Serialize};
+use serde_yml::Value;
 use winit::event::MouseButton;
 use winit::keyboard::KeyCode;
 
-use crate::{playground_module, shared::GameSettings};
+use crate::{
+    gamepad::{AxisDirection, GamepadAxis, GamepadButton},
+    input_map::{Action, Binding},
+    playground_module,
+    shared::GameSettings,
+    utils::translate_key_code_to_egui,
+};
 
 #[derive(Debug, Deserialize, Serialize, Clone, EguiProbe)]
+#[serde(default)]
 pub struct Settings {
     pub local_url: String,
     pub remote_url: String,
     pub card_file: String,
     pub card_dir: String,
+    /// Edited in the controls section of the settings screen, which checks for conflicts
+    #[egui_probe(skip)]
     pub fullscreen_toggle: Control,
+    #[egui_probe(skip)]
     pub movement_controls: ControlSettings,
+    #[egui_probe(skip)]
+    pub gamepad_settings: GamepadSettings,
     pub graphics_settings: playground_module::GraphicsSettings,
     pub replay_settings: ReplaySettings,
     pub do_profiling: bool,
@@ -24,35 +36,356 @@ pub struct Settings {
     pub create_lobby_settings: GameSettings,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 pub enum Control {
     Key(KeyCode),
     Mouse(MouseButton),
+    GamepadButton(GamepadButton),
+    /// Held while the axis is pushed past the deadzone in the direction
+    GamepadAxis(GamepadAxis, AxisDirection),
 }
 
 impl Display for Control {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Control::Key(key) => write!(f, "{:?}", key),
+            Control::Key(key) => match translate_key_code_to_egui(*key) {
+                Some(key) => write!(f, "{}", key.symbol_or_name()),
+                None => write!(f, "{:?}", key),
+            },
             Control::Mouse(MouseButton::Left) => write!(f, "↖"),
             Control::Mouse(MouseButton::Middle) => write!(f, "⬆"),
             Control::Mouse(MouseButton::Right) => write!(f, "↗"),
             Control::Mouse(button) => write!(f, "{:?}", button),
+            Control::GamepadButton(button) => write!(f, "Pad {:?}", button),
+            Control::GamepadAxis(axis, direction) => {
+                write!(f, "Pad {:?}{}", axis, direction.get_symbol())
+            }
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            local_url: "127.0.0.1:3536/".to_string(),
+            remote_url: "primd.net:3536/".to_string(),
+            card_file: "decks/movement.ron".to_string(),
+            card_dir: "decks".to_string(),
+            fullscreen_toggle: Control::Key(KeyCode::F11),
+            movement_controls: ControlSettings::default(),
+            gamepad_settings: GamepadSettings::default(),
+            graphics_settings: playground_module::GraphicsSettings {
+                primary_ray_dist: 150,
+                shadow_ray_dist: 20,
+                reflection_ray_dist: 15,
+                ao_ray_dist: 15,
+            },
+            replay_settings: ReplaySettings::default(),
+            do_profiling: false,
+            crash_log: "crash_log.txt".to_string(),
+            preset_settings: vec![],
+            create_lobby_settings: GameSettings {
+                name: "Standard Open Multiplayer".to_string(),
+                is_remote: true,
+                player_count: 2,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Location of a value inside the settings file, used to point warnings at the right line
+#[derive(Clone)]
+enum SettingsKey {
+    Field(Value),
+    Index(usize),
+}
+
+fn get_setting_name(path: &[SettingsKey]) -> String {
+    let mut name = String::new();
+    for key in path {
+        match key {
+            SettingsKey::Field(Value::String(field)) => {
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(field);
+            }
+            SettingsKey::Field(field) => name.push_str(&format!("[{:?}]", field)),
+            SettingsKey::Index(idx) => name.push_str(&format!("[{}]", idx)),
         }
     }
+    name
+}
+
+/// Tries to load settings where `value` is the only thing set at `path`, everything else is left at its default
+fn check_setting(path: &[SettingsKey], value: &Value) -> Result<(), String> {
+    let mut root = value.clone();
+    for key in path.iter().rev() {
+        root = match key {
+            SettingsKey::Field(field) => {
+                let mut mapping = serde_yml::Mapping::new();
+                mapping.insert(field.clone(), root);
+                Value::Mapping(mapping)
+            }
+            SettingsKey::Index(_) => Value::Sequence(vec![root]),
+        };
+    }
+    serde_yml::from_value::<Settings>(root)
+        .map(|_| ())
+        .map_err(|e| {
+            // Unknown key names list every key code there is
+            let message = e.to_string();
+            match message.split_once(", expected one of") {
+                Some((message, _)) => message.to_string(),
+                None => message,
+            }
+        })
+}
+
+/// Removes the values that fail to load, each one is checked on its own so a single typo only resets that value.
+/// Returns false if `value` itself should be removed.
+fn remove_invalid(
+    path: &mut Vec<SettingsKey>,
+    value: &mut Value,
+    warnings: &mut Vec<String>,
+) -> bool {
+    if check_setting(path, value).is_ok() {
+        return true;
+    }
+    match value {
+        Value::Mapping(mapping) => mapping.retain(|field, child| {
+            path.push(SettingsKey::Field(field.clone()));
+            let keep = remove_invalid(path, child, warnings);
+            path.pop();
+            keep
+        }),
+        Value::Sequence(sequence) => {
+            let mut idx = 0;
+            sequence.retain_mut(|child| {
+                path.push(SettingsKey::Index(idx));
+                idx += 1;
+                let keep = remove_invalid(path, child, warnings);
+                path.pop();
+                keep
+            });
+        }
+        _ => {}
+    }
+    match check_setting(path, value) {
+        Ok(()) => true,
+        Err(e) => {
+            warnings.push(format!(
+                "Invalid setting {}, using the default: {}",
+                get_setting_name(path),
+                e
+            ));
+            false
+        }
+    }
+}
+
+/// Warns about keys in the file that do not exist in the loaded settings, which are usually typos
+fn find_unknown_keys(
+    path: &mut Vec<SettingsKey>,
+    file: &Value,
+    loaded: &Value,
+    warnings: &mut Vec<String>,
+) {
+    match (file, loaded) {
+        (Value::Mapping(file), Value::Mapping(loaded)) => {
+            for (field, file_child) in file.iter() {
+                path.push(SettingsKey::Field(field.clone()));
+                match loaded.get(field) {
+                    Some(loaded_child) => {
+                        find_unknown_keys(path, file_child, loaded_child, warnings)
+                    }
+                    None => warnings.push(format!("Unknown setting {}", get_setting_name(path))),
+                }
+                path.pop();
+            }
+        }
+        (Value::Sequence(file), Value::Sequence(loaded)) => {
+            for (idx, (file_child, loaded_child)) in file.iter().zip(loaded.iter()).enumerate() {
+                path.push(SettingsKey::Index(idx));
+                find_unknown_keys(path, file_child, loaded_child, warnings);
+                path.pop();
+            }
+        }
+        (Value::Tagged(file), Value::Tagged(loaded)) if file.tag == loaded.tag => {
+            find_unknown_keys(path, &file.value, &loaded.value, warnings)
+        }
+        _ => {}
+    }
+}
+
+fn validate_game_settings(
+    name: &str,
+    game_settings: &mut GameSettings,
+    warnings: &mut Vec<String>,
+) {
+    let defaults = GameSettings::default();
+    if !(game_settings.delta_time > 0.0) {
+        warnings.push(format!(
+            "{}.delta_time must be greater than 0, using {}",
+            name, defaults.delta_time
+        ));
+        game_settings.delta_time = defaults.delta_time;
+    }
+    if game_settings.player_count == 0 {
+        warnings.push(format!(
+            "{}.player_count must be at least 1, using {}",
+            name, defaults.player_count
+        ));
+        game_settings.player_count = defaults.player_count;
+    }
+    let render_size = game_settings.render_size;
+    if render_size.x == 0 || render_size.y == 0 || render_size.z == 0 {
+        warnings.push(format!(
+            "{}.render_size must not be 0 in any direction, using {:?}",
+            name, defaults.render_size
+        ));
+        game_settings.render_size = defaults.render_size;
+    }
+}
+
+/// Game settings keys that older settings files have but nothing reads anymore.
+/// `fixed_center` was never read by this version, so it is dropped quietly instead of warned about as unknown.
+const LEGACY_GAME_SETTINGS: &[&str] = &["fixed_center"];
+
+fn remove_legacy_settings(file: &mut Value) {
+    let Value::Mapping(mapping) = file else {
+        return;
+    };
+    let remove_keys = |game_settings: &mut Value| {
+        if let Value::Mapping(game_settings) = game_settings {
+            for key in LEGACY_GAME_SETTINGS {
+                game_settings.remove(*key);
+            }
+        }
+    };
+    if let Some(Value::Sequence(presets)) = mapping.get_mut("preset_settings") {
+        presets.iter_mut().for_each(remove_keys);
+    }
+    if let Some(create_lobby_settings) = mapping.get_mut("create_lobby_settings") {
+        remove_keys(create_lobby_settings);
+    }
+}
+
+/// Older settings files have a single `sensitivity` for both axes, it becomes the yaw and pitch sensitivity
+fn migrate_sensitivity(file: &mut Value) {
+    let Some(Value::Mapping(controls)) = file.get_mut("movement_controls") else {
+        return;
+    };
+    if let Some(sensitivity) = controls.remove("sensitivity") {
+        controls.insert("yaw_sensitivity".into(), sensitivity.clone());
+        controls.insert("pitch_sensitivity".into(), sensitivity);
+    }
+}
+
+/// Parses a settings file into its raw values, an empty file has no values set
+pub fn parse_settings_file(yaml_string: &str) -> Result<Value, String> {
+    match serde_yml::from_str(yaml_string) {
+        Ok(Value::Null) => Ok(Value::Mapping(serde_yml::Mapping::new())),
+        Ok(file @ Value::Mapping(_)) => Ok(file),
+        Ok(_) => Err("Settings should map setting names to values".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 impl Settings {
-    pub fn from_string(yaml_string: &str) -> Self {
-        serde_yml::from_str(yaml_string).unwrap()
+    /// Loads settings without ever failing. Missing values use their defaults, and anything that
+    /// is unknown, fails to parse or is out of range is listed in the returned warnings.
+    pub fn from_value(mut file: Value) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        remove_legacy_settings(&mut file);
+        migrate_sensitivity(&mut file);
+        remove_invalid(&mut vec![], &mut file, &mut warnings);
+        let mut settings = match serde_yml::from_value::<Settings>(file.clone()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warnings.push(format!(
+                    "Failed to load settings, using the defaults: {}",
+                    e
+                ));
+                return (Settings::default(), warnings);
+            }
+        };
+        if let Ok(loaded) = serde_yml::to_value(&settings) {
+            find_unknown_keys(&mut vec![], &file, &loaded, &mut warnings);
+        }
+        settings.validate(&mut warnings);
+        (settings, warnings)
     }
 
-    pub fn to_string(&self) -> String {
-        serde_yml::to_string(self).unwrap()
+    /// Resets values that are out of range to their defaults
+    fn validate(&mut self, warnings: &mut Vec<String>) {
+        let defaults = Settings::default();
+        let yaw_sensitivity = self.movement_controls.yaw_sensitivity;
+        if !(yaw_sensitivity > 0.0 && yaw_sensitivity.is_finite()) {
+            warnings.push(format!(
+                "movement_controls.yaw_sensitivity must be greater than 0, using {}",
+                defaults.movement_controls.yaw_sensitivity
+            ));
+            self.movement_controls.yaw_sensitivity = defaults.movement_controls.yaw_sensitivity;
+        }
+        let pitch_sensitivity = self.movement_controls.pitch_sensitivity;
+        if !(pitch_sensitivity > 0.0 && pitch_sensitivity.is_finite()) {
+            warnings.push(format!(
+                "movement_controls.pitch_sensitivity must be greater than 0, using {}",
+                defaults.movement_controls.pitch_sensitivity
+            ));
+            self.movement_controls.pitch_sensitivity = defaults.movement_controls.pitch_sensitivity;
+        }
+        let acceleration = self.movement_controls.acceleration;
+        if !(acceleration >= 0.0 && acceleration.is_finite()) {
+            warnings.push(format!(
+                "movement_controls.acceleration must not be negative, using {}",
+                defaults.movement_controls.acceleration
+            ));
+            self.movement_controls.acceleration = defaults.movement_controls.acceleration;
+        }
+        if !(self.movement_controls.max_acceleration >= 1.0) {
+            warnings.push(format!(
+                "movement_controls.max_acceleration must be at least 1, using {}",
+                defaults.movement_controls.max_acceleration
+            ));
+            self.movement_controls.max_acceleration = defaults.movement_controls.max_acceleration;
+        }
+        let deadzone = self.gamepad_settings.deadzone;
+        if !(0.0..1.0).contains(&deadzone) {
+            warnings.push(format!(
+                "gamepad_settings.deadzone must be at least 0 and below 1, using {}",
+                defaults.gamepad_settings.deadzone
+            ));
+            self.gamepad_settings.deadzone = defaults.gamepad_settings.deadzone;
+        }
+        let look_curve = self.gamepad_settings.look_curve;
+        if !(look_curve > 0.0 && look_curve.is_finite()) {
+            warnings.push(format!(
+                "gamepad_settings.look_curve must be greater than 0, using {}",
+                defaults.gamepad_settings.look_curve
+            ));
+            self.gamepad_settings.look_curve = defaults.gamepad_settings.look_curve;
+        }
+        for (preset_idx, preset) in self.preset_settings.iter_mut().enumerate() {
+            validate_game_settings(
+                &format!("preset_settings[{}]", preset_idx),
+                preset,
+                warnings,
+            );
+        }
+        validate_game_settings(
+            "create_lobby_settings",
+            &mut self.create_lobby_settings,
+            warnings,
+        );
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, EguiProbe)]
+/// Edited in the controls editor rather than through the settings probe
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct ControlSettings {
     pub forward: Control,
     pub backward: Control,
@@ -60,89 +393,258 @@ pub struct ControlSettings {
     pub right: Control,
     pub jump: Control,
     pub crouch: Control,
-    pub sensitivity: f32,
+    pub interact: Control,
+    /// Radians turned left and right per pixel of mouse movement
+    pub yaw_sensitivity: f32,
+    /// Radians turned up and down per pixel of mouse movement
+    pub pitch_sensitivity: f32,
+    pub invert_y: bool,
+    /// Extra sensitivity per 1000 pixels per second of mouse speed, 0 turns acceleration off
+    pub acceleration: f32,
+    /// Largest multiple of the sensitivity acceleration can reach
+    pub max_acceleration: f32,
+    /// Further bindings on top of the controls above, such as a second key for an action or keys to look around
+    pub extra_bindings: Vec<Binding>,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        ControlSettings {
+            forward: Control::Key(KeyCode::KeyW),
+            backward: Control::Key(KeyCode::KeyS),
+            left: Control::Key(KeyCode::KeyA),
+            right: Control::Key(KeyCode::KeyD),
+            jump: Control::Key(KeyCode::Space),
+            crouch: Control::Key(KeyCode::ControlLeft),
+            interact: Control::Key(KeyCode::KeyE),
+            yaw_sensitivity: 0.01,
+            pitch_sensitivity: 0.01,
+            invert_y: false,
+            acceleration: 0.0,
+            max_acceleration: 4.0,
+            extra_bindings: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GamepadSettings {
+    /// How far a stick has to be pushed before it counts, from 0 to 1
+    pub deadzone: f32,
+    /// How fast the look stick turns when pushed all the way, in pixels of mouse movement per second
+    pub look_speed: f32,
+    /// Power applied to how far the look stick is pushed, above 1 gives finer aim near the center
+    pub look_curve: f32,
+    pub bindings: Vec<Binding>,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        let stick = |action, axis, direction, scale| Binding {
+            scale,
+            ..Binding::new(action, Control::GamepadAxis(axis, direction))
+        };
+        let button = |action, button| Binding::new(action, Control::GamepadButton(button));
+        GamepadSettings {
+            deadzone: 0.15,
+            look_speed: 600.0,
+            look_curve: 2.0,
+            bindings: vec![
+                stick(
+                    Action::Forward,
+                    GamepadAxis::LeftStickY,
+                    AxisDirection::Positive,
+                    1.0,
+                ),
+                stick(
+                    Action::Backward,
+                    GamepadAxis::LeftStickY,
+                    AxisDirection::Negative,
+                    1.0,
+                ),
+                stick(
+                    Action::Left,
+                    GamepadAxis::LeftStickX,
+                    AxisDirection::Negative,
+                    1.0,
+                ),
+                stick(
+                    Action::Right,
+                    GamepadAxis::LeftStickX,
+                    AxisDirection::Positive,
+                    1.0,
+                ),
+                stick(
+                    Action::LookX,
+                    GamepadAxis::RightStickX,
+                    AxisDirection::Positive,
+                    1.0,
+                ),
+                stick(
+                    Action::LookX,
+                    GamepadAxis::RightStickX,
+                    AxisDirection::Negative,
+                    -1.0,
+                ),
+                // Mouse movement is down positive while stick movement is up positive
+                stick(
+                    Action::LookY,
+                    GamepadAxis::RightStickY,
+                    AxisDirection::Positive,
+                    -1.0,
+                ),
+                stick(
+                    Action::LookY,
+                    GamepadAxis::RightStickY,
+                    AxisDirection::Negative,
+                    1.0,
+                ),
+                button(Action::Jump, GamepadButton::South),
+                button(Action::Crouch, GamepadButton::East),
+                button(Action::Interact, GamepadButton::West),
+                button(Action::Menu, GamepadButton::Start),
+            ],
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, EguiProbe)]
+#[serde(default)]
 pub struct ReplaySettings {
     pub replay_folder: String,
     pub record_replay: bool,
 }
 
-// Helper used by egui-probe derive to render ControlSettings
-// We implement a custom probe for the Control enum below so each control field
-// will render a ComboBox selector when probed.
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        ReplaySettings {
+            replay_folder: "replays".to_string(),
+            record_replay: false,
+        }
+    }
+}
 
-impl EguiProbe for Control {
-    fn probe(&mut self, ui: &mut egui::Ui, _style: &egui_probe::Style) -> egui::Response {
-        let mut current = match self {
-            Control::Key(k) => format!("Key::{:?}", k),
-            Control::Mouse(b) => format!("Mouse::{:?}", b),
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let resp = ComboBox::from_label("")
-            .selected_text(current.clone())
-            .show_ui(ui, |ui| {
-                for opt in control_variants().into_iter() {
-                    if ui.selectable_label(opt == current, opt.clone()).clicked() {
-                        current = opt.clone();
-                    }
-                }
-            })
-            .response
-            .clone();
-
-        if let Some(parsed) = parse_control(&current) {
-            *self = parsed;
-        }
-
-        resp
-    }
-}
-
-fn control_variants() -> Vec<String> {
-    let mut v = vec![
-        "Mouse::Left".to_string(),
-        "Mouse::Middle".to_string(),
-        "Mouse::Right".to_string(),
-    ];
-    let keys = [
-        "Escape",
-        "Tab",
-        "Enter",
-        "Space",
-        "ArrowUp",
-        "ArrowDown",
-        "ArrowLeft",
-        "ArrowRight",
-    ];
-    for k in keys.iter() {
-        v.push(format!("Key::{}", k));
-    }
-    v
-}
-
-fn parse_control(s: &str) -> Option<Control> {
-    if s.starts_with("Mouse::") {
-        match &s[7..] {
-            "Left" => Some(Control::Mouse(winit::event::MouseButton::Left)),
-            "Middle" => Some(Control::Mouse(winit::event::MouseButton::Middle)),
-            "Right" => Some(Control::Mouse(winit::event::MouseButton::Right)),
-            _ => None,
-        }
-    } else if s.starts_with("Key::") {
-        match &s[5..] {
-            "Escape" => Some(Control::Key(winit::keyboard::KeyCode::Escape)),
-            "Tab" => Some(Control::Key(winit::keyboard::KeyCode::Tab)),
-            "Enter" => Some(Control::Key(winit::keyboard::KeyCode::Enter)),
-            "Space" => Some(Control::Key(winit::keyboard::KeyCode::Space)),
-            "ArrowUp" => Some(Control::Key(winit::keyboard::KeyCode::ArrowUp)),
-            "ArrowDown" => Some(Control::Key(winit::keyboard::KeyCode::ArrowDown)),
-            "ArrowLeft" => Some(Control::Key(winit::keyboard::KeyCode::ArrowLeft)),
-            "ArrowRight" => Some(Control::Key(winit::keyboard::KeyCode::ArrowRight)),
-            _ => None,
-        }
-    } else {
-        None
+    #[test]
+    fn legacy_fixed_center_is_ignored_without_warnings() {
+        let file = parse_settings_file(
+            "create_lobby_settings:\n  player_count: 3\n  fixed_center: false\npreset_settings:\n- name: Test\n  fixed_center: true\n",
+        )
+        .unwrap();
+        let (settings, warnings) = Settings::from_value(file);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(settings.create_lobby_settings.player_count, 3);
+        assert_eq!(settings.preset_settings[0].name, "Test");
+    }
+
+    #[test]
+    fn single_sensitivity_sets_yaw_and_pitch() {
+        let file = parse_settings_file("movement_controls:\n  sensitivity: 0.02\n").unwrap();
+        let (settings, warnings) = Settings::from_value(file);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(settings.movement_controls.yaw_sensitivity, 0.02);
+        assert_eq!(settings.movement_controls.pitch_sensitivity, 0.02);
+    }
+
+    #[test]
+    fn unknown_keys_are_still_reported() {
+        let file = parse_settings_file("create_lobby_settings:\n  fixed_centre: false\n").unwrap();
+        let (_, warnings) = Settings::from_value(file);
+        assert_eq!(
+            warnings,
+            vec!["Unknown setting create_lobby_settings.fixed_centre".to_string()]
+        );
+    }
+
+    fn load(yaml: &str) -> (Settings, Vec<String>) {
+        Settings::from_value(parse_settings_file(yaml).unwrap())
+    }
+
+    #[test]
+    fn invalid_values_are_reset_on_their_own() {
+        let (settings, warnings) =
+            load("gamepad_settings:\n  deadzone: fast\n  look_speed: 123.0\n");
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0]
+                .starts_with("Invalid setting gamepad_settings.deadzone, using the default:"),
+            "{}",
+            warnings[0]
+        );
+        assert_eq!(
+            settings.gamepad_settings.deadzone,
+            GamepadSettings::default().deadzone
+        );
+        assert_eq!(settings.gamepad_settings.look_speed, 123.0);
+    }
+
+    #[test]
+    fn malformed_controls_are_reset() {
+        let (settings, warnings) = load(
+            "movement_controls:\n  jump: !Key Spacebar\n  left: !Keyboard KeyQ\n  crouch: !Key KeyC\n",
+        );
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(
+            warnings[0].starts_with("Invalid setting movement_controls.jump, using the default:")
+        );
+        assert!(
+            warnings[1].starts_with("Invalid setting movement_controls.left, using the default:")
+        );
+        assert_eq!(
+            settings.movement_controls.jump,
+            Control::Key(KeyCode::Space)
+        );
+        assert_eq!(settings.movement_controls.left, Control::Key(KeyCode::KeyA));
+        assert_eq!(
+            settings.movement_controls.crouch,
+            Control::Key(KeyCode::KeyC)
+        );
+    }
+
+    #[test]
+    fn out_of_range_values_use_their_defaults() {
+        let (settings, warnings) = load(
+            "movement_controls:\n  yaw_sensitivity: -1.0\npreset_settings:\n- name: Test\n  delta_time: 0.0\n",
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "movement_controls.yaw_sensitivity must be greater than 0, using 0.01".to_string(),
+                "preset_settings[0].delta_time must be greater than 0, using 0.02".to_string(),
+            ]
+        );
+        assert_eq!(settings.movement_controls.yaw_sensitivity, 0.01);
+        assert_eq!(settings.preset_settings[0].delta_time, 0.02);
+    }
+
+    #[test]
+    fn misspelled_keys_are_reported() {
+        let (settings, warnings) = load("movement_controls:\n  yaw_sensitivty: 0.5\n");
+        assert_eq!(
+            warnings,
+            vec!["Unknown setting movement_controls.yaw_sensitivty".to_string()]
+        );
+        assert_eq!(settings.movement_controls.yaw_sensitivity, 0.01);
+    }
+
+    #[test]
+    fn unreadable_files_fall_back_to_the_defaults() {
+        assert!(parse_settings_file("movement_controls: [unclosed").is_err());
+        assert!(parse_settings_file("- not\n- a mapping\n").is_err());
+        assert!(parse_settings_file("").is_ok());
+
+        let (settings, warnings) = Settings::from_value(Value::String("settings".to_string()));
+        assert!(
+            warnings
+                .iter()
+                .any(|warning| warning.starts_with("Failed to load settings, using the defaults")),
+            "{:?}",
+            warnings
+        );
+        assert_eq!(settings.card_file, Settings::default().card_file);
     }
 }
@@ -115,6 +115,7 @@ impl egui_probe::EguiProbe for GameModeSettings {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, EguiProbe)]
+#[serde(default)]
 pub struct GameSettings {
     pub name: String,
     pub delta_time: f32,
@@ -134,14 +135,14 @@ impl Default for GameSettings {
     fn default() -> Self {
         GameSettings {
             name: String::new(),
-            delta_time: 0.0,
+            delta_time: 0.02,
             is_remote: false,
-            rollback_buffer_size: 0,
+            rollback_buffer_size: 2,
             player_count: 1,
             render_size: Vector3::new(64, 64, 64),
-            max_loaded_chunks: 0,
-            max_worldgen_rate: 0,
-            max_update_rate: 0,
+            max_loaded_chunks: 2048,
+            max_worldgen_rate: 128,
+            max_update_rate: 128,
             world_gen: WorldGenSettings::default(),
             game_mode: GameModeSettings::default(),
         }
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card_system::{
+    BaseCard, DirectionCard, ReferencedStatusEffect, ReferencedStatusEffectType,
+    ReferencedStatusEffects, SimpleStatusEffectType, StatusEffect,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum StackingPolicy {
+    /// Every application is tracked separately with its own timer and the stacks are summed
+    Additive,
+    /// Reapplying an identical effect restarts its timer
+    RefreshDuration,
+    /// Reapplying keeps a single instance with the longest remaining time
+    Max,
+}
+
+impl ReferencedStatusEffectType {
+    pub fn get_stacking_policy(&self) -> StackingPolicy {
+        match self {
+            ReferencedStatusEffectType::Speed => StackingPolicy::Additive,
+            ReferencedStatusEffectType::DamageOverTime => StackingPolicy::Additive,
+            ReferencedStatusEffectType::IncreaseDamageTaken => StackingPolicy::Additive,
+            ReferencedStatusEffectType::IncreaseGravity => StackingPolicy::Additive,
+            ReferencedStatusEffectType::Overheal => StackingPolicy::Additive,
+            ReferencedStatusEffectType::Grow => StackingPolicy::Additive,
+            ReferencedStatusEffectType::IncreaseMaxHealth => StackingPolicy::Additive,
+            ReferencedStatusEffectType::Invincibility => StackingPolicy::Max,
+            ReferencedStatusEffectType::Trapped => StackingPolicy::Max,
+            ReferencedStatusEffectType::Lockout => StackingPolicy::Max,
+            ReferencedStatusEffectType::OnHit => StackingPolicy::RefreshDuration,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AppliedStatusEffect {
+    pub effect: ReferencedStatusEffect,
+    pub time_left: f32,
+}
+
+/// The status effects currently applied to a single player.
+/// Effects are kept in application order and only advanced by `tick` so that every client simulating
+/// the same inputs ends up with the same state.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct StatusEffectContainer {
+    effects: Vec<AppliedStatusEffect>,
+    /// Effects of the deck's passive, which never run out and can't be cleansed
+    passive: Vec<ReferencedStatusEffect>,
+}
+
+impl StatusEffectContainer {
+    /// The timed effects, passive effects are left out
+    pub fn iter(&self) -> impl Iterator<Item = &AppliedStatusEffect> {
+        self.effects.iter()
+    }
+
+    pub fn set_passive(&mut self, passive: Vec<ReferencedStatusEffect>) {
+        self.passive = passive;
+    }
+
+    fn iter_all(&self) -> impl Iterator<Item = &ReferencedStatusEffect> {
+        self.passive
+            .iter()
+            .chain(self.effects.iter().map(|effect| &effect.effect))
+    }
+
+    pub fn apply(&mut self, status_effects: &ReferencedStatusEffects) {
+        let duration = status_effects.duration as f32 * BaseCard::EFFECT_LENGTH_SCALE;
+        for effect in status_effects.effects.iter() {
+            self.apply_effect(effect.clone(), duration);
+        }
+    }
+
+    pub fn apply_effect(&mut self, effect: ReferencedStatusEffect, duration: f32) {
+        if duration <= 0.0 {
+            return;
+        }
+        match effect.get_type().get_stacking_policy() {
+            StackingPolicy::Additive => {}
+            StackingPolicy::RefreshDuration => {
+                if let Some(existing) = self.effects.iter_mut().find(|e| e.effect == effect) {
+                    existing.time_left = duration;
+                    return;
+                }
+            }
+            StackingPolicy::Max => {
+                if let Some(existing) = self
+                    .effects
+                    .iter_mut()
+                    .find(|e| e.effect.get_type() == effect.get_type())
+                {
+                    existing.time_left = existing.time_left.max(duration);
+                    return;
+                }
+            }
+        }
+        self.effects.push(AppliedStatusEffect {
+            effect,
+            time_left: duration,
+        });
+    }
+
+    /// Advances every timer by `delta_time` and drops the effects that ran out
+    pub fn tick(&mut self, delta_time: f32) {
+        for effect in self.effects.iter_mut() {
+            effect.time_left -= delta_time;
+        }
+        self.effects.retain(|effect| effect.time_left > 0.0);
+    }
+
+    /// Removes every harmful timed effect
+    pub fn cleanse(&mut self) {
+        self.effects.retain(|effect| !effect.effect.is_harmful());
+    }
+
+    /// Removes the timed effects, the passive stays
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn has_status(&self, effect_type: ReferencedStatusEffectType) -> bool {
+        self.iter_all()
+            .any(|effect| effect.get_type() == effect_type)
+    }
+
+    fn get_stacks(&self, effect_type: SimpleStatusEffectType) -> i32 {
+        self.iter_all()
+            .map(|effect| match (effect, &effect_type) {
+                (ReferencedStatusEffect::Speed(stacks), SimpleStatusEffectType::Speed) => *stacks,
+                (
+                    ReferencedStatusEffect::DamageOverTime(stacks),
+                    SimpleStatusEffectType::DamageOverTime,
+                ) => *stacks,
+                (
+                    ReferencedStatusEffect::IncreaseDamageTaken(stacks),
+                    SimpleStatusEffectType::IncreaseDamageTaken,
+                ) => *stacks,
+                (ReferencedStatusEffect::Grow(stacks), SimpleStatusEffectType::Grow) => *stacks,
+                (
+                    ReferencedStatusEffect::IncreaseMaxHealth(stacks),
+                    SimpleStatusEffectType::IncreaseMaxHealth,
+                ) => *stacks,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn get_effect_value(&self, effect_type: SimpleStatusEffectType) -> f32 {
+        let stacks = self.get_stacks(effect_type.clone());
+        StatusEffect::SimpleStatusEffect(effect_type, stacks).get_effect_value()
+    }
+
+    pub fn get_speed_multiplier(&self) -> f32 {
+        if self.is_trapped() {
+            return 0.0;
+        }
+        self.get_effect_value(SimpleStatusEffectType::Speed)
+    }
+
+    pub fn get_damage_taken_multiplier(&self) -> f32 {
+        self.get_effect_value(SimpleStatusEffectType::IncreaseDamageTaken)
+    }
+
+    pub fn get_size_multiplier(&self) -> f32 {
+        self.get_effect_value(SimpleStatusEffectType::Grow)
+    }
+
+    /// Damage per second, negative values heal
+    pub fn get_damage_over_time(&self) -> f32 {
+        self.get_effect_value(SimpleStatusEffectType::DamageOverTime)
+    }
+
+    pub fn get_max_health_bonus(&self) -> f32 {
+        self.get_effect_value(SimpleStatusEffectType::IncreaseMaxHealth)
+    }
+
+    /// Extra gravity along a direction, in multiples of the normal gravity.
+    /// `update_player` turns these into a vector since only it knows where the directions point.
+    pub fn get_gravity_change(&self, direction: DirectionCard) -> f32 {
+        self.iter_all()
+            .filter_map(|effect| match effect {
+                ReferencedStatusEffect::IncreaseGravity(effect_direction, stacks)
+                    if *effect_direction == direction =>
+                {
+                    Some(
+                        StatusEffect::SimpleStatusEffect(
+                            SimpleStatusEffectType::IncreaseGravity(direction.clone()),
+                            *stacks,
+                        )
+                        .get_effect_value(),
+                    )
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    pub fn is_invincible(&self) -> bool {
+        self.has_status(ReferencedStatusEffectType::Invincibility)
+    }
+
+    pub fn is_trapped(&self) -> bool {
+        self.has_status(ReferencedStatusEffectType::Trapped)
+    }
+
+    pub fn is_locked_out(&self) -> bool {
+        self.has_status(ReferencedStatusEffectType::Lockout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(effects: Vec<ReferencedStatusEffect>, duration: u32) -> ReferencedStatusEffects {
+        ReferencedStatusEffects { duration, effects }
+    }
+
+    #[test]
+    fn additive_effects_sum_their_stacks() {
+        let mut container = StatusEffectContainer::default();
+        container.apply_effect(ReferencedStatusEffect::Speed(1), 1.0);
+        container.apply_effect(ReferencedStatusEffect::Speed(2), 2.0);
+        assert_eq!(container.iter().count(), 2);
+        assert_eq!(container.get_speed_multiplier(), 1.25f32.powi(3));
+
+        container.tick(1.5);
+        assert_eq!(container.get_speed_multiplier(), 1.25f32.powi(2));
+    }
+
+    #[test]
+    fn refreshing_restarts_the_timer() {
+        let mut container = StatusEffectContainer::default();
+        let effect = ReferencedStatusEffect::OnHit(Default::default());
+        container.apply_effect(effect.clone(), 2.0);
+        container.tick(1.5);
+        container.apply_effect(effect, 2.0);
+        let time_left: Vec<f32> = container.iter().map(|e| e.time_left).collect();
+        assert_eq!(time_left, vec![2.0]);
+    }
+
+    #[test]
+    fn max_keeps_the_longest_duration() {
+        let mut container = StatusEffectContainer::default();
+        container.apply_effect(ReferencedStatusEffect::Invincibility, 3.0);
+        container.apply_effect(ReferencedStatusEffect::Invincibility, 1.0);
+        let time_left: Vec<f32> = container.iter().map(|e| e.time_left).collect();
+        assert_eq!(time_left, vec![3.0]);
+    }
+
+    #[test]
+    fn effects_expire_after_their_duration() {
+        let mut container = StatusEffectContainer::default();
+        container.apply(&applied(vec![ReferencedStatusEffect::Trapped], 2));
+        let duration = 2.0 * BaseCard::EFFECT_LENGTH_SCALE;
+        for _ in 0..9 {
+            container.tick(duration / 10.0);
+        }
+        assert!(container.is_trapped());
+        container.tick(duration / 10.0 + 0.001);
+        assert!(!container.is_trapped());
+    }
+
+    #[test]
+    fn cleanse_removes_only_harmful_timed_effects() {
+        let mut container = StatusEffectContainer::default();
+        container.set_passive(vec![ReferencedStatusEffect::IncreaseDamageTaken(1)]);
+        container.apply(&applied(
+            vec![
+                ReferencedStatusEffect::Speed(-2),
+                ReferencedStatusEffect::Speed(1),
+                ReferencedStatusEffect::Lockout,
+            ],
+            4,
+        ));
+        container.cleanse();
+        let remaining: Vec<_> = container.iter().map(|e| e.effect.clone()).collect();
+        assert_eq!(remaining, vec![ReferencedStatusEffect::Speed(1)]);
+        assert!(!container.is_locked_out());
+        assert_eq!(container.get_damage_taken_multiplier(), 1.25);
+    }
+
+    #[test]
+    fn trapped_players_cannot_move() {
+        let mut container = StatusEffectContainer::default();
+        container.apply_effect(ReferencedStatusEffect::Speed(4), 1.0);
+        container.apply_effect(ReferencedStatusEffect::Trapped, 1.0);
+        assert_eq!(container.get_speed_multiplier(), 0.0);
+    }
+
+    #[test]
+    fn gravity_changes_are_split_by_direction() {
+        let mut container = StatusEffectContainer::default();
+        container.set_passive(vec![ReferencedStatusEffect::IncreaseGravity(
+            DirectionCard::Up,
+            2,
+        )]);
+        container.apply_effect(
+            ReferencedStatusEffect::IncreaseGravity(DirectionCard::Up, -1),
+            1.0,
+        );
+        container.apply_effect(
+            ReferencedStatusEffect::IncreaseGravity(DirectionCard::Forward, 3),
+            1.0,
+        );
+        assert_eq!(container.get_gravity_change(DirectionCard::Up), 0.5);
+        assert_eq!(container.get_gravity_change(DirectionCard::Forward), 1.5);
+        assert_eq!(container.get_gravity_change(DirectionCard::Movement), 0.0);
+    }
+}
@@ -0,0 +1,396 @@
+use std::sync::Arc;
+
+use cgmath::Vector2;
+use egui::{Color32, Id, Rect, Sense, Shape, Slider, Stroke, Ui, emath::RectTransform, pos2, vec2};
+
+use crate::card_system::{BaseCard, CardManager, ReferencedBaseCard, ReferencedBaseCardType};
+
+/// The preview assumes flat ground this far below the point the ability is cast from
+const PREVIEW_CAST_HEIGHT: f32 = 1.5;
+const STEPS_PER_PROJECTILE: usize = 300;
+/// Duplication grows exponentially, so casting stops once this many projectiles have been simulated
+const MAX_PREVIEW_PROJECTILES: usize = 64;
+const MAX_PREVIEW_SPAWNS: usize = 256;
+const MAX_PREVIEW_DEPTH: u32 = 4;
+const PREVIEW_SIZE: egui::Vec2 = vec2(320.0, 140.0);
+const DEPTH_COLORS: [Color32; 4] = [
+    Color32::WHITE,
+    Color32::LIGHT_BLUE,
+    Color32::LIGHT_GREEN,
+    Color32::LIGHT_YELLOW,
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum SpawnTrigger {
+    OnHit,
+    OnExpiry,
+    Trail,
+}
+
+impl SpawnTrigger {
+    fn get_name(&self) -> &'static str {
+        match self {
+            SpawnTrigger::OnHit => "On hit",
+            SpawnTrigger::OnExpiry => "On expiry",
+            SpawnTrigger::Trail => "Trail",
+        }
+    }
+
+    fn get_color(&self) -> Color32 {
+        match self {
+            SpawnTrigger::OnHit => Color32::RED,
+            SpawnTrigger::OnExpiry => Color32::ORANGE,
+            SpawnTrigger::Trail => Color32::GRAY,
+        }
+    }
+}
+
+/// Positions are in a side view where x is the distance forward and y is the height above the cast point
+struct ProjectilePath {
+    points: Vec<(f32, Vector2<f32>)>,
+    depth: u32,
+}
+
+struct ChildSpawn {
+    pos: Vector2<f32>,
+    time: f32,
+    trigger: SpawnTrigger,
+    card_type: ReferencedBaseCardType,
+}
+
+struct SpreadCone {
+    origin: Vector2<f32>,
+    angle: f32,
+    half_angle: f32,
+}
+
+/// Runs a card on the CPU in a flat, empty world to show where its projectiles go
+#[derive(Default)]
+struct TrajectoryPreview {
+    paths: Vec<ProjectilePath>,
+    spawns: Vec<ChildSpawn>,
+    cones: Vec<SpreadCone>,
+}
+
+impl TrajectoryPreview {
+    fn simulate(card: &BaseCard) -> Self {
+        let mut card_manager = CardManager::default();
+        let card = card_manager.register_base_card(card.clone());
+        let mut preview = TrajectoryPreview::default();
+        preview.cast(&card_manager, card, Vector2::new(0.0, 0.0), 0.0, 0.0, 0);
+        preview
+    }
+
+    fn cast(
+        &mut self,
+        card_manager: &CardManager,
+        card: ReferencedBaseCard,
+        pos: Vector2<f32>,
+        angle: f32,
+        time: f32,
+        depth: u32,
+    ) {
+        if depth > MAX_PREVIEW_DEPTH || self.paths.len() >= MAX_PREVIEW_PROJECTILES {
+            return;
+        }
+        match card.card_type {
+            ReferencedBaseCardType::Projectile => {
+                self.fly(card_manager, card.card_idx, pos, angle, time, depth)
+            }
+            ReferencedBaseCardType::MultiCast => {
+                let multicast = &card_manager.referenced_multicasts[card.card_idx];
+                let spread = multicast.get_spread_angle();
+                if spread > 0.0 {
+                    self.cones.push(SpreadCone {
+                        origin: pos,
+                        angle,
+                        half_angle: spread,
+                    });
+                }
+                // Only the pitch of each duplicate shows in a side view
+                for offset in multicast.get_duplicate_offsets() {
+                    for sub_card in multicast.sub_cards.iter() {
+                        self.cast(card_manager, *sub_card, pos, angle + offset.y, time, depth);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fly(
+        &mut self,
+        card_manager: &CardManager,
+        proj_idx: usize,
+        start: Vector2<f32>,
+        angle: f32,
+        start_time: f32,
+        depth: u32,
+    ) {
+        let stats = card_manager.get_referenced_proj(proj_idx);
+        let dt = stats.lifetime / STEPS_PER_PROJECTILE as f32;
+        let mut pos = start;
+        let mut vel = Vector2::new(angle.cos(), angle.sin()) * stats.speed;
+        let mut time = start_time;
+        let mut points = vec![(time, pos)];
+        let mut trail_timers: Vec<f32> =
+            stats.trail.iter().map(|(interval, _)| *interval).collect();
+        let mut children: Vec<(ReferencedBaseCard, Vector2<f32>, f32, f32, SpawnTrigger)> = vec![];
+        let mut expired = true;
+        for _ in 0..STEPS_PER_PROJECTILE {
+            time += dt;
+            vel.y -= stats.gravity * dt;
+            let next = pos + vel * dt;
+            if next.y < -PREVIEW_CAST_HEIGHT {
+                let hit_fraction = (pos.y + PREVIEW_CAST_HEIGHT) / (pos.y - next.y);
+                let hit_pos = pos + (next - pos) * hit_fraction;
+                points.push((time, hit_pos));
+                for card in stats.on_hit.iter() {
+                    children.push((
+                        *card,
+                        hit_pos,
+                        vel.y.atan2(vel.x),
+                        time,
+                        SpawnTrigger::OnHit,
+                    ));
+                }
+                if !stats.wall_bounce {
+                    expired = false;
+                    break;
+                }
+                vel.y = -vel.y;
+                pos = hit_pos;
+                continue;
+            }
+            pos = next;
+            points.push((time, pos));
+            for ((interval, card), timer) in stats.trail.iter().zip(trail_timers.iter_mut()) {
+                *timer -= dt;
+                while *timer <= 0.0 && *interval > 0.0 {
+                    *timer += interval;
+                    children.push((*card, pos, vel.y.atan2(vel.x), time, SpawnTrigger::Trail));
+                }
+            }
+        }
+        if expired {
+            for card in stats.on_expiry.iter() {
+                children.push((*card, pos, vel.y.atan2(vel.x), time, SpawnTrigger::OnExpiry));
+            }
+        }
+        self.paths.push(ProjectilePath { points, depth });
+        for (card, pos, angle, time, trigger) in children {
+            if self.spawns.len() >= MAX_PREVIEW_SPAWNS {
+                break;
+            }
+            self.spawns.push(ChildSpawn {
+                pos,
+                time,
+                trigger,
+                card_type: card.card_type,
+            });
+            self.cast(card_manager, card, pos, angle, time, depth + 1);
+        }
+    }
+
+    fn get_duration(&self) -> f32 {
+        self.paths
+            .iter()
+            .filter_map(|path| path.points.last())
+            .map(|(time, _)| *time)
+            .fold(0.0, f32::max)
+    }
+
+    /// Furthest horizontal distance reached by the projectiles cast directly by the card.
+    /// Unlike `ReferencedProjectile::get_range` this includes gravity and stopping on the ground.
+    fn get_furthest_distance(&self) -> f32 {
+        self.paths
+            .iter()
+            .filter(|path| path.depth == 0)
+            .flat_map(|path| path.points.iter())
+            .map(|(_, pos)| pos.x.abs())
+            .fold(0.0, f32::max)
+    }
+
+    fn get_bounds(&self) -> Rect {
+        let mut bounds = Rect::from_min_max(pos2(0.0, -PREVIEW_CAST_HEIGHT), pos2(1.0, 1.0));
+        for (_, pos) in self.paths.iter().flat_map(|path| path.points.iter()) {
+            bounds.extend_with(pos2(pos.x, pos.y));
+        }
+        bounds.expand(0.05 * bounds.width().max(bounds.height()))
+    }
+}
+
+/// Side view plot of where the projectiles of a card travel, with a time slider for the spawned child cards
+pub fn draw_trajectory_preview(ui: &mut Ui, card: &BaseCard, id: Id) {
+    if card.has_placeholder() {
+        ui.label("Finish the card to preview it");
+        return;
+    }
+    // Simulating is too slow to redo every frame, so it is only rerun when the card changes
+    let preview_id = id.with("preview");
+    let card_key = format!("{:?}", card);
+    let preview =
+        match ui.data(|data| data.get_temp::<(String, Arc<TrajectoryPreview>)>(preview_id)) {
+            Some((key, preview)) if key == card_key => preview,
+            _ => {
+                let preview = Arc::new(TrajectoryPreview::simulate(card));
+                ui.data_mut(|data| data.insert_temp(preview_id, (card_key, preview.clone())));
+                preview
+            }
+        };
+    if preview.paths.is_empty() {
+        ui.label("No projectiles to preview");
+        return;
+    }
+    let duration = preview.get_duration();
+    let time_id = id.with("time");
+    let mut time = ui
+        .data(|data| data.get_temp::<f32>(time_id))
+        .unwrap_or(duration)
+        .min(duration);
+
+    let (response, painter) = ui.allocate_painter(PREVIEW_SIZE, Sense::hover());
+    let bounds = preview.get_bounds();
+    // Flip y so up is up
+    let to_screen = RectTransform::from_to(
+        Rect::from_min_max(
+            pos2(bounds.min.x, bounds.max.y),
+            pos2(bounds.max.x, bounds.min.y),
+        ),
+        response.rect,
+    );
+    let to_pos2 = |pos: Vector2<f32>| to_screen.transform_pos(pos2(pos.x, pos.y));
+    painter.rect_filled(response.rect, 2.0, Color32::from_gray(20));
+    painter.line_segment(
+        [
+            to_screen.transform_pos(pos2(bounds.min.x, -PREVIEW_CAST_HEIGHT)),
+            to_screen.transform_pos(pos2(bounds.max.x, -PREVIEW_CAST_HEIGHT)),
+        ],
+        Stroke::new(1.0, Color32::DARK_GREEN),
+    );
+
+    let cone_length = 0.15 * bounds.width();
+    for cone in preview.cones.iter() {
+        let edge = |angle: f32| cone.origin + Vector2::new(angle.cos(), angle.sin()) * cone_length;
+        painter.add(Shape::convex_polygon(
+            vec![
+                to_pos2(cone.origin),
+                to_pos2(edge(cone.angle - cone.half_angle)),
+                to_pos2(edge(cone.angle + cone.half_angle)),
+            ],
+            Color32::from_rgba_unmultiplied(255, 255, 0, 30),
+            Stroke::NONE,
+        ));
+    }
+
+    for path in preview.paths.iter() {
+        let color = DEPTH_COLORS[(path.depth as usize).min(DEPTH_COLORS.len() - 1)];
+        let flown: Vec<_> = path
+            .points
+            .iter()
+            .take_while(|(point_time, _)| *point_time <= time)
+            .map(|(_, pos)| to_pos2(*pos))
+            .collect();
+        if let Some(current) = flown.last() {
+            let has_landed = path
+                .points
+                .last()
+                .is_some_and(|(end_time, _)| *end_time <= time);
+            if !has_landed {
+                painter.circle_filled(*current, 2.5, color);
+            }
+        }
+        painter.add(Shape::line(flown, Stroke::new(1.0, color)));
+    }
+
+    let hover_pos = response.hover_pos();
+    let mut hovered_spawn = None;
+    for spawn in preview.spawns.iter() {
+        let center = to_pos2(spawn.pos);
+        let color = spawn.trigger.get_color();
+        if spawn.time <= time {
+            painter.circle_filled(center, 3.0, color);
+        } else {
+            painter.circle_stroke(center, 3.0, Stroke::new(1.0, color));
+        }
+        if hover_pos.is_some_and(|hover_pos| hover_pos.distance(center) < 6.0) {
+            hovered_spawn = Some(spawn);
+        }
+    }
+    if let Some(spawn) = hovered_spawn {
+        response.on_hover_text(format!(
+            "{} spawns {:?} at {:.2}s, {:.1} forward, {:.1} up",
+            spawn.trigger.get_name(),
+            spawn.card_type,
+            spawn.time,
+            spawn.pos.x,
+            spawn.pos.y
+        ));
+    }
+
+    ui.label(format!(
+        "Furthest: {:.1}  Flight time: {:.2}s  Projectiles: {}",
+        preview.get_furthest_distance(),
+        duration,
+        preview.paths.len()
+    ));
+    if duration > 0.0 {
+        ui.add(
+            Slider::new(&mut time, 0.0..=duration)
+                .text("Time")
+                .suffix("s"),
+        );
+    }
+    ui.data_mut(|data| data.insert_temp(time_id, time));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_system::{MultiCastModifier, ProjectileModifier, SimpleProjectileModifierType};
+
+    fn gravity(amount: i32) -> ProjectileModifier {
+        ProjectileModifier::SimpleModify(SimpleProjectileModifierType::Gravity, amount)
+    }
+
+    #[test]
+    fn range_without_gravity_matches_the_card_range() {
+        let card = BaseCard::Projectile(vec![]);
+        let mut card_manager = CardManager::default();
+        let proj_idx = card_manager.register_base_card(card.clone()).card_idx;
+        let range = card_manager.get_referenced_proj(proj_idx).get_range();
+
+        let preview = TrajectoryPreview::simulate(&card);
+        assert_eq!(preview.paths.len(), 1);
+        assert!((preview.get_furthest_distance() - range).abs() < range * 1e-3);
+        assert!(preview.paths[0].points.iter().all(|(_, pos)| pos.y == 0.0));
+    }
+
+    #[test]
+    fn gravity_bends_the_path_downward() {
+        let preview = TrajectoryPreview::simulate(&BaseCard::Projectile(vec![gravity(5)]));
+        let points = &preview.paths[0].points;
+        assert!(points.windows(2).all(|pair| pair[1].1.y < pair[0].1.y));
+        // The projectile stops once it reaches the ground
+        assert!((points.last().unwrap().1.y + PREVIEW_CAST_HEIGHT).abs() < 1e-4);
+        let straight = TrajectoryPreview::simulate(&BaseCard::Projectile(vec![]));
+        assert!(preview.get_furthest_distance() < straight.get_furthest_distance());
+    }
+
+    #[test]
+    fn multicast_casts_every_duplicate() {
+        let preview = TrajectoryPreview::simulate(&BaseCard::MultiCast(
+            vec![
+                BaseCard::Projectile(vec![]),
+                BaseCard::Projectile(vec![gravity(1)]),
+            ],
+            vec![
+                MultiCastModifier::Spread(3),
+                MultiCastModifier::Duplication(2),
+            ],
+        ));
+        assert_eq!(preview.paths.len(), 8);
+        assert!(preview.paths.iter().all(|path| path.depth == 0));
+        assert_eq!(preview.cones.len(), 1);
+    }
+}
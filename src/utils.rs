@@ -3,116 +3,228 @@ use std::{
 };
 use winit::keyboard::KeyCode;
 
-pub fn translate_egui_key_code(key: egui::Key) -> KeyCode {
+/// Physical key for an egui key, using the US layout egui names physical keys by.
+/// Symbols that are only typed with Shift, like `:` or `{`, have no key of their own and give `None`.
+/// `Plus` is only reported for the numpad plus key.
+pub fn translate_egui_key_code(key: egui::Key) -> Option<KeyCode> {
     match key {
-        egui::Key::ArrowDown => KeyCode::ArrowDown,
-        egui::Key::ArrowLeft => KeyCode::ArrowLeft,
-        egui::Key::ArrowRight => KeyCode::ArrowRight,
-        egui::Key::ArrowUp => KeyCode::ArrowUp,
-        egui::Key::Escape => KeyCode::Escape,
-        egui::Key::Tab => KeyCode::Tab,
-        egui::Key::Backspace => KeyCode::Backspace,
-        egui::Key::Enter => KeyCode::Enter,
-        egui::Key::Space => KeyCode::Space,
-        egui::Key::Insert => KeyCode::Insert,
-        egui::Key::Delete => KeyCode::Delete,
-        egui::Key::Home => KeyCode::Home,
-        egui::Key::End => KeyCode::End,
-        egui::Key::PageUp => KeyCode::PageUp,
-        egui::Key::PageDown => KeyCode::PageDown,
-        egui::Key::Minus => KeyCode::Minus,
-        egui::Key::Num0 => KeyCode::Numpad0,
-        egui::Key::Num1 => KeyCode::Numpad1,
-        egui::Key::Num2 => KeyCode::Numpad2,
-        egui::Key::Num3 => KeyCode::Numpad3,
-        egui::Key::Num4 => KeyCode::Numpad4,
-        egui::Key::Num5 => KeyCode::Numpad5,
-        egui::Key::Num6 => KeyCode::Numpad6,
-        egui::Key::Num7 => KeyCode::Numpad7,
-        egui::Key::Num8 => KeyCode::Numpad8,
-        egui::Key::Num9 => KeyCode::Numpad9,
-        egui::Key::A => KeyCode::KeyA,
-        egui::Key::B => KeyCode::KeyB,
-        egui::Key::C => KeyCode::KeyC,
-        egui::Key::D => KeyCode::KeyD,
-        egui::Key::E => KeyCode::KeyE,
-        egui::Key::F => KeyCode::KeyF,
-        egui::Key::G => KeyCode::KeyG,
-        egui::Key::H => KeyCode::KeyH,
-        egui::Key::I => KeyCode::KeyI,
-        egui::Key::J => KeyCode::KeyJ,
-        egui::Key::K => KeyCode::KeyK,
-        egui::Key::L => KeyCode::KeyL,
-        egui::Key::M => KeyCode::KeyM,
-        egui::Key::N => KeyCode::KeyN,
-        egui::Key::O => KeyCode::KeyO,
-        egui::Key::P => KeyCode::KeyP,
-        egui::Key::Q => KeyCode::KeyQ,
-        egui::Key::R => KeyCode::KeyR,
-        egui::Key::S => KeyCode::KeyS,
-        egui::Key::T => KeyCode::KeyT,
-        egui::Key::U => KeyCode::KeyU,
-        egui::Key::V => KeyCode::KeyV,
-        egui::Key::W => KeyCode::KeyW,
-        egui::Key::X => KeyCode::KeyX,
-        egui::Key::Y => KeyCode::KeyY,
-        egui::Key::Z => KeyCode::KeyZ,
-        egui::Key::F1 => KeyCode::F1,
-        egui::Key::F2 => KeyCode::F2,
-        egui::Key::F3 => KeyCode::F3,
-        egui::Key::F4 => KeyCode::F4,
-        egui::Key::F5 => KeyCode::F5,
-        egui::Key::F6 => KeyCode::F6,
-        egui::Key::F7 => KeyCode::F7,
-        egui::Key::F8 => KeyCode::F8,
-        egui::Key::F9 => KeyCode::F9,
-        egui::Key::F10 => KeyCode::F10,
-        egui::Key::F11 => KeyCode::F11,
-        egui::Key::F12 => KeyCode::F12,
-        egui::Key::F13 => KeyCode::F13,
-        egui::Key::F14 => KeyCode::F14,
-        egui::Key::F15 => KeyCode::F15,
-        egui::Key::F16 => KeyCode::F16,
-        egui::Key::F17 => KeyCode::F17,
-        egui::Key::F18 => KeyCode::F18,
-        egui::Key::F19 => KeyCode::F19,
-        egui::Key::F20 => KeyCode::F20,
-        egui::Key::Copy => KeyCode::Copy,
-        egui::Key::Cut => KeyCode::Cut,
-        egui::Key::Paste => KeyCode::Paste,
-        egui::Key::Colon => todo!(),
-        egui::Key::Comma => KeyCode::Comma,
-        egui::Key::Backslash => KeyCode::Backslash,
-        egui::Key::Slash => KeyCode::Slash,
-        egui::Key::Pipe => todo!(),
-        egui::Key::Questionmark => todo!(),
-        egui::Key::Exclamationmark => todo!(),
-        egui::Key::OpenBracket => todo!(),
-        egui::Key::CloseBracket => todo!(),
-        egui::Key::OpenCurlyBracket => todo!(),
-        egui::Key::CloseCurlyBracket => todo!(),
-        egui::Key::Backtick => todo!(),
-        egui::Key::Period => KeyCode::Period,
-        egui::Key::Plus => todo!(),
-        egui::Key::Equals => todo!(),
-        egui::Key::Semicolon => KeyCode::Semicolon,
-        egui::Key::Quote => KeyCode::Quote,
-        egui::Key::F21 => KeyCode::F21,
-        egui::Key::F22 => KeyCode::F22,
-        egui::Key::F23 => KeyCode::F23,
-        egui::Key::F24 => KeyCode::F24,
-        egui::Key::F25 => KeyCode::F25,
-        egui::Key::F26 => KeyCode::F26,
-        egui::Key::F27 => KeyCode::F27,
-        egui::Key::F28 => KeyCode::F28,
-        egui::Key::F29 => KeyCode::F29,
-        egui::Key::F30 => KeyCode::F30,
-        egui::Key::F31 => KeyCode::F31,
-        egui::Key::F32 => KeyCode::F32,
-        egui::Key::F33 => KeyCode::F33,
-        egui::Key::F34 => KeyCode::F34,
-        egui::Key::F35 => KeyCode::F35,
-        egui::Key::BrowserBack => KeyCode::BrowserBack,
+        egui::Key::ArrowDown => Some(KeyCode::ArrowDown),
+        egui::Key::ArrowLeft => Some(KeyCode::ArrowLeft),
+        egui::Key::ArrowRight => Some(KeyCode::ArrowRight),
+        egui::Key::ArrowUp => Some(KeyCode::ArrowUp),
+        egui::Key::Escape => Some(KeyCode::Escape),
+        egui::Key::Tab => Some(KeyCode::Tab),
+        egui::Key::Backspace => Some(KeyCode::Backspace),
+        egui::Key::Enter => Some(KeyCode::Enter),
+        egui::Key::Space => Some(KeyCode::Space),
+        egui::Key::Insert => Some(KeyCode::Insert),
+        egui::Key::Delete => Some(KeyCode::Delete),
+        egui::Key::Home => Some(KeyCode::Home),
+        egui::Key::End => Some(KeyCode::End),
+        egui::Key::PageUp => Some(KeyCode::PageUp),
+        egui::Key::PageDown => Some(KeyCode::PageDown),
+        egui::Key::Copy => Some(KeyCode::Copy),
+        egui::Key::Cut => Some(KeyCode::Cut),
+        egui::Key::Paste => Some(KeyCode::Paste),
+        egui::Key::Comma => Some(KeyCode::Comma),
+        egui::Key::Backslash => Some(KeyCode::Backslash),
+        egui::Key::Slash => Some(KeyCode::Slash),
+        egui::Key::OpenBracket => Some(KeyCode::BracketLeft),
+        egui::Key::CloseBracket => Some(KeyCode::BracketRight),
+        egui::Key::Backtick => Some(KeyCode::Backquote),
+        egui::Key::Minus => Some(KeyCode::Minus),
+        egui::Key::Period => Some(KeyCode::Period),
+        egui::Key::Plus => Some(KeyCode::NumpadAdd),
+        egui::Key::Equals => Some(KeyCode::Equal),
+        egui::Key::Semicolon => Some(KeyCode::Semicolon),
+        egui::Key::Quote => Some(KeyCode::Quote),
+        egui::Key::Num0 => Some(KeyCode::Digit0),
+        egui::Key::Num1 => Some(KeyCode::Digit1),
+        egui::Key::Num2 => Some(KeyCode::Digit2),
+        egui::Key::Num3 => Some(KeyCode::Digit3),
+        egui::Key::Num4 => Some(KeyCode::Digit4),
+        egui::Key::Num5 => Some(KeyCode::Digit5),
+        egui::Key::Num6 => Some(KeyCode::Digit6),
+        egui::Key::Num7 => Some(KeyCode::Digit7),
+        egui::Key::Num8 => Some(KeyCode::Digit8),
+        egui::Key::Num9 => Some(KeyCode::Digit9),
+        egui::Key::A => Some(KeyCode::KeyA),
+        egui::Key::B => Some(KeyCode::KeyB),
+        egui::Key::C => Some(KeyCode::KeyC),
+        egui::Key::D => Some(KeyCode::KeyD),
+        egui::Key::E => Some(KeyCode::KeyE),
+        egui::Key::F => Some(KeyCode::KeyF),
+        egui::Key::G => Some(KeyCode::KeyG),
+        egui::Key::H => Some(KeyCode::KeyH),
+        egui::Key::I => Some(KeyCode::KeyI),
+        egui::Key::J => Some(KeyCode::KeyJ),
+        egui::Key::K => Some(KeyCode::KeyK),
+        egui::Key::L => Some(KeyCode::KeyL),
+        egui::Key::M => Some(KeyCode::KeyM),
+        egui::Key::N => Some(KeyCode::KeyN),
+        egui::Key::O => Some(KeyCode::KeyO),
+        egui::Key::P => Some(KeyCode::KeyP),
+        egui::Key::Q => Some(KeyCode::KeyQ),
+        egui::Key::R => Some(KeyCode::KeyR),
+        egui::Key::S => Some(KeyCode::KeyS),
+        egui::Key::T => Some(KeyCode::KeyT),
+        egui::Key::U => Some(KeyCode::KeyU),
+        egui::Key::V => Some(KeyCode::KeyV),
+        egui::Key::W => Some(KeyCode::KeyW),
+        egui::Key::X => Some(KeyCode::KeyX),
+        egui::Key::Y => Some(KeyCode::KeyY),
+        egui::Key::Z => Some(KeyCode::KeyZ),
+        egui::Key::F1 => Some(KeyCode::F1),
+        egui::Key::F2 => Some(KeyCode::F2),
+        egui::Key::F3 => Some(KeyCode::F3),
+        egui::Key::F4 => Some(KeyCode::F4),
+        egui::Key::F5 => Some(KeyCode::F5),
+        egui::Key::F6 => Some(KeyCode::F6),
+        egui::Key::F7 => Some(KeyCode::F7),
+        egui::Key::F8 => Some(KeyCode::F8),
+        egui::Key::F9 => Some(KeyCode::F9),
+        egui::Key::F10 => Some(KeyCode::F10),
+        egui::Key::F11 => Some(KeyCode::F11),
+        egui::Key::F12 => Some(KeyCode::F12),
+        egui::Key::F13 => Some(KeyCode::F13),
+        egui::Key::F14 => Some(KeyCode::F14),
+        egui::Key::F15 => Some(KeyCode::F15),
+        egui::Key::F16 => Some(KeyCode::F16),
+        egui::Key::F17 => Some(KeyCode::F17),
+        egui::Key::F18 => Some(KeyCode::F18),
+        egui::Key::F19 => Some(KeyCode::F19),
+        egui::Key::F20 => Some(KeyCode::F20),
+        egui::Key::F21 => Some(KeyCode::F21),
+        egui::Key::F22 => Some(KeyCode::F22),
+        egui::Key::F23 => Some(KeyCode::F23),
+        egui::Key::F24 => Some(KeyCode::F24),
+        egui::Key::F25 => Some(KeyCode::F25),
+        egui::Key::F26 => Some(KeyCode::F26),
+        egui::Key::F27 => Some(KeyCode::F27),
+        egui::Key::F28 => Some(KeyCode::F28),
+        egui::Key::F29 => Some(KeyCode::F29),
+        egui::Key::F30 => Some(KeyCode::F30),
+        egui::Key::F31 => Some(KeyCode::F31),
+        egui::Key::F32 => Some(KeyCode::F32),
+        egui::Key::F33 => Some(KeyCode::F33),
+        egui::Key::F34 => Some(KeyCode::F34),
+        egui::Key::F35 => Some(KeyCode::F35),
+        egui::Key::BrowserBack => Some(KeyCode::BrowserBack),
+        egui::Key::Colon
+        | egui::Key::Pipe
+        | egui::Key::Questionmark
+        | egui::Key::Exclamationmark
+        | egui::Key::OpenCurlyBracket
+        | egui::Key::CloseCurlyBracket => None,
+    }
+}
+
+/// Inverse of `translate_egui_key_code`, keys egui has no name for give `None`
+pub fn translate_key_code_to_egui(key: KeyCode) -> Option<egui::Key> {
+    match key {
+        KeyCode::ArrowDown => Some(egui::Key::ArrowDown),
+        KeyCode::ArrowLeft => Some(egui::Key::ArrowLeft),
+        KeyCode::ArrowRight => Some(egui::Key::ArrowRight),
+        KeyCode::ArrowUp => Some(egui::Key::ArrowUp),
+        KeyCode::Escape => Some(egui::Key::Escape),
+        KeyCode::Tab => Some(egui::Key::Tab),
+        KeyCode::Backspace => Some(egui::Key::Backspace),
+        KeyCode::Enter => Some(egui::Key::Enter),
+        KeyCode::Space => Some(egui::Key::Space),
+        KeyCode::Insert => Some(egui::Key::Insert),
+        KeyCode::Delete => Some(egui::Key::Delete),
+        KeyCode::Home => Some(egui::Key::Home),
+        KeyCode::End => Some(egui::Key::End),
+        KeyCode::PageUp => Some(egui::Key::PageUp),
+        KeyCode::PageDown => Some(egui::Key::PageDown),
+        KeyCode::Copy => Some(egui::Key::Copy),
+        KeyCode::Cut => Some(egui::Key::Cut),
+        KeyCode::Paste => Some(egui::Key::Paste),
+        KeyCode::Comma => Some(egui::Key::Comma),
+        KeyCode::Backslash => Some(egui::Key::Backslash),
+        KeyCode::Slash => Some(egui::Key::Slash),
+        KeyCode::BracketLeft => Some(egui::Key::OpenBracket),
+        KeyCode::BracketRight => Some(egui::Key::CloseBracket),
+        KeyCode::Backquote => Some(egui::Key::Backtick),
+        KeyCode::Minus => Some(egui::Key::Minus),
+        KeyCode::Period => Some(egui::Key::Period),
+        KeyCode::NumpadAdd => Some(egui::Key::Plus),
+        KeyCode::Equal => Some(egui::Key::Equals),
+        KeyCode::Semicolon => Some(egui::Key::Semicolon),
+        KeyCode::Quote => Some(egui::Key::Quote),
+        KeyCode::Digit0 => Some(egui::Key::Num0),
+        KeyCode::Digit1 => Some(egui::Key::Num1),
+        KeyCode::Digit2 => Some(egui::Key::Num2),
+        KeyCode::Digit3 => Some(egui::Key::Num3),
+        KeyCode::Digit4 => Some(egui::Key::Num4),
+        KeyCode::Digit5 => Some(egui::Key::Num5),
+        KeyCode::Digit6 => Some(egui::Key::Num6),
+        KeyCode::Digit7 => Some(egui::Key::Num7),
+        KeyCode::Digit8 => Some(egui::Key::Num8),
+        KeyCode::Digit9 => Some(egui::Key::Num9),
+        KeyCode::KeyA => Some(egui::Key::A),
+        KeyCode::KeyB => Some(egui::Key::B),
+        KeyCode::KeyC => Some(egui::Key::C),
+        KeyCode::KeyD => Some(egui::Key::D),
+        KeyCode::KeyE => Some(egui::Key::E),
+        KeyCode::KeyF => Some(egui::Key::F),
+        KeyCode::KeyG => Some(egui::Key::G),
+        KeyCode::KeyH => Some(egui::Key::H),
+        KeyCode::KeyI => Some(egui::Key::I),
+        KeyCode::KeyJ => Some(egui::Key::J),
+        KeyCode::KeyK => Some(egui::Key::K),
+        KeyCode::KeyL => Some(egui::Key::L),
+        KeyCode::KeyM => Some(egui::Key::M),
+        KeyCode::KeyN => Some(egui::Key::N),
+        KeyCode::KeyO => Some(egui::Key::O),
+        KeyCode::KeyP => Some(egui::Key::P),
+        KeyCode::KeyQ => Some(egui::Key::Q),
+        KeyCode::KeyR => Some(egui::Key::R),
+        KeyCode::KeyS => Some(egui::Key::S),
+        KeyCode::KeyT => Some(egui::Key::T),
+        KeyCode::KeyU => Some(egui::Key::U),
+        KeyCode::KeyV => Some(egui::Key::V),
+        KeyCode::KeyW => Some(egui::Key::W),
+        KeyCode::KeyX => Some(egui::Key::X),
+        KeyCode::KeyY => Some(egui::Key::Y),
+        KeyCode::KeyZ => Some(egui::Key::Z),
+        KeyCode::F1 => Some(egui::Key::F1),
+        KeyCode::F2 => Some(egui::Key::F2),
+        KeyCode::F3 => Some(egui::Key::F3),
+        KeyCode::F4 => Some(egui::Key::F4),
+        KeyCode::F5 => Some(egui::Key::F5),
+        KeyCode::F6 => Some(egui::Key::F6),
+        KeyCode::F7 => Some(egui::Key::F7),
+        KeyCode::F8 => Some(egui::Key::F8),
+        KeyCode::F9 => Some(egui::Key::F9),
+        KeyCode::F10 => Some(egui::Key::F10),
+        KeyCode::F11 => Some(egui::Key::F11),
+        KeyCode::F12 => Some(egui::Key::F12),
+        KeyCode::F13 => Some(egui::Key::F13),
+        KeyCode::F14 => Some(egui::Key::F14),
+        KeyCode::F15 => Some(egui::Key::F15),
+        KeyCode::F16 => Some(egui::Key::F16),
+        KeyCode::F17 => Some(egui::Key::F17),
+        KeyCode::F18 => Some(egui::Key::F18),
+        KeyCode::F19 => Some(egui::Key::F19),
+        KeyCode::F20 => Some(egui::Key::F20),
+        KeyCode::F21 => Some(egui::Key::F21),
+        KeyCode::F22 => Some(egui::Key::F22),
+        KeyCode::F23 => Some(egui::Key::F23),
+        KeyCode::F24 => Some(egui::Key::F24),
+        KeyCode::F25 => Some(egui::Key::F25),
+        KeyCode::F26 => Some(egui::Key::F26),
+        KeyCode::F27 => Some(egui::Key::F27),
+        KeyCode::F28 => Some(egui::Key::F28),
+        KeyCode::F29 => Some(egui::Key::F29),
+        KeyCode::F30 => Some(egui::Key::F30),
+        KeyCode::F31 => Some(egui::Key::F31),
+        KeyCode::F32 => Some(egui::Key::F32),
+        KeyCode::F33 => Some(egui::Key::F33),
+        KeyCode::F34 => Some(egui::Key::F34),
+        KeyCode::F35 => Some(egui::Key::F35),
+        KeyCode::BrowserBack => Some(egui::Key::BrowserBack),
+        _ => None,
     }
 }
 
@@ -126,6 +238,88 @@ pub fn translate_egui_pointer_button(button: egui::PointerButton) -> winit::even
     }
 }
 
+/// Case insensitive subsequence match of `query` in `text`, returning a score where higher is a better match.
+/// Consecutive characters and characters at the start of a word score extra.
+pub fn fuzzy_match_score(query: &str, text: &str) -> Option<u32> {
+    let query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let mut score = 0;
+    let mut query_idx = 0;
+    let mut previous_matched = false;
+    let mut previous_char: Option<char> = None;
+    for c in text.chars() {
+        if query_idx == query.len() {
+            break;
+        }
+        if c.to_lowercase().eq(std::iter::once(query[query_idx])) {
+            score += 1;
+            if previous_matched {
+                score += 2;
+            }
+            let word_start = match previous_char {
+                None => true,
+                Some(previous) => {
+                    !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+                }
+            };
+            if word_start {
+                score += 3;
+            }
+            query_idx += 1;
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_char = Some(c);
+    }
+    (query_idx == query.len()).then_some(score)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineDiff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Line based diff from `old` to `new` using the longest common subsequence of lines
+pub fn diff_lines(old: &str, new: &str) -> Vec<LineDiff> {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    // common_lengths[i][j] is the length of the common subsequence of old_lines[i..] and new_lines[j..]
+    let mut common_lengths = vec![vec![0u32; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            common_lengths[i][j] = if old_lines[i] == new_lines[j] {
+                common_lengths[i + 1][j + 1] + 1
+            } else {
+                common_lengths[i + 1][j].max(common_lengths[i][j + 1])
+            };
+        }
+    }
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() && j < new_lines.len() {
+        if old_lines[i] == new_lines[j] {
+            diff.push(LineDiff::Same(old_lines[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if common_lengths[i + 1][j] >= common_lengths[i][j + 1] {
+            diff.push(LineDiff::Removed(old_lines[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(LineDiff::Added(new_lines[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(old_lines[i..].iter().map(|line| LineDiff::Removed(line.to_string())));
+    diff.extend(new_lines[j..].iter().map(|line| LineDiff::Added(line.to_string())));
+    diff
+}
+
 pub fn recurse_files(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
     let mut buf = vec![];
     let entries = read_dir(path)?;
@@ -145,4 +339,34 @@ pub fn recurse_files(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
     }
 
     Ok(buf)
-}
\ No newline at end of file
+}
+
+/// An empty directory for a test to write files in, `name` has to be unique between tests
+#[cfg(test)]
+pub fn get_test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("slang-voxels-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn egui_keys_round_trip_through_key_codes() {
+        for key in egui::Key::ALL.iter().copied() {
+            let Some(key_code) = translate_egui_key_code(key) else {
+                continue;
+            };
+            assert_eq!(
+                translate_key_code_to_egui(key_code),
+                Some(key),
+                "{:?} translates to {:?}",
+                key,
+                key_code
+            );
+        }
+    }
+}